@@ -0,0 +1,384 @@
+//! Parse, highlight and query benchmarks on generated book chapters.
+//!
+//! ```text
+//! cargo bench --bench grammar                         # run and compare with benches/baseline.json
+//! BENCH_BASELINE=record cargo bench --bench grammar   # run and rewrite the baseline
+//! ```
+//!
+//! After the benchmarks run, each one's median is compared with the committed
+//! baseline; the run fails if any is more than `BENCH_TOLERANCE` (default
+//! 1.5) times slower. Only benchmarks measured in this run are compared, so a
+//! filtered run such as `cargo bench --bench grammar -- parse` works too.
+//! Timings depend on the machine, so record the baseline on the machine that
+//! checks it.
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput};
+use tree_sitter::{InputEdit, Parser, Point, Query, QueryCursor, StreamingIterator, Tree};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
+
+const OUTLINE: &str = include_str!("../languages/quarto/outline.scm");
+const FOLDS: &str = include_str!("../languages/quarto/folds.scm");
+
+const BASELINE: &str = "benches/baseline.json";
+
+/// Document sizes in lines; the larger is a long book chapter.
+const SIZES: [usize; 2] = [500, 5_000];
+
+/// A book chapter of about `lines` lines, mixing the constructs real chapters
+/// use: headings with attributes, prose with emphasis, citations, math and
+/// cross-references, python, r and julia cells, callouts, tables and lists.
+fn chapter(lines: usize) -> String {
+    let mut source = String::from(
+        "---\ntitle: \"Modelling Commutes\"\nauthor: Ada Lovelace\nexecute:\n  echo: true\n---\n\n",
+    );
+    let mut section = 0;
+    while source.lines().count() < lines {
+        section += 1;
+        source.push_str(&format!(
+            "## Section {section} {{#sec-s{section}}}\n\n\
+             Rents rose by *12 percent* while wages grew by **3 percent** \
+             [@smith2024; @jones2023, p. {section}]. As @brown2022 notes, the \
+             model $t = \\beta_0 + \\beta_1 \\log r$ fits well (see @fig-s{section}).\n\
+             A flat costs $2,400 a month; `r nrow(homes)` homes were sampled[^n{section}].\n\n\
+             [^n{section}]: From the {section}th survey wave.\n\n\
+             ```{{python}}\n\
+             #| label: fig-s{section}\n\
+             #| fig-cap: \"Commute time against rent\"\n\
+             import pandas as pd\n\
+             homes = pd.read_csv(\"homes-{section}.csv\")\n\
+             homes.plot.scatter(x=\"rent\", y=\"commute\")\n\
+             ```\n\n\
+             ::: {{.callout-note}}\n\
+             ## Note {section}\n\n\
+             Results exclude remote workers; see <https://example.org/{section}>.\n\
+             :::\n\n\
+             | City     | Rent   | Commute |\n\
+             |----------|-------:|--------:|\n\
+             | Boston   | $2,400 | 38 min  |\n\
+             | Portland | $1,650 | 27 min  |\n\n\
+             : Rent and commute {{#tbl-s{section}}}\n\n\
+             ```{{r}}\n\
+             fit <- lm(commute ~ log(rent), data = homes)\n\
+             summary(fit)\n\
+             ```\n\n\
+             1. Fit the model with `lm()`.\n\
+             2. Check the residuals.\n   - Plot them against *rent*.\n\n\
+             $$\n\
+             \\hat\\beta = (X^T X)^{{-1}} X^T y\n\
+             $$ {{#eq-s{section}}}\n\n\
+             ```{{julia}}\n\
+             using GLM\n\
+             lm(@formula(commute ~ log(rent)), homes)\n\
+             ```\n\n"
+        ));
+    }
+    source
+}
+
+fn parse(source: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&support::language())
+        .expect("parser loads language");
+    parser.parse(source, None).expect("parse succeeds")
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(20);
+    for lines in SIZES {
+        let source = chapter(lines);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+
+        let mut parser = Parser::new();
+        parser
+            .set_language(&support::language())
+            .expect("parser loads language");
+        group.bench_with_input(BenchmarkId::new("full", lines), &source, |b, source| {
+            b.iter(|| {
+                parser
+                    .parse(black_box(source), None)
+                    .expect("parse succeeds")
+            })
+        });
+
+        // Type one character into the middle of a paragraph
+        let tree = parse(&source);
+        let offset = source[source.len() / 2..]
+            .find("Rents rose")
+            .map(|found| source.len() / 2 + found)
+            .expect("a paragraph past the middle");
+        let mut edited = source.clone();
+        edited.insert(offset, 'x');
+        let position = point_at(&source, offset);
+        let edit = InputEdit {
+            start_byte: offset,
+            old_end_byte: offset,
+            new_end_byte: offset + 1,
+            start_position: position,
+            old_end_position: position,
+            new_end_position: Point::new(position.row, position.column + 1),
+        };
+        group.bench_with_input(
+            BenchmarkId::new("incremental", lines),
+            &edited,
+            |b, edited| {
+                b.iter_batched(
+                    || {
+                        let mut old = tree.clone();
+                        old.edit(&edit);
+                        old
+                    },
+                    |old| {
+                        parser
+                            .parse(black_box(edited), Some(&old))
+                            .expect("reparse succeeds")
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn point_at(source: &str, offset: usize) -> Point {
+    let before = &source[..offset];
+    let row = before.matches('\n').count();
+    let column = offset - before.rfind('\n').map_or(0, |newline| newline + 1);
+    Point::new(row, column)
+}
+
+/// A highlight configuration for an injected language, recognizing every
+/// capture its query uses.
+fn injected_configuration(
+    language: tree_sitter::Language,
+    name: &str,
+    highlights: &str,
+) -> HighlightConfiguration {
+    let mut config = HighlightConfiguration::new(language, name, highlights, "", "")
+        .expect("valid injected highlight configuration");
+    let names: Vec<String> = config.names().iter().map(|name| name.to_string()).collect();
+    config.configure(&names);
+    config
+}
+
+fn bench_highlight(c: &mut Criterion) {
+    let quarto = support::highlight_configuration();
+    let python = injected_configuration(
+        tree_sitter_python::LANGUAGE.into(),
+        "python",
+        tree_sitter_python::HIGHLIGHTS_QUERY,
+    );
+    let yaml = injected_configuration(
+        tree_sitter_yaml::LANGUAGE.into(),
+        "yaml",
+        tree_sitter_yaml::HIGHLIGHTS_QUERY,
+    );
+    let mut group = c.benchmark_group("highlight");
+    group.sample_size(20);
+    for lines in SIZES {
+        let source = chapter(lines);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(BenchmarkId::new("injections", lines), |b| {
+            b.iter(|| {
+                let mut highlighter = Highlighter::new();
+                // Languages without a grammar here (r, julia) are still matched
+                // by injections.scm; only their inner highlighting is skipped
+                let events = highlighter
+                    .highlight(
+                        &quarto,
+                        black_box(&source).as_bytes(),
+                        None,
+                        |name| match name {
+                            "quarto" | "markdown" => Some(&quarto),
+                            "python" => Some(&python),
+                            "yaml" => Some(&yaml),
+                            _ => None,
+                        },
+                    )
+                    .expect("highlighting succeeds");
+                events
+                    .filter(|event| matches!(event, Ok(HighlightEvent::HighlightStart(_))))
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let language = support::language();
+    let queries = [
+        (
+            "outline",
+            Query::new(&language, OUTLINE).expect("valid outline.scm"),
+        ),
+        (
+            "folds",
+            Query::new(&language, FOLDS).expect("valid folds.scm"),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("query");
+    for lines in SIZES {
+        let source = chapter(lines);
+        let tree = parse(&source);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        for (name, query) in &queries {
+            group.bench_with_input(BenchmarkId::new(*name, lines), &source, |b, source| {
+                b.iter(|| {
+                    let mut cursor = QueryCursor::new();
+                    let mut matches = cursor.matches(query, tree.root_node(), source.as_bytes());
+                    let mut count = 0;
+                    while matches.next().is_some() {
+                        count += 1;
+                    }
+                    count
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Where Criterion writes its results, set explicitly so they can be read back.
+fn output_directory() -> PathBuf {
+    if let Some(home) = std::env::var_os("CRITERION_HOME") {
+        return PathBuf::from(home);
+    }
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target"))
+        .join("criterion")
+}
+
+/// Median time in nanoseconds of every benchmark Criterion measured since
+/// `since`, by its full id (`parse/full/5000`).
+fn measured(dir: &Path, since: SystemTime) -> BTreeMap<String, f64> {
+    let mut medians = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let path = entry.path();
+        if path.file_name().is_none_or(|name| name != "estimates.json")
+            || path
+                .parent()
+                .and_then(Path::file_name)
+                .is_none_or(|dir| dir != "new")
+        {
+            continue;
+        }
+        let fresh = entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .is_some_and(|modified| modified >= since);
+        if !fresh {
+            continue;
+        }
+        let read = |file: &Path| -> Option<serde_json::Value> {
+            serde_json::from_str(&std::fs::read_to_string(file).ok()?).ok()
+        };
+        let id = read(&path.with_file_name("benchmark.json"))
+            .and_then(|benchmark| benchmark["full_id"].as_str().map(str::to_string));
+        let median =
+            read(path).and_then(|estimates| estimates["median"]["point_estimate"].as_f64());
+        if let (Some(id), Some(median)) = (id, median) {
+            medians.insert(id, median);
+        }
+    }
+    medians
+}
+
+fn format_ns(ns: f64) -> String {
+    match ns {
+        ns if ns >= 1e9 => format!("{:.2} s", ns / 1e9),
+        ns if ns >= 1e6 => format!("{:.2} ms", ns / 1e6),
+        ns if ns >= 1e3 => format!("{:.2} µs", ns / 1e3),
+        ns => format!("{:.0} ns", ns),
+    }
+}
+
+/// Compares this run with the committed baseline, or records it. Returns
+/// false when a benchmark regressed past the tolerance.
+fn check_baseline(measured: &BTreeMap<String, f64>) -> bool {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(BASELINE);
+    let mut baseline: BTreeMap<String, f64> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    if std::env::var("BENCH_BASELINE").is_ok_and(|mode| mode == "record") {
+        baseline.extend(
+            measured
+                .iter()
+                .map(|(id, median)| (id.clone(), median.round())),
+        );
+        let json = serde_json::to_string_pretty(&baseline).expect("baseline serializes");
+        std::fs::write(&path, json + "\n").expect("baseline written");
+        println!("Recorded {} benchmark(s) in {}", measured.len(), BASELINE);
+        return true;
+    }
+
+    let tolerance: f64 = std::env::var("BENCH_TOLERANCE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(1.5);
+    let mut regressed = false;
+    println!("\nCompared with {} (tolerance {}x):", BASELINE, tolerance);
+    for (id, &median) in measured {
+        let Some(&expected) = baseline.get(id) else {
+            println!("  {id:<32} {:>10}  (not in baseline)", format_ns(median));
+            continue;
+        };
+        let ratio = median / expected;
+        let verdict = if ratio > tolerance {
+            regressed = true;
+            "REGRESSED"
+        } else {
+            "ok"
+        };
+        println!(
+            "  {id:<32} {:>10}  baseline {:>10}  {ratio:.2}x  {verdict}",
+            format_ns(median),
+            format_ns(expected)
+        );
+    }
+    if regressed {
+        println!(
+            "\nSome benchmarks are over {}x their baseline. If the slowdown is intended, \
+             rerun with BENCH_BASELINE=record and commit {}.",
+            tolerance, BASELINE
+        );
+    }
+    !regressed
+}
+
+/// A hand-written main rather than `criterion_main!`, so the output directory
+/// is known and can be read back for the baseline check.
+fn main() {
+    let started = SystemTime::now();
+    let dir = output_directory();
+    let mut criterion = Criterion::default()
+        .output_directory(&dir)
+        .configure_from_args();
+    bench_parse(&mut criterion);
+    bench_highlight(&mut criterion);
+    bench_queries(&mut criterion);
+    criterion.final_summary();
+
+    let measured = measured(&dir, started);
+    if !measured.is_empty() && !check_baseline(&measured) {
+        std::process::exit(1);
+    }
+}
@@ -1,256 +1,154 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-const REPO_URL: &str = "https://github.com/ck37/tree-sitter-pandoc-markdown";
-const COMMIT: &str = "f2e5718a1b2190cf59dd22d9a97fc9b7329a25b6";
+use sha2::{Digest, Sha256};
 
-// tree-sitter-quarto for automated highlighting tests
-const QUARTO_REPO_URL: &str = "https://github.com/ck37/tree-sitter-quarto";
-const QUARTO_COMMIT: &str = "acaaaff1fe10780f496fb122e0de32bd8d7191d6";
+// Checksums of the C sources compiled below, relative to grammars/
+const CHECKSUMS: &str = "grammars/SHA256SUMS";
+
+const FETCH_HINT: &str = "Run scripts/fetch-grammars.sh once (needs network access)";
+
+// Where the tree-sitter-quarto pin lives; Zed reads the same table
+const REV_SOURCE: &str = "extension.toml [grammars.quarto] rev";
 
 fn main() {
+    println!("cargo:rerun-if-changed=extension.toml");
+
     // Only compile the grammar for native tests, not for WASM
     let target = std::env::var("TARGET").unwrap_or_default();
     if target.contains("wasm") {
         return;
     }
 
+    // build.rs never touches the network: the sources are fetched by
+    // scripts/fetch-grammars.sh and pinned by grammars/SHA256SUMS.
     let dir = PathBuf::from("grammars");
-    let pandoc_dir = dir.join("pandoc_markdown");
-    let src_dir = pandoc_dir.join("tree-sitter-pandoc-markdown").join("src");
-
-    std::fs::create_dir_all(&dir).ok();
-
-    let repo_path = pandoc_dir.to_str().expect("path is valid utf-8");
-    if !pandoc_dir.join(".git").exists() {
-        eprintln!("Cloning pandoc-markdown grammar repository...");
-        let status = Command::new("git")
-            .args(["clone", REPO_URL, repo_path])
-            .status()
-            .expect("failed to spawn git clone");
-
-        if !status.success() {
-            panic!("Could not clone pandoc-markdown grammar repository");
-        }
-    }
-
-    eprintln!("Ensuring pandoc-markdown grammar is at commit {COMMIT}...");
-
-    let fetch_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["fetch", "origin"])
-        .status()
-        .expect("failed to fetch pandoc-markdown grammar");
-
-    if !fetch_status.success() {
-        panic!("Could not fetch pandoc-markdown grammar updates");
-    }
-
-    // Discard any local changes before checkout
-    let _clean_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["reset", "--hard"])
-        .status();
-
-    let checkout_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["checkout", COMMIT])
-        .status()
-        .expect("failed to checkout pandoc-markdown grammar commit");
-
-    if !checkout_status.success() {
-        panic!("Could not checkout pandoc-markdown grammar commit {COMMIT}");
-    }
-
-    let reset_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["reset", "--hard", COMMIT])
-        .status()
-        .expect("failed to reset pandoc-markdown grammar");
-
-    if !reset_status.success() {
-        panic!("Could not reset pandoc-markdown grammar to commit {COMMIT}");
-    }
-
-    // Patch Cargo.toml to add edition if missing
-    let cargo_toml_path = pandoc_dir.join("Cargo.toml");
-    if cargo_toml_path.exists() {
-        let cargo_toml =
-            std::fs::read_to_string(&cargo_toml_path).expect("failed to read grammar Cargo.toml");
-        if !cargo_toml.contains("edition = ") {
-            // Insert edition after version line
-            let patched = cargo_toml.replace(
-                "version = \"0.1.0\"\nauthors",
-                "version = \"0.1.0\"\nedition = \"2021\"\nauthors",
-            );
-            std::fs::write(&cargo_toml_path, patched).expect("failed to patch grammar Cargo.toml");
-            eprintln!("Patched pandoc-markdown Cargo.toml to add edition = \"2021\"");
-        }
-    }
-
-    // NOTE: Patching code no longer needed as of commit 4f184e2 (zed-compatible-scopes branch)
-    // The grammar now uses Zed-compatible scopes directly, so no runtime patching needed
-    // See docs/scope-naming-decision.md for full rationale and migration path
-    //
-    // Keeping patching code below commented out for reference:
-    /*
-    let inline_highlights_path = pandoc_dir
-        .join("tree-sitter-pandoc-markdown-inline")
-        .join("queries")
-        .join("highlights.scm");
-    if inline_highlights_path.exists() {
-        let highlights = std::fs::read_to_string(&inline_highlights_path)
-            .expect("failed to read inline highlights.scm");
-
-        let patched = highlights
-            .replace("@markup.italic", "@text.emphasis")
-            .replace("@markup.bold", "@emphasis.strong")
-            .replace("@markup.raw.inline", "@text.literal")
-            .replace("@markup.link.label", "@text.reference")
-            .replace("@markup.link.url", "@text.uri")
-            .replace("@markup.reference.citation", "@text.reference")
-            .replace("@markup.reference.cross_ref", "@text.reference")
-            .replace("@markup.reference.footnote", "@text.reference")
-            .replace("@markup.strikethrough", "@text.strike")
-            .replace("@markup.highlight", "@text.highlight")
-            .replace("@markup.subscript", "@text.subscript")
-            .replace("@markup.superscript", "@text.super")
-            .replace("@markup.underline", "@text.underline")
-            .replace("@markup.math.inline", "@string")
-            .replace("@attribute", "@property");
-
-        std::fs::write(&inline_highlights_path, patched)
-            .expect("failed to patch inline highlights.scm");
-        eprintln!("Patched inline grammar highlights.scm to use Zed-compatible scopes");
-    }
-    */
-
-    let sync_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["submodule", "sync", "--recursive"])
-        .status()
-        .expect("failed to sync pandoc-markdown submodules");
-
-    if !sync_status.success() {
-        panic!("Could not sync pandoc-markdown grammar submodules");
-    }
+    let checksums = Checksums::read(Path::new(CHECKSUMS));
+    println!("cargo:rerun-if-changed={CHECKSUMS}");
 
-    let configure_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args([
-            "config",
-            "submodule.tree-sitter-markdown.url",
-            "https://github.com/tree-sitter-grammars/tree-sitter-markdown.git",
-        ])
-        .status()
-        .expect("failed to configure pandoc-markdown submodule url");
-
-    if !configure_status.success() {
-        panic!("Could not configure pandoc-markdown submodule url");
-    }
-
-    let update_status = Command::new("git")
-        .current_dir(&pandoc_dir)
-        .args(["submodule", "update", "--init", "--recursive"])
-        .status()
-        .expect("failed to update pandoc-markdown submodules after configuration");
-
-    if !update_status.success() {
-        panic!("Could not update pandoc-markdown grammar submodules after configuration");
-    }
-
-    // Compile both grammars together into one library for easier linking
-    let inline_src_dir = pandoc_dir
-        .join("tree-sitter-pandoc-markdown-inline")
-        .join("src");
-
-    if src_dir.join("parser.c").exists() && inline_src_dir.join("parser.c").exists() {
-        eprintln!("Compiling pandoc-markdown grammars (block + inline)...");
-
-        // Compile both grammars into one library
-        // Suppress warnings for unused functions/variables in upstream grammar code
-        cc::Build::new()
-            .include(&src_dir)
-            .include(&inline_src_dir)
-            .file(src_dir.join("parser.c"))
-            .file(src_dir.join("scanner.c"))
-            .file(inline_src_dir.join("parser.c"))
-            .file(inline_src_dir.join("scanner.c"))
-            .flag_if_supported("-Wno-unused-parameter")
-            .flag_if_supported("-Wno-unused-function")
-            .flag_if_supported("-Wno-unused-const-variable")
-            .compile("tree-sitter-pandoc-markdown");
-
-        println!("cargo:rerun-if-changed={}", src_dir.display());
-        println!("cargo:rerun-if-changed={}", inline_src_dir.display());
-    } else {
-        panic!("Pandoc-markdown grammar source not found. Run: git clone --depth=1 --branch=feat/phase-1-pandoc-grammar https://github.com/ck37/tree-sitter-pandoc-markdown grammars/pandoc_markdown");
-    }
+    compile_quarto_grammar(&dir, &checksums, &quarto_rev());
+}
 
-    // Also compile tree-sitter-quarto for heading_highlighting tests
-    compile_quarto_grammar(&dir);
+/// The tree-sitter-quarto commit Zed builds the extension with.
+fn quarto_rev() -> String {
+    let manifest =
+        std::fs::read_to_string("extension.toml").expect("failed to read extension.toml");
+    let manifest: toml::Table = manifest.parse().expect("extension.toml is not valid TOML");
+    manifest
+        .get("grammars")
+        .and_then(|grammars| grammars.get("quarto"))
+        .and_then(|quarto| quarto.get("rev"))
+        .and_then(|rev| rev.as_str())
+        .unwrap_or_else(|| panic!("{REV_SOURCE} is missing"))
+        .to_string()
 }
 
-fn compile_quarto_grammar(dir: &Path) {
-    // Build.rs clones tree-sitter-quarto to grammars/quarto/ for test compilation
-    // Zed will also clone its own copy when installing the extension
+fn compile_quarto_grammar(dir: &Path, checksums: &Checksums, rev: &str) {
+    // Zed clones its own copy when installing the extension; this one is only
+    // for the native tests
     let quarto_dir = dir.join("quarto");
     let src_dir = quarto_dir.join("src");
+    let sources = [src_dir.join("parser.c"), src_dir.join("scanner.c")];
+    checksums.verify("quarto", rev, &sources);
+
+    // Patch the grammar's queries to use Zed-compatible scopes for tests
+    // This simulates what we want Zed to do: load our extension's queries instead of grammar's
+    patch_quarto_queries(&quarto_dir);
+
+    eprintln!("Compiling tree-sitter-quarto grammar...");
+    cc::Build::new()
+        .include(&src_dir)
+        .files(&sources)
+        .flag_if_supported("-Wno-unused-parameter")
+        .flag_if_supported("-Wno-unused-function")
+        .flag_if_supported("-Wno-unused-const-variable")
+        .compile("tree-sitter-quarto");
+
+    println!("cargo:rerun-if-changed={}", src_dir.display());
+    println!("cargo:rerun-if-changed=languages/quarto/highlights.scm");
+    println!("cargo:rerun-if-changed=languages/quarto/injections.scm");
+}
 
-    if !quarto_dir.join(".git").exists() {
-        eprintln!("Cloning tree-sitter-quarto repository...");
-        let status = Command::new("git")
-            .args(["clone", QUARTO_REPO_URL, quarto_dir.to_str().unwrap()])
-            .status()
-            .expect("failed to spawn git clone for quarto");
+/// The contents of grammars/SHA256SUMS: a `# <grammar> <commit>` line per
+/// grammar, then `<sha256>  <path>` lines in `sha256sum` format.
+struct Checksums {
+    commits: Vec<(String, String)>,
+    files: Vec<(String, PathBuf)>,
+}
 
-        if !status.success() {
-            panic!("Could not clone tree-sitter-quarto repository");
+impl Checksums {
+    fn read(path: &Path) -> Self {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            panic!(
+                "{} not found. {FETCH_HINT}, then commit the checksums it records.",
+                path.display()
+            )
+        });
+        let mut commits = Vec::new();
+        let mut files = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(pin) = line.strip_prefix('#') {
+                if let [grammar, commit] = pin.split_whitespace().collect::<Vec<_>>()[..] {
+                    commits.push((grammar.to_string(), commit.to_string()));
+                }
+            } else if let Some((hash, file)) = line.split_once("  ") {
+                files.push((hash.to_lowercase(), Path::new("grammars").join(file)));
+            }
+        }
+        Self { commits, files }
+    }
+
+    /// Fails the build unless the checksums were recorded for `commit` and
+    /// every source matches them.
+    fn verify(&self, grammar: &str, commit: &str, sources: &[PathBuf]) {
+        let recorded = self
+            .commits
+            .iter()
+            .find(|(name, _)| name == grammar)
+            .map(|(_, commit)| commit.as_str());
+        if recorded != Some(commit) {
+            panic!(
+                "{CHECKSUMS} pins {grammar} at {}, but {REV_SOURCE} is {commit}. \
+                 Run scripts/fetch-grammars.sh --record to fetch {commit} and record its checksums.",
+                recorded.unwrap_or("no commit"),
+            );
         }
-    }
-
-    eprintln!("Ensuring tree-sitter-quarto is at commit {QUARTO_COMMIT}...");
-
-    let _fetch_status = Command::new("git")
-        .current_dir(&quarto_dir)
-        .args(["fetch", "origin"])
-        .status();
-
-    let _clean_status = Command::new("git")
-        .current_dir(&quarto_dir)
-        .args(["reset", "--hard"])
-        .status();
-
-    let checkout_status = Command::new("git")
-        .current_dir(&quarto_dir)
-        .args(["checkout", QUARTO_COMMIT])
-        .status()
-        .expect("failed to checkout quarto grammar commit");
 
-    if !checkout_status.success() {
-        panic!("Could not checkout tree-sitter-quarto commit {QUARTO_COMMIT}");
+        let mut drifted = Vec::new();
+        for source in sources {
+            let Ok(contents) = std::fs::read(source) else {
+                panic!(
+                    "{} not found. {FETCH_HINT} to fetch {grammar} at {commit}.",
+                    source.display()
+                );
+            };
+            let expected = self
+                .files
+                .iter()
+                .find(|(_, file)| file == source)
+                .map(|(hash, _)| hash.as_str());
+            let actual = hex(&Sha256::digest(&contents));
+            if expected != Some(actual.as_str()) {
+                drifted.push(format!(
+                    "  {}: expected {}, found {}",
+                    source.display(),
+                    expected.unwrap_or("no checksum"),
+                    actual
+                ));
+            }
+        }
+        if !drifted.is_empty() {
+            panic!(
+                "{grammar} grammar sources do not match {CHECKSUMS} for {REV_SOURCE} {commit}:\n{}\n\
+                 Run scripts/fetch-grammars.sh to restore them, or scripts/fetch-grammars.sh --record \
+                 if the change is intended.",
+                drifted.join("\n")
+            );
+        }
     }
+}
 
-    if src_dir.join("parser.c").exists() {
-        // Patch the grammar's queries to use Zed-compatible scopes for tests
-        // This simulates what we want Zed to do: load our extension's queries instead of grammar's
-        patch_quarto_queries(&quarto_dir);
-
-        eprintln!("Compiling tree-sitter-quarto grammar...");
-
-        cc::Build::new()
-            .include(&src_dir)
-            .file(src_dir.join("parser.c"))
-            .file(src_dir.join("scanner.c"))
-            .flag_if_supported("-Wno-unused-parameter")
-            .flag_if_supported("-Wno-unused-function")
-            .flag_if_supported("-Wno-unused-const-variable")
-            .compile("tree-sitter-quarto");
-
-        println!("cargo:rerun-if-changed={}", src_dir.display());
-    } else {
-        eprintln!("Warning: tree-sitter-quarto grammar source not found, heading tests may fail");
-    }
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
 }
 
 fn patch_quarto_queries(quarto_dir: &Path) {
@@ -0,0 +1,225 @@
+//! Shows how the extension's queries highlight a Quarto document.
+//!
+//! Parses the file with tree-sitter-quarto and runs `languages/quarto/highlights.scm`
+//! and `injections.scm` through `tree-sitter-highlight`, the same captures Zed
+//! receives:
+//!
+//! ```text
+//! cargo run --example inspect-highlights -- <file.qmd> [--html | --spans]
+//! ```
+//!
+//! By default the document is printed with ANSI colors followed by a legend of
+//! the captures it uses. `--html` writes a standalone page to stdout where
+//! hovering a token shows its capture stack; `--spans` prints the span list
+//! used by the snapshot tests. Pass `-` to read from stdin.
+//!
+//! Code cells are injected into python, r, julia and so on, whose grammars Zed
+//! supplies; here they keep only the captures from the Quarto queries.
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::process::ExitCode;
+
+use support::{Highlights, Span};
+
+const USAGE: &str = "usage: inspect-highlights <file.qmd | -> [--html | --spans]";
+
+enum Format {
+    Ansi,
+    Html,
+    Spans,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("inspect-highlights: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    let (file, format) = match args {
+        [file] => (file, Format::Ansi),
+        [file, flag] if flag == "--html" => (file, Format::Html),
+        [file, flag] if flag == "--spans" => (file, Format::Spans),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let source = if file == "-" {
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("could not read stdin: {}", e))?;
+        source
+    } else {
+        std::fs::read_to_string(file).map_err(|e| format!("could not read {}: {}", file, e))?
+    };
+
+    let highlights = support::highlight(&source);
+    Ok(match format {
+        Format::Ansi => ansi(&highlights),
+        Format::Html => html(file, &highlights),
+        Format::Spans => highlights.snapshot(),
+    })
+}
+
+/// SGR parameters for a capture, chosen by its leading name segments so
+/// related captures (`text.title`, `markup.heading`) look alike.
+fn ansi_style(scope: &str) -> &'static str {
+    const STYLES: &[(&str, &str)] = &[
+        ("text.title", "1;34"),
+        ("markup.heading", "1;34"),
+        ("emphasis.strong", "1"),
+        ("markup.bold", "1"),
+        ("text.emphasis", "3"),
+        ("markup.italic", "3"),
+        ("text.strike", "9"),
+        ("text.uri", "4;36"),
+        ("text.reference", "36"),
+        ("link", "4;36"),
+        ("text.literal", "32"),
+        ("string", "32"),
+        ("comment", "2;37"),
+        ("punctuation", "2"),
+        ("keyword", "35"),
+        ("function", "34"),
+        ("variable", "33"),
+        ("constant", "33"),
+        ("number", "33"),
+        ("property", "36"),
+        ("attribute", "36"),
+        ("type", "36"),
+        ("tag", "31"),
+        ("label", "31"),
+    ];
+    STYLES
+        .iter()
+        .find(|(prefix, _)| {
+            scope == *prefix
+                || scope
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+        .map_or("35;4", |(_, style)| style)
+}
+
+fn ansi(highlights: &Highlights) -> String {
+    let mut output = String::new();
+    let mut used = BTreeSet::new();
+    for span in &highlights.spans {
+        match span.scopes.last() {
+            Some(scope) => {
+                used.insert(scope.as_str());
+                // Style each line separately so a pager or a narrow terminal
+                // never leaves color bleeding past a newline.
+                let style = ansi_style(scope);
+                for (index, line) in span.text.split('\n').enumerate() {
+                    if index > 0 {
+                        output.push('\n');
+                    }
+                    if !line.is_empty() {
+                        output.push_str(&format!("\x1b[{}m{}\x1b[0m", style, line));
+                    }
+                }
+            }
+            None => output.push_str(&span.text),
+        }
+    }
+
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str("\n--- captures (innermost shown) ---\n");
+    for scope in used {
+        output.push_str(&format!("\x1b[{}m@{}\x1b[0m\n", ansi_style(scope), scope));
+    }
+    output
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The capture's leading segment, used as the CSS class for its color.
+fn css_class(span: &Span) -> Option<String> {
+    let scope = span.scopes.last()?;
+    let family = scope.split('.').next().unwrap_or(scope);
+    Some(format!("c-{}", family))
+}
+
+fn html(file: &str, highlights: &Highlights) -> String {
+    let mut body = String::new();
+    for span in &highlights.spans {
+        let text = escape(&span.text);
+        match css_class(span) {
+            Some(class) => {
+                let stack = span
+                    .scopes
+                    .iter()
+                    .map(|scope| format!("@{}", scope))
+                    .collect::<Vec<_>>()
+                    .join(" > ");
+                body.push_str(&format!(
+                    r#"<span class="{}" data-stack="{}" title="{}">{}</span>"#,
+                    class,
+                    escape(&stack),
+                    escape(&stack),
+                    text
+                ));
+            }
+            None => body.push_str(&text),
+        }
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title} — highlight captures</title>
+<style>
+body {{ margin: 0; font: 14px/1.5 ui-monospace, Menlo, Consolas, monospace; background: #fafafa; color: #24292f; }}
+pre {{ margin: 0; padding: 1rem 1rem 4rem; white-space: pre-wrap; }}
+pre span[data-stack]:hover {{ outline: 1px solid #888; background: #fff3bf; }}
+#stack {{ position: fixed; bottom: 0; left: 0; right: 0; padding: .5rem 1rem; background: #24292f; color: #f6f8fa; min-height: 1.5em; }}
+.c-text, .c-markup {{ color: #0550ae; }}
+.c-emphasis {{ color: #953800; font-weight: bold; }}
+.c-punctuation {{ color: #6e7781; }}
+.c-string {{ color: #116329; }}
+.c-comment {{ color: #6e7781; font-style: italic; }}
+.c-keyword {{ color: #8250df; }}
+.c-function {{ color: #0550ae; }}
+.c-variable, .c-constant, .c-number {{ color: #953800; }}
+.c-property, .c-attribute, .c-type, .c-label {{ color: #0a3069; }}
+.c-tag {{ color: #cf222e; }}
+</style>
+</head>
+<body>
+<pre>{body}</pre>
+<div id="stack">Hover a token to see its capture stack, outermost first.</div>
+<script>
+const stack = document.getElementById("stack");
+document.querySelectorAll("pre span[data-stack]").forEach((span) => {{
+  span.addEventListener("mouseenter", () => {{ stack.textContent = span.dataset.stack; }});
+}});
+</script>
+</body>
+</html>
+"#,
+        title = escape(file),
+        body = body
+    )
+}
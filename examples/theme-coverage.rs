@@ -0,0 +1,150 @@
+//! Reports how Zed themes style every capture in the extension's highlight queries.
+//!
+//! ```text
+//! cargo run --example theme-coverage -- <themes-dir> [--theme <name>]... [--strict]
+//! ```
+//!
+//! `<themes-dir>` is searched for Zed theme family files, such as
+//! `assets/themes/` in a checkout of the Zed repository. For each theme (or
+//! only those named with `--theme`), every capture in
+//! `languages/quarto/highlights.scm` is listed as styled, falling back to a
+//! shorter key, or plain text. With `--strict` the exit status is non-zero when
+//! any capture renders as plain text.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use quarto_zed::themes::{self, Resolution, Theme};
+use walkdir::WalkDir;
+
+const HIGHLIGHTS: &str = include_str!("../languages/quarto/highlights.scm");
+
+const USAGE: &str = "usage: theme-coverage <themes-dir> [--theme <name>]... [--strict]";
+
+struct Options {
+    dir: String,
+    themes: Vec<String>,
+    strict: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("theme-coverage: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut dir = None;
+    let mut themes = Vec::new();
+    let mut strict = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--theme" => themes.push(args.next().ok_or(USAGE)?.clone()),
+            "--strict" => strict = true,
+            _ if dir.is_none() && !arg.starts_with("--") => dir = Some(arg.clone()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    Ok(Options {
+        dir: dir.ok_or(USAGE)?,
+        themes,
+        strict,
+    })
+}
+
+/// Every theme in the family files under `dir`, sorted by name.
+fn load_themes(dir: &Path) -> Result<Vec<Theme>, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    let mut found = Vec::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Ok(json) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        match themes::parse_family(&json) {
+            Ok(family) => found.extend(family),
+            Err(e) => eprintln!("skipping {}: {}", path.display(), e),
+        }
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+fn describe(resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Styled => "styled".to_string(),
+        Resolution::Fallback(key) => format!("styled by @{}", key),
+        Resolution::Unstyled(None) => "PLAIN TEXT (no matching key)".to_string(),
+        Resolution::Unstyled(Some(key)) => format!("PLAIN TEXT (@{} sets no style)", key),
+    }
+}
+
+/// Prints the report; false when `--strict` and some capture is plain text.
+fn run(args: &[String]) -> Result<bool, String> {
+    let options = parse_args(args)?;
+    let mut selected = load_themes(Path::new(&options.dir))?;
+    if !options.themes.is_empty() {
+        let missing: Vec<&String> = options
+            .themes
+            .iter()
+            .filter(|name| !selected.iter().any(|theme| &theme.name == *name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "themes not found in {}: {:?}",
+                options.dir, missing
+            ));
+        }
+        selected.retain(|theme| options.themes.contains(&theme.name));
+    }
+    if selected.is_empty() {
+        return Err(format!("no Zed themes found in {}", options.dir));
+    }
+
+    let captures = themes::captures(HIGHLIGHTS);
+    let width = captures.iter().map(String::len).max().unwrap_or(0) + 1;
+    let mut plain_total = 0;
+    for theme in &selected {
+        println!("{} ({})", theme.name, theme.appearance);
+        let mut plain = 0;
+        for capture in &captures {
+            let resolution = theme.resolve(capture);
+            if !resolution.is_styled() {
+                plain += 1;
+            }
+            println!(
+                "  @{:<width$} {}",
+                capture,
+                describe(&resolution),
+                width = width
+            );
+        }
+        println!(
+            "  {} of {} captures styled\n",
+            captures.len() - plain,
+            captures.len()
+        );
+        plain_total += plain;
+    }
+
+    if plain_total > 0 {
+        println!(
+            "{} capture(s) render as plain text across {} theme(s).",
+            plain_total,
+            selected.len()
+        );
+    }
+    Ok(!(options.strict && plain_total > 0))
+}
@@ -0,0 +1,18 @@
+//! Documents assembled from the fuzzer's bytes by `support::fuzz`: parsing
+//! must finish within the time bound, and code cells and front matter must
+//! stay within their delimiters.
+#![no_main]
+
+#[path = "../../tests/support/mod.rs"]
+mod support;
+
+use libfuzzer_sys::fuzz_target;
+use quarto_zed as _;
+use support::fuzz::{self, Document};
+
+fuzz_target!(|data: &[u8]| {
+    let document = Document::from_bytes(data);
+    if let Err(message) = fuzz::check(&document) {
+        panic!("{}\nDocument:\n{}", message, document.source);
+    }
+});
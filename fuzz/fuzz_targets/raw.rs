@@ -0,0 +1,18 @@
+//! Arbitrary UTF-8 as a Quarto document: parsing and highlighting must finish
+//! within the time bound without panicking.
+#![no_main]
+
+#[path = "../../tests/support/mod.rs"]
+mod support;
+
+use libfuzzer_sys::fuzz_target;
+use quarto_zed as _;
+use support::fuzz;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        if let Err(message) = fuzz::check_terminates(source) {
+            panic!("{}", message);
+        }
+    }
+});
@@ -0,0 +1,29 @@
+; Runnable executable code cells
+;
+; Puts a run button in the gutter next to each cell's opening fence. The tag
+; selects a task template from tasks.json, which extracts the cell with the
+; quarto-cell helper and pipes it into the matching interpreter.
+
+; Python
+; ------
+
+((executable_code_cell
+  language: (language_name) @run
+  (#any-of? @run "python" "python3"))
+ (#set! tag quarto-python-cell))
+
+; R
+; -
+
+((executable_code_cell
+  language: (language_name) @run
+  (#eq? @run "r"))
+ (#set! tag quarto-r-cell))
+
+; Julia
+; -----
+
+((executable_code_cell
+  language: (language_name) @run
+  (#eq? @run "julia"))
+ (#set! tag quarto-julia-cell))
@@ -0,0 +1 @@
+../quarto/folds.scm
\ No newline at end of file
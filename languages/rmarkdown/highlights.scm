@@ -0,0 +1 @@
+../quarto/highlights.scm
\ No newline at end of file
@@ -0,0 +1 @@
+../quarto/indents.scm
\ No newline at end of file
@@ -0,0 +1 @@
+../quarto/injections.scm
\ No newline at end of file
@@ -0,0 +1 @@
+../quarto/locals.scm
\ No newline at end of file
@@ -0,0 +1 @@
+../quarto/outline.scm
\ No newline at end of file
@@ -0,0 +1 @@
+../quarto/runnables.scm
\ No newline at end of file
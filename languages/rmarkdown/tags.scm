@@ -0,0 +1 @@
+../quarto/tags.scm
\ No newline at end of file
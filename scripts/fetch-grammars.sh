@@ -0,0 +1,99 @@
+#!/usr/bin/env bash
+# Fetch the tree-sitter-quarto sources build.rs compiles for the native tests
+# into grammars/, at the repository and rev of [grammars.quarto] in
+# extension.toml.
+#
+#   scripts/fetch-grammars.sh           fetch and check against grammars/SHA256SUMS
+#   scripts/fetch-grammars.sh --record  fetch and (re)write grammars/SHA256SUMS,
+#                                       only if grammars/quarto is exactly at rev
+#
+# This is the only step that needs network access; cargo build and cargo test
+# then work offline. A checkout with local changes to src/ is left alone
+# unless --force is given.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+CHECKSUMS="grammars/SHA256SUMS"
+
+record=false
+force=false
+for arg in "$@"; do
+    case "$arg" in
+        --record) record=true ;;
+        --force) force=true ;;
+        *) echo "usage: $0 [--record] [--force]" >&2; exit 2 ;;
+    esac
+done
+
+# grammar_field <key>: a string value from the [grammars.quarto] table
+grammar_field() {
+    sed -n '/^\[grammars\.quarto\]/,/^\[/p' extension.toml |
+        sed -n "s/^$1 *= *\"\(.*\)\"/\1/p"
+}
+
+QUARTO_REPO_URL="$(grammar_field repository)"
+QUARTO_COMMIT="$(grammar_field rev)"
+
+# fetch <dir> <url> <commit>
+fetch() {
+    local dir="$1" url="$2" commit="$3"
+    if [ ! -d "$dir/.git" ]; then
+        git clone --quiet "$url" "$dir"
+    elif [ -n "$(git -C "$dir" status --porcelain --untracked-files=no -- src)" ] && ! $force; then
+        # queries/ is excluded: build.rs copies the extension's queries there
+        echo "$dir/src has local changes; commit or stash them, or rerun with --force" >&2
+        exit 1
+    fi
+    if ! git -C "$dir" cat-file -e "$commit^{commit}" 2>/dev/null; then
+        git -C "$dir" fetch --quiet origin
+    fi
+    git -C "$dir" checkout --quiet --force "$commit"
+    echo "$dir at $commit"
+}
+
+# verify_checkout <dir> <commit>: refuse to record checksums for anything but
+# the pristine sources of <commit>
+verify_checkout() {
+    local dir="$1" commit="$2" head
+    head="$(git -C "$dir" rev-parse HEAD)"
+    if [ "$head" != "$commit" ]; then
+        echo "$dir is at $head, not rev $commit; not recording checksums" >&2
+        exit 1
+    fi
+    if [ -n "$(git -C "$dir" status --porcelain -- src)" ]; then
+        echo "$dir/src differs from $commit; not recording checksums" >&2
+        exit 1
+    fi
+}
+
+mkdir -p grammars
+
+fetch grammars/quarto "$QUARTO_REPO_URL" "$QUARTO_COMMIT"
+
+# Paths relative to grammars/, as build.rs expects them
+SOURCES=(
+    quarto/src/parser.c
+    quarto/src/scanner.c
+)
+
+if command -v sha256sum >/dev/null; then
+    sha256() { sha256sum "$@"; }
+else
+    sha256() { shasum -a 256 "$@"; }
+fi
+
+if $record; then
+    verify_checkout grammars/quarto "$QUARTO_COMMIT"
+    {
+        echo "# quarto $QUARTO_COMMIT"
+        (cd grammars && sha256 "${SOURCES[@]}")
+    } > "$CHECKSUMS"
+    echo "Recorded $CHECKSUMS; commit it with the new pins."
+elif [ -f "$CHECKSUMS" ]; then
+    (cd grammars && grep -v '^#' SHA256SUMS | sha256 -c --quiet -)
+    echo "Grammar sources match $CHECKSUMS."
+else
+    echo "$CHECKSUMS is missing; rerun with --record to create it." >&2
+    exit 1
+fi
@@ -0,0 +1,56 @@
+#!/usr/bin/env bash
+# Review the pending highlight snapshots that tests/highlight_snapshots.rs
+# writes as tests/corpus/snapshots/*.snap.new when a corpus document's
+# highlighting changes.
+#
+#   scripts/review-snapshots.sh           show each pending change as a diff
+#   scripts/review-snapshots.sh --accept  replace the snapshots with the pending ones
+#   scripts/review-snapshots.sh --reject  delete the pending snapshots
+#
+# Run `cargo test --test highlight_snapshots` first to produce them, and
+# commit accepted snapshots together with the query or grammar change.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+mode=show
+case "${1:-}" in
+    "") ;;
+    --accept) mode=accept ;;
+    --reject) mode=reject ;;
+    *) echo "usage: $0 [--accept|--reject]" >&2; exit 2 ;;
+esac
+
+shopt -s nullglob
+pending=(tests/corpus/snapshots/*.snap.new)
+if [ ${#pending[@]} -eq 0 ]; then
+    echo "No pending snapshots."
+    exit 0
+fi
+
+for new in "${pending[@]}"; do
+    snapshot="${new%.new}"
+    case "$mode" in
+        show)
+            if [ -f "$snapshot" ]; then
+                git --no-pager diff --no-index -- "$snapshot" "$new" || true
+            else
+                echo "new snapshot: $snapshot"
+                cat "$new"
+            fi
+            ;;
+        accept)
+            mv "$new" "$snapshot"
+            echo "accepted $snapshot"
+            ;;
+        reject)
+            rm "$new"
+            echo "rejected $snapshot"
+            ;;
+    esac
+done
+
+if [ "$mode" = show ]; then
+    echo
+    echo "${#pending[@]} pending snapshot(s). Accept with $0 --accept or discard with $0 --reject."
+fi
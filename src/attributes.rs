@@ -0,0 +1,119 @@
+//! Pandoc attribute lists such as `{#fig-plot .callout-note collapse="true"}`.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Parses an attribute list with or without its surrounding braces.
+    ///
+    /// A bare word is treated as a class, matching Pandoc's `::: warning` shorthand.
+    pub fn parse(source: &str) -> Self {
+        let inner = source.trim();
+        let inner = inner
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .unwrap_or(inner);
+
+        let mut attributes = Self::default();
+        for token in tokenize(inner) {
+            if let Some(id) = token.strip_prefix('#') {
+                attributes.id = Some(id.to_string());
+            } else if let Some(class) = token.strip_prefix('.') {
+                attributes.classes.push(class.to_string());
+            } else if let Some((key, value)) = token.split_once('=') {
+                attributes.pairs.push((key.to_string(), unquote(value)));
+            } else if !token.is_empty() {
+                attributes.classes.push(token);
+            }
+        }
+        attributes
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets `key`, keeping its position when it already exists.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.pairs.iter_mut().find(|(k, _)| k == key) {
+            Some(pair) => pair.1 = value.to_string(),
+            None => self.pairs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.pairs.retain(|(k, _)| k != key);
+    }
+}
+
+impl fmt::Display for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(id) = &self.id {
+            parts.push(format!("#{id}"));
+        }
+        parts.extend(self.classes.iter().map(|class| format!(".{class}")));
+        parts.extend(
+            self.pairs
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", value.replace('"', "\\\""))),
+        );
+        write!(f, "{{{}}}", parts.join(" "))
+    }
+}
+
+/// Splits on whitespace outside of double quotes.
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for ch in source.chars() {
+        match ch {
+            _ if escaped => {
+                current.push(ch);
+                escaped = false;
+            }
+            '\\' if quoted => {
+                current.push(ch);
+                escaped = true;
+            }
+            '"' => {
+                quoted = !quoted;
+                current.push(ch);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(|v| v.replace("\\\"", "\""))
+        .unwrap_or_else(|| value.to_string())
+}
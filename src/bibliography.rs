@@ -0,0 +1,384 @@
+//! Local bibliographies (BibTeX and CSL-JSON) for the `/quarto-cite` slash command.
+//!
+//! Only the fields needed to find and recognise a work are kept: key, authors,
+//! title, year and the journal or book it appeared in.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use serde_yaml_ng::Value as YamlValue;
+
+use crate::project::{self, Project};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    /// Family names, or the full name when it could not be split.
+    pub authors: Vec<String>,
+    pub title: Option<String>,
+    pub year: Option<String>,
+    /// Journal, book or publisher.
+    pub container: Option<String>,
+}
+
+impl Entry {
+    /// `[@key]`, ready to paste into a document.
+    pub fn citation(&self) -> String {
+        format!("[@{}]", self.key)
+    }
+
+    /// `Smith & Doe (2020)` or `Smith et al. (2020)`.
+    pub fn short_authors(&self) -> String {
+        let names = match self.authors.as_slice() {
+            [] => "Anonymous".to_string(),
+            [one] => one.clone(),
+            [first, second] => format!("{} & {}", first, second),
+            [first, ..] => format!("{} et al.", first),
+        };
+        match &self.year {
+            Some(year) => format!("{} ({})", names, year),
+            None => names,
+        }
+    }
+
+    /// A one-line reference: `Smith & Doe (2020). Title. *Journal*.`
+    pub fn reference(&self) -> String {
+        let mut reference = self.short_authors();
+        if let Some(title) = &self.title {
+            reference.push_str(&format!(". {}", title.trim_end_matches('.')));
+        }
+        if let Some(container) = &self.container {
+            reference.push_str(&format!(". *{}*", container));
+        }
+        reference.push('.');
+        reference
+    }
+}
+
+/// Parses `text` as BibTeX or CSL-JSON depending on the file extension.
+pub fn load(path: &str, text: &str) -> Result<Vec<Entry>, String> {
+    if path.ends_with(".json") {
+        parse_csl_json(text).map_err(|e| format!("{}: {}", path, e))
+    } else {
+        Ok(parse_bibtex(text))
+    }
+}
+
+/// Entries of a BibTeX file. `@string`, `@comment` and `@preamble` are skipped,
+/// as are entries that are not closed.
+pub fn parse_bibtex(text: &str) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut rest = text;
+
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let Some(open) = rest.find(['{', '(']) else {
+            break;
+        };
+        let kind = rest[..open].trim().to_lowercase();
+        let Some(body_len) = balanced_len(&rest[open..]) else {
+            break;
+        };
+        let body = &rest[open + 1..open + body_len - 1];
+        rest = &rest[open + body_len..];
+
+        if matches!(kind.as_str(), "string" | "comment" | "preamble")
+            || !kind.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            continue;
+        }
+        let Some((key, fields)) = body.split_once(',') else {
+            continue;
+        };
+        let fields = bibtex_fields(fields);
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+
+        entries.push(Entry {
+            key: key.trim().to_string(),
+            authors: field("author")
+                .or_else(|| field("editor"))
+                .map(|names| names.split(" and ").map(family_name).collect())
+                .unwrap_or_default(),
+            title: field("title"),
+            year: field("year")
+                .or_else(|| field("date").map(|date| date.chars().take(4).collect::<String>())),
+            container: field("journal")
+                .or_else(|| field("journaltitle"))
+                .or_else(|| field("booktitle"))
+                .or_else(|| field("publisher")),
+        });
+    }
+
+    entries
+}
+
+/// Entries of a CSL-JSON file: an array of items with an `id`.
+pub fn parse_csl_json(text: &str) -> Result<Vec<Entry>, String> {
+    let items: Vec<Value> =
+        serde_json::from_str(text).map_err(|e| format!("Invalid CSL-JSON: {}", e))?;
+
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let key = match &item["id"] {
+                Value::String(id) => id.clone(),
+                Value::Number(id) => id.to_string(),
+                _ => return None,
+            };
+            let string = |field: &str| item[field].as_str().map(str::to_string);
+            let authors = item["author"]
+                .as_array()
+                .or_else(|| item["editor"].as_array())
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|name| {
+                            name["family"]
+                                .as_str()
+                                .or_else(|| name["literal"].as_str())
+                                .map(str::to_string)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let year = item["issued"]["date-parts"][0][0]
+                .as_i64()
+                .map(|year| year.to_string())
+                .or_else(|| {
+                    item["issued"]["date-parts"][0][0]
+                        .as_str()
+                        .map(str::to_string)
+                })
+                .or_else(|| {
+                    item["issued"]["raw"]
+                        .as_str()
+                        .map(|raw| raw.chars().take(4).collect())
+                });
+
+            Some(Entry {
+                key,
+                authors,
+                title: string("title"),
+                year,
+                container: string("container-title").or_else(|| string("publisher")),
+            })
+        })
+        .collect())
+}
+
+/// Entries matching every word of `query`, best match first.
+///
+/// Each word is looked for in the key, authors, year and title; a word that
+/// only appears as a subsequence of the key or title (`smth20`) still matches,
+/// but ranks below whole-substring matches.
+pub fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+
+    let mut scored: Vec<(u32, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            words
+                .iter()
+                .map(|word| word_score(entry, word))
+                .sum::<Option<u32>>()
+                .map(|score| (score, entry))
+        })
+        .collect();
+    scored.sort_by(|(a, left), (b, right)| b.cmp(a).then_with(|| left.key.cmp(&right.key)));
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn word_score(entry: &Entry, word: &str) -> Option<u32> {
+    let key = entry.key.to_lowercase();
+    let title = entry.title.as_deref().unwrap_or_default().to_lowercase();
+
+    let mut score = 0;
+    if key.starts_with(word) {
+        score += 4;
+    } else if key.contains(word) {
+        score += 3;
+    }
+    if entry
+        .authors
+        .iter()
+        .any(|author| author.to_lowercase().starts_with(word))
+    {
+        score += 3;
+    }
+    if entry.year.as_deref() == Some(word) {
+        score += 3;
+    }
+    if title
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|w| w.starts_with(word))
+    {
+        score += 2;
+    } else if title.contains(word) {
+        score += 1;
+    }
+
+    if score == 0 && (is_subsequence(word, &key) || is_subsequence(word, &title)) {
+        score = 1;
+    }
+    (score > 0).then_some(score)
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Length of the `{...}` or `(...)` group at the start of `text`, delimiters
+/// included. Only the opening delimiter's own pair is counted.
+fn balanced_len(text: &str) -> Option<usize> {
+    let open = text.chars().next()?;
+    let close = match open {
+        '{' => '}',
+        '(' => ')',
+        _ => return None,
+    };
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index + 1);
+            }
+        }
+    }
+    None
+}
+
+/// `name = {value}`, `name = "value"` and `name = 2020` pairs, lowercased names.
+fn bibtex_fields(text: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    let mut rest = text;
+
+    while let Some(equals) = rest.find('=') {
+        let name = rest[..equals]
+            .trim()
+            .trim_start_matches(',')
+            .trim()
+            .to_lowercase();
+        let value_text = rest[equals + 1..].trim_start();
+        let (value, consumed) = match value_text.chars().next() {
+            Some('{') => match balanced_len(value_text) {
+                Some(len) => (&value_text[1..len - 1], len),
+                None => (&value_text[1..], value_text.len()),
+            },
+            Some('"') => match value_text[1..].find('"') {
+                Some(end) => (&value_text[1..end + 1], end + 2),
+                None => (&value_text[1..], value_text.len()),
+            },
+            _ => {
+                let end = value_text.find(',').unwrap_or(value_text.len());
+                (value_text[..end].trim(), end)
+            }
+        };
+        fields.push((name, clean_latex(value)));
+        let offset = rest.len() - value_text.len() + consumed;
+        rest = &rest[offset..];
+    }
+
+    fields
+}
+
+/// Strips grouping braces and the common escapes from a BibTeX value.
+fn clean_latex(value: &str) -> String {
+    let value = value
+        .replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_")
+        .replace("--", "–");
+    let value: String = value.chars().filter(|&c| c != '{' && c != '}').collect();
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `Smith, John` and `John Smith` both give `Smith`.
+fn family_name(name: &str) -> String {
+    let name = name.trim();
+    match name.split_once(',') {
+        Some((family, _)) => family.trim().to_string(),
+        None => name.rsplit(' ').next().unwrap_or(name).to_string(),
+    }
+}
+
+/// Every entry in the bibliographies of the project at `root`.
+///
+/// Uses the `bibliography:` files from `_quarto.yml` when the project lists
+/// any, then those in the front matter of its documents, and otherwise every
+/// `.bib` file and CSL-JSON `.json` file under `root`.
+pub fn project_entries(root: &Path) -> Result<Vec<Entry>, String> {
+    let mut configured = match Project::discover(root, None)? {
+        Some(project) => project.bibliography_paths(),
+        None => Vec::new(),
+    };
+    if configured.is_empty() {
+        configured = front_matter_paths(root);
+    }
+
+    let mut entries = Vec::new();
+    if configured.is_empty() {
+        for (path, text) in project::files(root, &["bib", "json"]) {
+            // Other JSON files in the project are not bibliographies.
+            if path.ends_with(".json") && !is_csl_json(&text) {
+                continue;
+            }
+            if let Ok(found) = load(&path, &text) {
+                entries.extend(found);
+            }
+        }
+    } else {
+        for path in configured {
+            let text = fs::read_to_string(&path)
+                .map_err(|e| format!("Could not read bibliography {}: {}", path.display(), e))?;
+            entries.extend(load(&path.to_string_lossy(), &text)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// `bibliography:` files named in the front matter of the documents under
+/// `root`, resolved against each document's directory.
+fn front_matter_paths(root: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = project::files(root, &project::SOURCE_EXTENSIONS)
+        .into_iter()
+        .flat_map(|(document, text)| {
+            let directory = root.join(&document);
+            let directory = directory.parent().unwrap_or(root).to_path_buf();
+            let files = match project::front_matter(&text).map(|yaml| yaml["bibliography"].clone())
+            {
+                Some(YamlValue::String(file)) => vec![file],
+                Some(YamlValue::Sequence(files)) => files
+                    .iter()
+                    .filter_map(|file| file.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            files.into_iter().map(move |file| directory.join(file))
+        })
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Whether `text` is a CSL-JSON bibliography: a non-empty array of items that
+/// each have an `id` and a `type`.
+fn is_csl_json(text: &str) -> bool {
+    serde_json::from_str::<Vec<Value>>(text).is_ok_and(|items| {
+        !items.is_empty()
+            && items
+                .iter()
+                .all(|item| !item["id"].is_null() && item["type"].is_string())
+    })
+}
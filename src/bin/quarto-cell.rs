@@ -0,0 +1,59 @@
+//! Prints the code of the executable cell at a row of a Quarto document.
+//!
+//! Used by the runnable tasks in `languages/quarto/tasks.json`, which pipe the
+//! output into the cell's interpreter:
+//!
+//! ```text
+//! quarto-cell <file> <row> [--above]
+//! ```
+//!
+//! `row` is one-based, as in Zed's `$ZED_ROW`, and may be any line of the cell
+//! from its opening fence to its closing fence. Option lines (`#| ...`) are
+//! left out. With `--above`, prints every cell above `row` that uses the same
+//! kernel instead, for "run all above".
+
+use std::process::ExitCode;
+
+use quarto_zed::repl::{self, RunScope};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => {
+            print!("{}", code);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("quarto-cell: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    let (file, row, above) = match args {
+        [file, row] => (file, row, false),
+        [file, row, flag] if flag == "--above" => (file, row, true),
+        _ => return Err("usage: quarto-cell <file> <row> [--above]".to_string()),
+    };
+    let row: usize = row
+        .parse()
+        .ok()
+        .filter(|&row| row > 0)
+        .ok_or_else(|| format!("invalid row '{}'", row))?;
+
+    let text =
+        std::fs::read_to_string(file).map_err(|e| format!("could not read {}: {}", file, e))?;
+
+    if above {
+        let plan = repl::plan(&text, row - 1, RunScope::AllAbove)
+            .ok_or_else(|| format!("no executable cells above {}:{}", file, row))?;
+        return Ok(plan.runs.into_iter().map(|run| run.code).collect());
+    }
+
+    let cell = quarto_zed::cells::cell_at(&text, row - 1)
+        .ok_or_else(|| format!("no executable cell at {}:{}", file, row))?;
+
+    let lines: Vec<&str> = text.lines().collect();
+    Ok(cell.code(&lines))
+}
@@ -0,0 +1,22 @@
+//! Quarto language server, started by the extension over stdio.
+//!
+//! `quarto-ls project|slash|complete …` instead runs a slash command for the
+//! extension and prints its JSON output (see `quarto_zed::slash`).
+
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = quarto_zed::slash::main(&args) {
+        match result {
+            Ok(json) => println!("{}", json),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    quarto_zed::lsp::run(stdin.lock(), stdout.lock())
+}
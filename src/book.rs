@@ -0,0 +1,450 @@
+//! The reading order of a `type: book` project for `/quarto-book` and the
+//! language server's workspace symbols.
+//!
+//! Chapters come from `book: chapters:` and `appendices:` in `_quarto.yml` and
+//! are numbered the way Quarto numbers them: chapters count up across parts,
+//! appendices are lettered, and `{.unnumbered}` headings take no number.
+
+use std::ops::Range as ByteRange;
+use std::path::Path;
+
+use crate::outline::{self, EntryKind};
+use crate::project::{self, Chapter, Project};
+use crate::text::{Diagnostic, Range, Severity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    /// `- part:` with a title or a part page.
+    Part,
+    Chapter,
+    Appendix,
+}
+
+/// A heading inside a chapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub title: String,
+    pub level: usize,
+    /// Zero-based.
+    pub line: usize,
+    /// `2.1`, `A.3`; `None` for unnumbered headings.
+    pub number: Option<String>,
+}
+
+/// A part or chapter in reading order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// Path relative to the project root; `None` for a part that only has a title.
+    pub path: Option<String>,
+    pub title: String,
+    /// Zero-based line of the title heading.
+    pub line: usize,
+    /// `3` for chapters and `C` for appendices; `None` when unnumbered.
+    pub number: Option<String>,
+    pub sections: Vec<Section>,
+    /// Listed in `_quarto.yml` but not found on disk.
+    pub missing: bool,
+    /// Listed under a `- part:`.
+    pub in_part: bool,
+}
+
+impl Item {
+    /// `3 Regression`, or just the title when unnumbered.
+    pub fn heading(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{} {}", number, self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contents {
+    pub title: Option<String>,
+    pub items: Vec<Item>,
+    /// `.qmd` files in the project that no chapter list mentions.
+    pub unlisted: Vec<String>,
+}
+
+impl Contents {
+    pub fn missing(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter().filter(|item| item.missing)
+    }
+}
+
+/// The table of contents of a book project, or `None` for other projects.
+///
+/// `read` returns the text of a chapter given its path relative to the root,
+/// so open documents can be used instead of what is saved on disk.
+pub fn contents(project: &Project, read: impl Fn(&str) -> Option<String>) -> Option<Contents> {
+    let book = project.book.as_ref()?;
+    let mut numbering = Numbering::new(project);
+    let mut items = Vec::new();
+
+    for (entries, appendix) in [(&book.chapters, false), (&book.appendices, true)] {
+        for entry in entries {
+            match entry {
+                Chapter::File(path) => {
+                    items.push(numbering.chapter(path, read(path), appendix, false));
+                }
+                Chapter::Part { part, chapters } => {
+                    items.push(part_item(part, &read));
+                    for path in chapters {
+                        items.push(numbering.chapter(path, read(path), appendix, true));
+                    }
+                }
+            }
+        }
+    }
+
+    let unlisted = project::files(&project.root, &["qmd"])
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| is_unlisted(project, path))
+        .collect();
+
+    Some(Contents {
+        title: book.title.clone(),
+        items,
+        unlisted,
+    })
+}
+
+/// Whether `relative` is a `.qmd` file of a book project that neither the
+/// chapters nor the appendices list. Files and directories starting with `_`
+/// or `.` and the output directory are not part of the book.
+pub fn is_unlisted(project: &Project, relative: &str) -> bool {
+    let Some(book) = &project.book else {
+        return false;
+    };
+    let output_dir = project.output_dir();
+    relative.ends_with(".qmd")
+        && !book.files().contains(&relative)
+        && !relative
+            .split('/')
+            .any(|part| part.starts_with(['_', '.']) || Some(part) == output_dir)
+}
+
+/// Reads chapters from disk, relative to the project root.
+pub fn read_from(root: &Path) -> impl Fn(&str) -> Option<String> + '_ {
+    move |path| std::fs::read_to_string(root.join(path)).ok()
+}
+
+/// Chapter and section counters, following Quarto's book numbering.
+struct Numbering {
+    enabled: bool,
+    depth: Option<usize>,
+    chapters: usize,
+    appendices: usize,
+}
+
+impl Numbering {
+    fn new(project: &Project) -> Self {
+        Self {
+            enabled: project.number_sections,
+            depth: project.number_depth,
+            chapters: 0,
+            appendices: 0,
+        }
+    }
+
+    fn chapter(&mut self, path: &str, text: Option<String>, appendix: bool, in_part: bool) -> Item {
+        let kind = if appendix {
+            ItemKind::Appendix
+        } else {
+            ItemKind::Chapter
+        };
+        let Some(text) = text else {
+            return Item {
+                kind,
+                path: Some(path.to_string()),
+                title: file_title(path),
+                line: 0,
+                number: None,
+                sections: Vec::new(),
+                missing: true,
+                in_part,
+            };
+        };
+
+        let headings = headings(&text);
+        let title_heading = headings.iter().position(|heading| heading.level == 1);
+        let (title, line, unnumbered) = match title_heading {
+            Some(index) => {
+                let heading = &headings[index];
+                (heading.title.clone(), heading.line, heading.unnumbered)
+            }
+            None => (
+                front_matter_title(&text).unwrap_or_else(|| file_title(path)),
+                0,
+                false,
+            ),
+        };
+
+        let number = (self.enabled && !unnumbered).then(|| {
+            if appendix {
+                self.appendices += 1;
+                appendix_letter(self.appendices)
+            } else {
+                self.chapters += 1;
+                self.chapters.to_string()
+            }
+        });
+
+        // Deeper headings count from the chapter number: 2.1, 2.1.1, …
+        let mut counters: Vec<usize> = Vec::new();
+        let mut sections = Vec::new();
+        for (index, heading) in headings.into_iter().enumerate() {
+            if Some(index) == title_heading {
+                continue;
+            }
+            let level = heading.level.max(2);
+            let within_depth = self.depth.is_none_or(|depth| level <= depth);
+            let section_number = match &number {
+                Some(chapter) if !heading.unnumbered && within_depth => {
+                    counters.resize(level - 1, 0);
+                    counters[level - 2] += 1;
+                    let parts: Vec<String> = counters.iter().map(usize::to_string).collect();
+                    Some(format!("{}.{}", chapter, parts.join(".")))
+                }
+                _ => None,
+            };
+            sections.push(Section {
+                title: heading.title,
+                level,
+                line: heading.line,
+                number: section_number,
+            });
+        }
+
+        Item {
+            kind,
+            path: Some(path.to_string()),
+            title,
+            line,
+            number,
+            sections,
+            missing: false,
+            in_part,
+        }
+    }
+}
+
+/// A part is either a title or a page whose first heading is the title.
+fn part_item(part: &str, read: &impl Fn(&str) -> Option<String>) -> Item {
+    let mut item = Item {
+        kind: ItemKind::Part,
+        path: None,
+        title: part.to_string(),
+        line: 0,
+        number: None,
+        sections: Vec::new(),
+        missing: false,
+        in_part: false,
+    };
+    if part.ends_with(".qmd") || part.ends_with(".md") {
+        item.path = Some(part.to_string());
+        match read(part) {
+            Some(text) => {
+                let heading = headings(&text).into_iter().find(|h| h.level == 1);
+                item.line = heading.as_ref().map_or(0, |heading| heading.line);
+                item.title = heading
+                    .map(|heading| heading.title)
+                    .or_else(|| front_matter_title(&text))
+                    .unwrap_or_else(|| file_title(part));
+            }
+            None => {
+                item.title = file_title(part);
+                item.missing = true;
+            }
+        }
+    }
+    item
+}
+
+struct Heading {
+    title: String,
+    level: usize,
+    line: usize,
+    unnumbered: bool,
+}
+
+fn headings(text: &str) -> Vec<Heading> {
+    outline::outline(text)
+        .into_iter()
+        .filter_map(|entry| match entry.kind {
+            EntryKind::Heading {
+                level, unnumbered, ..
+            } => Some(Heading {
+                title: entry.title.unwrap_or_default(),
+                level,
+                line: entry.start_line,
+                unnumbered,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `title:` from the document's YAML front matter.
+fn front_matter_title(text: &str) -> Option<String> {
+    project::front_matter(text)?["title"]
+        .as_str()
+        .map(str::to_string)
+}
+
+/// `chapters/data-cleaning.qmd` gives `data-cleaning`.
+fn file_title(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.')
+        .map_or(name, |(stem, _)| stem)
+        .to_string()
+}
+
+/// `A`, …, `Z`, then `AA`, `AB`, …
+fn appendix_letter(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    while index > 0 {
+        index -= 1;
+        letters.push(char::from(b'A' + (index % 26) as u8));
+        index /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// The book in reading order, one section per part and chapter, followed by
+/// missing and unlisted files.
+pub fn render(contents: &Contents) -> (String, Vec<(ByteRange<usize>, String)>) {
+    let mut text = String::new();
+    let mut sections = Vec::new();
+    if let Some(title) = &contents.title {
+        text.push_str(&format!("{}\n\n", title));
+    }
+
+    let mut in_appendices = false;
+    for item in &contents.items {
+        if item.kind == ItemKind::Appendix && !in_appendices {
+            in_appendices = true;
+            text.push_str("Appendices\n");
+        }
+        let start = text.len();
+        let indent = if item.in_part || item.kind == ItemKind::Appendix {
+            "  "
+        } else {
+            ""
+        };
+        let heading = match item.kind {
+            ItemKind::Part => format!("Part: {}", item.title),
+            _ => item.heading(),
+        };
+        let location = match (&item.path, item.missing) {
+            (Some(path), true) => format!(" ({}, missing)", path),
+            (Some(path), false) => format!(" ({})", path),
+            (None, _) => String::new(),
+        };
+        text.push_str(&format!("{}{}{}\n", indent, heading, location));
+        for section in &item.sections {
+            let heading = match &section.number {
+                Some(number) => format!("{} {}", number, section.title),
+                None => section.title.clone(),
+            };
+            text.push_str(&format!(
+                "{}{}{} (line {})\n",
+                indent,
+                "  ".repeat(section.level - 1),
+                heading,
+                section.line + 1
+            ));
+        }
+        sections.push((start..text.len(), heading));
+    }
+
+    let missing: Vec<&Item> = contents.missing().collect();
+    if !missing.is_empty() {
+        text.push_str("\nMissing chapters (listed but not found):\n");
+        for item in missing {
+            text.push_str(&format!("- {}\n", item.path.as_deref().unwrap_or_default()));
+        }
+    }
+    if !contents.unlisted.is_empty() {
+        text.push_str("\nNot in the book (not listed in chapters or appendices):\n");
+        for path in &contents.unlisted {
+            text.push_str(&format!("- {}\n", path));
+        }
+    }
+    (text, sections)
+}
+
+/// Diagnostics for `_quarto.yml`: chapters that do not exist, on the entry
+/// that lists them, and unlisted `.qmd` files, on the listed chapter they
+/// would follow (the nearest one from the same directory, else the last
+/// chapter).
+pub fn config_diagnostics(contents: &Contents, config: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = config.lines().collect();
+    let entry = |path: &str| {
+        lines.iter().enumerate().find_map(|(index, line)| {
+            let found = project::config_value_range(line, &["file", "href", "part"], path)?;
+            Some(Range::within_line(index, line, found.start, found.end))
+        })
+    };
+    let mut diagnostics = Vec::new();
+
+    for item in contents.missing() {
+        let path = item.path.as_deref().unwrap_or_default();
+        diagnostics.push(Diagnostic {
+            range: entry(path).unwrap_or_else(|| Range::at_line_start(0)),
+            severity: Severity::Warning,
+            code: "missing-chapter",
+            message: format!("{} is listed in the book but does not exist", path),
+        });
+    }
+
+    let listed: Vec<(&str, Range)> = contents
+        .items
+        .iter()
+        .filter(|item| item.kind != ItemKind::Appendix)
+        .filter_map(|item| {
+            let path = item.path.as_deref()?;
+            Some((path, entry(path)?))
+        })
+        .collect();
+    for path in &contents.unlisted {
+        let siblings: Vec<&(&str, Range)> = listed
+            .iter()
+            .filter(|(listed, _)| directory(listed) == directory(path))
+            .collect();
+        let neighbour = siblings
+            .iter()
+            .filter(|(listed, _)| *listed < path.as_str())
+            .max_by_key(|(listed, _)| *listed)
+            .or_else(|| siblings.first())
+            .copied()
+            .or_else(|| listed.last());
+        let (range, message) = match neighbour {
+            Some((listed, range)) => (
+                *range,
+                format!(
+                    "{} is not listed in the book's chapters or appendices; it would fit next to {}",
+                    path, listed
+                ),
+            ),
+            None => (
+                Range::at_line_start(0),
+                format!("{} is not listed in the book's chapters or appendices", path),
+            ),
+        };
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Severity::Information,
+            code: "unlisted-chapter",
+            message,
+        });
+    }
+    diagnostics
+}
+
+/// The directory part of a project-relative path, `""` at the root.
+fn directory(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
@@ -0,0 +1,284 @@
+//! Callout blocks (`::: {.callout-note}`): fence matching, diagnostics and edits.
+//!
+//! tree-sitter-quarto parses callouts as `fenced_div` nodes (see
+//! `tests/fenced_divs.rs`); this module follows the same fence rules on plain
+//! text so it can run inside the language server without the C grammar.
+
+use crate::attributes::Attributes;
+use crate::text::{verbatim_lines, CodeAction, Diagnostic, Range, Severity, TextEdit};
+
+/// Callout types Quarto renders.
+pub const CALLOUT_TYPES: [&str; 5] = ["note", "tip", "warning", "caution", "important"];
+
+/// A `:::` fenced div and the closing fence that matches it, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FencedDiv {
+    pub open_line: usize,
+    pub close_line: Option<usize>,
+    /// Number of colons in the opening fence.
+    pub colons: usize,
+    pub indent: String,
+    pub attributes: Attributes,
+}
+
+impl FencedDiv {
+    /// Whether `line` lies between the fences (inclusive); unclosed divs run to
+    /// the end of the document.
+    pub fn contains(&self, line: usize) -> bool {
+        line >= self.open_line && self.close_line.is_none_or(|close| line <= close)
+    }
+
+    /// The opening fence rebuilt around `attributes`.
+    fn opening_fence(&self, attributes: &Attributes) -> String {
+        format!("{}{} {}", self.indent, ":".repeat(self.colons), attributes)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FenceScan {
+    pub divs: Vec<FencedDiv>,
+    /// Closing fences with no open div to close.
+    pub stray_closes: Vec<usize>,
+}
+
+/// Matches `:::` fences the way Pandoc does: a bare fence closes the innermost
+/// open div regardless of colon count.
+pub fn fenced_divs(text: &str) -> FenceScan {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut scan = FenceScan::default();
+    let mut stack: Vec<usize> = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        let Some((indent, colons, rest)) = div_fence(line) else {
+            continue;
+        };
+
+        if rest.is_empty() {
+            match stack.pop() {
+                Some(open) => scan.divs[open].close_line = Some(index),
+                None => scan.stray_closes.push(index),
+            }
+        } else {
+            stack.push(scan.divs.len());
+            scan.divs.push(FencedDiv {
+                open_line: index,
+                close_line: None,
+                colons,
+                indent: indent.to_string(),
+                attributes: Attributes::parse(rest.trim_end_matches(':').trim_end()),
+            });
+        }
+    }
+
+    scan
+}
+
+/// Splits a fence line into indentation, colon count and the text after the colons.
+fn div_fence(line: &str) -> Option<(&str, usize, &str)> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    if indent.len() > 3 {
+        return None;
+    }
+    let colons = trimmed.chars().take_while(|&c| c == ':').count();
+    if colons < 3 {
+        return None;
+    }
+    Some((indent, colons, trimmed[colons..].trim()))
+}
+
+/// A fenced div carrying a `callout-*` class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callout {
+    pub div: FencedDiv,
+    /// The part after `callout-`, which may not be a known type.
+    pub kind: String,
+}
+
+impl Callout {
+    pub fn is_collapsed(&self) -> bool {
+        self.div.attributes.get("collapse") == Some("true")
+    }
+
+    /// A callout has a title when it sets `title=` or opens with a heading.
+    pub fn has_title(&self, lines: &[&str]) -> bool {
+        if self.div.attributes.get("title").is_some() {
+            return true;
+        }
+        lines
+            .iter()
+            .skip(self.div.open_line + 1)
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| line.trim_start().starts_with('#'))
+    }
+}
+
+fn callout_kind(attributes: &Attributes) -> Option<&str> {
+    attributes
+        .classes
+        .iter()
+        .find_map(|class| class.strip_prefix("callout-"))
+}
+
+pub fn callouts(text: &str) -> Vec<Callout> {
+    fenced_divs(text)
+        .divs
+        .into_iter()
+        .filter_map(|div| {
+            let kind = callout_kind(&div.attributes)?.to_string();
+            Some(Callout { div, kind })
+        })
+        .collect()
+}
+
+/// Unknown callout types and unbalanced `:::` fences.
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = text.lines().collect();
+    let scan = fenced_divs(text);
+    let mut diagnostics = Vec::new();
+
+    for div in &scan.divs {
+        let range = Range::whole_line(div.open_line, lines[div.open_line]);
+        if let Some(kind) = callout_kind(&div.attributes) {
+            if !CALLOUT_TYPES.contains(&kind) {
+                diagnostics.push(Diagnostic {
+                    range,
+                    severity: Severity::Warning,
+                    code: "unknown-callout",
+                    message: format!(
+                        "Unknown callout type `callout-{kind}`; expected one of {}",
+                        CALLOUT_TYPES.map(|t| format!("callout-{t}")).join(", ")
+                    ),
+                });
+            }
+        }
+        if div.close_line.is_none() {
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Error,
+                code: "unclosed-fence",
+                message: "Fenced div is never closed with `:::`".to_string(),
+            });
+        }
+    }
+
+    for &line in &scan.stray_closes {
+        diagnostics.push(Diagnostic {
+            range: Range::whole_line(line, lines[line]),
+            severity: Severity::Error,
+            code: "unmatched-fence",
+            message: "Closing `:::` has no matching opening fence".to_string(),
+        });
+    }
+
+    diagnostics.sort_by_key(|d| d.range.start);
+    diagnostics
+}
+
+/// Callout actions available for `range`: wrapping a selection, and changing
+/// the type, collapse state or title of the innermost enclosing callout.
+pub fn code_actions(text: &str, range: Range) -> Vec<CodeAction> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut actions = Vec::new();
+
+    if range.start != range.end {
+        let start = range.start.line as usize;
+        let mut end = range.end.line as usize;
+        // A selection ending at column 0 does not include that line.
+        if range.end.character == 0 && end > start {
+            end -= 1;
+        }
+        for kind in CALLOUT_TYPES {
+            actions.push(CodeAction {
+                title: format!("Wrap in callout-{kind}"),
+                kind: "refactor.rewrite",
+                edits: wrap_lines(text, &lines, start, end, kind),
+            });
+        }
+    }
+
+    let line = range.start.line as usize;
+    let Some(callout) = callouts(text)
+        .into_iter()
+        .filter(|callout| callout.div.contains(line))
+        .max_by_key(|callout| callout.div.open_line)
+    else {
+        return actions;
+    };
+
+    let open = callout.div.open_line;
+    let fence_range = Range::whole_line(open, lines[open]);
+
+    for kind in CALLOUT_TYPES.iter().filter(|kind| **kind != callout.kind) {
+        let mut attributes = callout.div.attributes.clone();
+        for class in attributes.classes.iter_mut() {
+            if class.starts_with("callout-") {
+                *class = format!("callout-{kind}");
+            }
+        }
+        actions.push(CodeAction {
+            title: format!("Change to callout-{kind}"),
+            kind: "refactor.rewrite",
+            edits: vec![TextEdit::replace(
+                fence_range,
+                callout.div.opening_fence(&attributes),
+            )],
+        });
+    }
+
+    let mut attributes = callout.div.attributes.clone();
+    let title = if callout.is_collapsed() {
+        attributes.remove("collapse");
+        "Expand callout by default"
+    } else {
+        attributes.set("collapse", "true");
+        "Collapse callout by default"
+    };
+    actions.push(CodeAction {
+        title: title.to_string(),
+        kind: "refactor.rewrite",
+        edits: vec![TextEdit::replace(
+            fence_range,
+            callout.div.opening_fence(&attributes),
+        )],
+    });
+
+    if !callout.has_title(&lines) {
+        actions.push(CodeAction {
+            title: "Add callout title".to_string(),
+            kind: "refactor.rewrite",
+            edits: vec![TextEdit::insert(
+                open + 1,
+                format!("{}## {}\n", callout.div.indent, capitalize(&callout.kind)),
+            )],
+        });
+    }
+
+    actions
+}
+
+/// Edits that surround lines `start..=end` with a callout of `kind`.
+fn wrap_lines(text: &str, lines: &[&str], start: usize, end: usize, kind: &str) -> Vec<TextEdit> {
+    let open = TextEdit::insert(start, format!("::: {{.callout-{kind}}}\n"));
+    let close = if end + 1 < lines.len() || text.ends_with('\n') {
+        TextEdit::insert(end + 1, ":::\n")
+    } else {
+        // Last line without a trailing newline: append after its content.
+        let last = lines.get(end).copied().unwrap_or_default();
+        let eol = Range::within_line(end, last, last.len(), last.len());
+        TextEdit::replace(eol, "\n:::")
+    };
+    vec![open, close]
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
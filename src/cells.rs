@@ -0,0 +1,207 @@
+//! Executable code cells (```` ```{python} ````) and their `#|` option lines.
+//!
+//! Mirrors the grammar's `executable_code_cell` node: a backtick fence whose info
+//! string is a braced engine name, optional `#|` options, then `cell_content`.
+//! Cells are scanned from the text because the WASM extension has no grammar
+//! to parse with; `tests/outline.rs` checks they start where the grammar's
+//! cells do.
+
+use std::ops::Range as LineRange;
+
+use crate::knitr;
+
+/// One `#| key: value` option at the top of a cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellOption {
+    pub line: usize,
+    /// Last line of a value continued over several `#|` lines.
+    pub end_line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub open_line: usize,
+    /// `None` when the cell runs to the end of the document.
+    pub close_line: Option<usize>,
+    /// The backtick run that opened the cell.
+    pub fence: String,
+    /// Engine name as written, e.g. `python` or `r`.
+    pub language: String,
+    /// Anything after the engine name inside the braces, e.g. `setup, include=FALSE`.
+    pub header: String,
+    pub options: Vec<CellOption>,
+}
+
+impl Cell {
+    /// Lines between the fences: option lines followed by the code.
+    pub fn content_lines(&self, line_count: usize) -> LineRange<usize> {
+        self.open_line + 1..self.close_line.unwrap_or(line_count)
+    }
+
+    /// Lines of code after the `#|` option block.
+    pub fn code_lines(&self, line_count: usize) -> LineRange<usize> {
+        let content = self.content_lines(line_count);
+        let start = self
+            .options
+            .last()
+            .map_or(content.start, |option| option.end_line + 1);
+        start..content.end
+    }
+
+    /// The code without option lines, joined with newlines.
+    pub fn code(&self, lines: &[&str]) -> String {
+        let mut code = lines[self.code_lines(lines.len())].join("\n");
+        if !code.is_empty() {
+            code.push('\n');
+        }
+        code
+    }
+
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|option| option.key == key)
+            .map(|option| option.value.as_str())
+    }
+
+    /// A `#|` option, or else the same option in an R Markdown style header
+    /// (```` ```{r, fig.cap="Sales"} ```` for `fig-cap`) as its raw R value.
+    pub fn option_or_header(&self, key: &str) -> Option<&str> {
+        self.option(key)
+            .or_else(|| knitr::header_option(&self.header, &knitr::knitr_option_name(key)))
+    }
+
+    /// The `#| label`, or the label of a knitr header such as ```` ```{r setup} ````.
+    pub fn label(&self) -> Option<&str> {
+        self.option_or_header("label")
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        line >= self.open_line && self.close_line.is_none_or(|close| line <= close)
+    }
+}
+
+/// Comment prefix Quarto expects for option lines in `language` cells.
+pub fn option_prefix(language: &str) -> &'static str {
+    match language {
+        "ojs" | "js" | "javascript" | "typescript" | "ts" | "mermaid" | "dot" => "//|",
+        "sql" => "--|",
+        _ => "#|",
+    }
+}
+
+/// All executable cells in `text`, in document order.
+pub fn cells(text: &str) -> Vec<Cell> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut cells = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let Some(fence_len) = backtick_fence(line) else {
+            index += 1;
+            continue;
+        };
+
+        let close_line = lines
+            .iter()
+            .enumerate()
+            .skip(index + 1)
+            .find(|(_, l)| {
+                let trimmed = l.trim();
+                backtick_fence(l) >= Some(fence_len) && trimmed.trim_matches('`').is_empty()
+            })
+            .map(|(i, _)| i);
+
+        if let Some((language, header)) = cell_info(&line.trim()[fence_len..]) {
+            let end = close_line.unwrap_or(lines.len());
+            let prefix = option_prefix(&language);
+            let options = parse_options(&lines, index + 1..end, prefix);
+
+            cells.push(Cell {
+                open_line: index,
+                close_line,
+                fence: "`".repeat(fence_len),
+                language,
+                header,
+                options,
+            });
+        }
+
+        // Skip the whole block so fences inside code are not mistaken for cells.
+        index = close_line.map_or(lines.len(), |close| close + 1);
+    }
+
+    cells
+}
+
+/// The cell whose fences enclose `line` (zero-based), if any.
+pub fn cell_at(text: &str, line: usize) -> Option<Cell> {
+    cells(text).into_iter().find(|cell| cell.contains(line))
+}
+
+/// Number of leading backticks when `line` starts a backtick fence.
+fn backtick_fence(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let run = trimmed.chars().take_while(|&c| c == '`').count();
+    (run >= 3).then_some(run)
+}
+
+/// Splits `{python}` or `{r setup, echo=FALSE}` into engine and remaining header.
+///
+/// Returns `None` for plain or attribute fences (`{.python}`) and for `{{r}}`,
+/// which Quarto displays verbatim instead of executing.
+fn cell_info(info: &str) -> Option<(String, String)> {
+    let inner = info.trim().strip_prefix('{')?.strip_suffix('}')?;
+    if inner.starts_with('{') {
+        return None;
+    }
+    let inner = inner.trim_start();
+    let end = inner
+        .find(|c: char| c.is_whitespace() || c == ',' || c == '}')
+        .unwrap_or(inner.len());
+    let language = &inner[..end];
+    if language.is_empty()
+        || !language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return None;
+    }
+    let header = inner[end..].trim_start_matches(',').trim();
+    Some((language.to_string(), header.to_string()))
+}
+
+/// Reads the option block at the top of a cell. Indented or list lines continue
+/// the previous option's value, as in multi-line YAML.
+fn parse_options(lines: &[&str], range: LineRange<usize>, prefix: &str) -> Vec<CellOption> {
+    let mut options: Vec<CellOption> = Vec::new();
+    for index in range {
+        let Some(rest) = lines[index].trim_start().strip_prefix(prefix) else {
+            break;
+        };
+        let continuation = rest.starts_with("  ") || rest.trim_start().starts_with("- ");
+        match (continuation, options.last_mut(), rest.split_once(':')) {
+            (true, Some(last), _) => {
+                if !last.value.is_empty() {
+                    last.value.push('\n');
+                }
+                last.value.push_str(rest.trim());
+                last.end_line = index;
+            }
+            (false, _, Some((key, value))) => options.push(CellOption {
+                line: index,
+                end_line: index,
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }),
+            _ => break,
+        }
+    }
+    options
+}
@@ -0,0 +1,116 @@
+//! Running a labelled cell for the `/quarto-run-cell <file>#<label>` slash command.
+//!
+//! The extension runs inside Zed's WASM sandbox, where a process cannot be given
+//! a working directory or stdin. Each interpreter is therefore started with a
+//! fixed prelude (`python3 -c <prelude> <directory> <code>`) that changes into
+//! the directory and then runs the code. `extension.toml` allows exactly these
+//! preludes, so the extension can start nothing but the four interpreters.
+
+use std::ops::Range as ByteRange;
+
+use crate::cells::{self, Cell};
+use crate::text::fence_for;
+
+/// Runs `sys.argv[2]` as `__main__` from the directory in `sys.argv[1]`.
+pub const PYTHON_PRELUDE: &str = "import os, sys; os.chdir(sys.argv[1]); code = sys.argv[2]; \
+     del sys.argv[1:]; exec(compile(code, '<cell>', 'exec'), {'__name__': '__main__'})";
+
+/// Runs the second argument from the directory in the first, printing visible
+/// values as `Rscript -e` does.
+pub const R_PRELUDE: &str = "local({ args <- commandArgs(TRUE); setwd(args[1]); \
+     for (e in parse(text = args[2])) { v <- withVisible(eval(e, globalenv())); \
+     if (v$visible) print(v$value) } })";
+
+/// Runs `ARGS[2]` in `Main` from the directory in `ARGS[1]`.
+pub const JULIA_PRELUDE: &str = "let dir = ARGS[1], code = ARGS[2]; empty!(ARGS); cd(dir); \
+     include_string(Main, code, \"cell\") end";
+
+/// Runs `$2` from the directory in `$1`; `$0` is the name in error messages.
+pub const BASH_PRELUDE: &str = "cd \"$1\" && eval \"$2\"";
+
+/// Splits `report.qmd#fig-plot` into the file and the label.
+pub fn parse_target(target: &str) -> Result<(&str, &str), String> {
+    match target.rsplit_once('#') {
+        Some((file, label)) if !file.is_empty() && !label.is_empty() => Ok((file, label)),
+        _ => Err(format!(
+            "Expected <file>#<label>, e.g. analysis.qmd#fig-plot, got '{}'",
+            target
+        )),
+    }
+}
+
+/// Every `#| label:` in `text`, in document order.
+pub fn labels(text: &str) -> Vec<String> {
+    cells::cells(text)
+        .iter()
+        .filter_map(|cell| cell.label().map(str::to_string))
+        .collect()
+}
+
+/// The cell labelled `label`.
+pub fn find_cell(text: &str, label: &str) -> Result<Cell, String> {
+    cells::cells(text)
+        .into_iter()
+        .find(|cell| cell.label() == Some(label))
+        .ok_or_else(|| format!("No cell labelled '{}'", label))
+}
+
+/// Interpreter for an engine and the fixed arguments that come before the
+/// directory and the code: the flag that takes code, the prelude, and for
+/// bash the `$0` it runs as.
+pub fn interpreter(language: &str) -> Option<(&'static str, &'static [&'static str])> {
+    match language.to_lowercase().as_str() {
+        "python" | "python3" => Some(("python3", &["-c", PYTHON_PRELUDE])),
+        "r" => Some(("Rscript", &["-e", R_PRELUDE])),
+        "julia" => Some(("julia", &["-e", JULIA_PRELUDE])),
+        "bash" | "sh" => Some(("bash", &["-c", BASH_PRELUDE, "bash"])),
+        _ => None,
+    }
+}
+
+/// Program and arguments that run `code` as `language` from `directory`.
+pub fn run_command(
+    directory: &str,
+    language: &str,
+    code: &str,
+) -> Result<(String, Vec<String>), String> {
+    let (program, prelude) = interpreter(language)
+        .ok_or_else(|| format!("Running {} cells is not supported", language))?;
+    let args = prelude
+        .iter()
+        .chain([&directory, &code])
+        .map(|arg| arg.to_string())
+        .collect();
+    Ok((program.to_string(), args))
+}
+
+/// Slash command text for a finished run, with one labelled byte range each
+/// for stdout, stderr and the exit status.
+pub fn format_output(
+    label: &str,
+    stdout: &str,
+    stderr: &str,
+    status: Option<i32>,
+) -> (String, Vec<(ByteRange<usize>, String)>) {
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for (name, stream) in [("stdout", stdout), ("stderr", stderr)] {
+        let start = text.len();
+        let body = stream.trim_end();
+        let fence = fence_for(body);
+        text.push_str(&format!("{fence}\n{body}\n{fence}\n\n"));
+        sections.push((start..text.len() - 1, format!("{}: {}", label, name)));
+    }
+
+    let start = text.len();
+    let status = match status {
+        Some(code) => format!("Exit status: {}", code),
+        None => "Exit status: terminated by signal".to_string(),
+    };
+    text.push_str(&status);
+    text.push('\n');
+    sections.push((start..text.len(), format!("{}: {}", label, status)));
+
+    (text, sections)
+}
@@ -0,0 +1,474 @@
+//! Footnotes: `[^label]` references, `[^label]: text` notes and inline
+//! `^[text]` notes.
+//!
+//! `tags.scm` registers `footnote_definition` nodes for Zed's outline; this
+//! module adds what needs both ends of a note: navigation between reference
+//! and note, diagnostics for notes missing on either side, renumbering and
+//! conversion between inline and reference-style notes.
+
+use crate::text::{
+    byte_offset, closing_delimiter, outside_code_spans, utf16_len, verbatim_lines, CodeAction,
+    Diagnostic, Position, Range, Severity, TextEdit,
+};
+
+/// A `[^label]` reference in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub label: String,
+    /// Zero-based line and the byte range of `[^label]`.
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A `[^label]: text` note, with its indented continuation lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub label: String,
+    pub line: usize,
+    /// Byte range of `[^label]` on the first line.
+    pub start: usize,
+    pub end: usize,
+    /// Last line of the note, continuation paragraphs included.
+    pub end_line: usize,
+    /// The note's text with continuation lines joined by newlines, indentation removed.
+    pub content: String,
+}
+
+impl Note {
+    /// Whether the note is a single paragraph that fits in `^[...]`.
+    pub fn is_single_paragraph(&self) -> bool {
+        !self.content.contains("\n\n")
+    }
+}
+
+/// An inline `^[text]` note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineNote {
+    pub content: String,
+    pub line: usize,
+    /// Byte range of `^[text]`.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Footnote notes in document order.
+pub fn notes(text: &str) -> Vec<Note> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut notes = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        let Some((label, start, end)) = note_marker(line) else {
+            continue;
+        };
+        let mut content = line[end + 1..].trim().to_string();
+        let mut end_line = index;
+        let mut paragraph_break = false;
+        let mut next = index + 1;
+        while next < lines.len() {
+            let candidate = lines[next];
+            let indented = candidate.starts_with("    ") || candidate.starts_with('\t');
+            if candidate.trim().is_empty() {
+                // A blank line ends the note unless an indented paragraph follows.
+                let resumes = lines[next..]
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .is_some_and(|line| line.starts_with("    ") || line.starts_with('\t'));
+                if !resumes {
+                    break;
+                }
+                paragraph_break = true;
+            } else if indented || (end_line == next - 1 && note_marker(candidate).is_none()) {
+                if !content.is_empty() {
+                    content.push_str(if paragraph_break { "\n\n" } else { "\n" });
+                }
+                content.push_str(candidate.trim());
+                paragraph_break = false;
+                end_line = next;
+            } else {
+                break;
+            }
+            next += 1;
+        }
+        notes.push(Note {
+            label,
+            line: index,
+            start,
+            end,
+            end_line,
+            content,
+        });
+    }
+    notes
+}
+
+/// `[^label]:` at the start of a line: the label and the byte range of
+/// `[^label]`.
+fn note_marker(line: &str) -> Option<(String, usize, usize)> {
+    let indent = line.len() - line.trim_start().len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = line[indent..].strip_prefix("[^")?;
+    let close = rest.find(']')?;
+    let label = &rest[..close];
+    if !is_label(label) || !rest[close + 1..].starts_with(':') {
+        return None;
+    }
+    Some((label.to_string(), indent, indent + close + 3))
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty() && !label.contains(|c: char| c.is_whitespace() || c == '[' || c == ']')
+}
+
+/// Footnote references outside code, notes' own markers excluded.
+pub fn references(text: &str) -> Vec<Reference> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut found = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        let marker_end = note_marker(line).map(|(_, _, end)| end);
+        for (offset, _) in outside_code_spans(line).filter(|&(_, c)| c == '[') {
+            if marker_end.is_some_and(|end| offset < end) || line[..offset].ends_with('\\') {
+                continue;
+            }
+            let Some(rest) = line[offset..].strip_prefix("[^") else {
+                continue;
+            };
+            let Some(close) = rest.find(']') else {
+                continue;
+            };
+            let label = &rest[..close];
+            if is_label(label) {
+                found.push(Reference {
+                    label: label.to_string(),
+                    line: index,
+                    start: offset,
+                    end: offset + close + 3,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Inline `^[text]` notes outside code.
+pub fn inline_notes(text: &str) -> Vec<InlineNote> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut found = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        let mut after = 0;
+        for (offset, _) in outside_code_spans(line).filter(|&(_, c)| c == '^') {
+            if offset < after
+                || !line[offset + 1..].starts_with('[')
+                || line[..offset].ends_with('\\')
+            {
+                continue;
+            }
+            let Some(close) = closing_delimiter(line, offset + 1, '[', ']') else {
+                continue;
+            };
+            found.push(InlineNote {
+                content: line[offset + 2..close].to_string(),
+                line: index,
+                start: offset,
+                end: close + 1,
+            });
+            after = close + 1;
+        }
+    }
+    found
+}
+
+/// References without a note, notes nobody references and labels defined twice.
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = text.lines().collect();
+    let notes = notes(text);
+    let references = references(text);
+    let mut diagnostics = Vec::new();
+
+    for reference in &references {
+        if !notes.iter().any(|note| note.label == reference.label) {
+            diagnostics.push(Diagnostic {
+                range: Range::within_line(
+                    reference.line,
+                    lines[reference.line],
+                    reference.start,
+                    reference.end,
+                ),
+                severity: Severity::Warning,
+                code: "undefined-footnote",
+                message: format!("No note for footnote [^{}]", reference.label),
+            });
+        }
+    }
+
+    for (index, note) in notes.iter().enumerate() {
+        let range = Range::within_line(note.line, lines[note.line], note.start, note.end);
+        if let Some(first) = notes[..index]
+            .iter()
+            .find(|first| first.label == note.label)
+        {
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Warning,
+                code: "duplicate-footnote",
+                message: format!(
+                    "Footnote [^{}] is already defined on line {}",
+                    note.label,
+                    first.line + 1
+                ),
+            });
+        } else if !references
+            .iter()
+            .any(|reference| reference.label == note.label)
+        {
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Information,
+                code: "unused-footnote",
+                message: format!("Footnote [^{}] is never referenced", note.label),
+            });
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    diagnostics
+}
+
+/// The footnote label under `position`, on a reference or a note's marker.
+fn label_at(text: &str, position: Position) -> Option<String> {
+    let line = text.lines().nth(position.line as usize)?;
+    let cursor = byte_offset(line, position.character);
+    let line_number = position.line as usize;
+    let within = |start: usize, end: usize| start <= cursor && cursor <= end;
+
+    references(text)
+        .into_iter()
+        .find(|reference| reference.line == line_number && within(reference.start, reference.end))
+        .map(|reference| reference.label)
+        .or_else(|| {
+            note_marker(line)
+                .filter(|&(_, start, end)| within(start, end))
+                .map(|(label, _, _)| label)
+        })
+}
+
+/// Go to definition: from a reference to its note, and from a note's marker
+/// back to the references.
+pub fn navigate(text: &str, position: Position) -> Vec<Range> {
+    let Some(label) = label_at(text, position) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let on_note = note_marker(lines[position.line as usize]).is_some_and(|(note, start, end)| {
+        let cursor = byte_offset(lines[position.line as usize], position.character);
+        note == label && start <= cursor && cursor <= end
+    });
+
+    if on_note {
+        references(text)
+            .into_iter()
+            .filter(|reference| reference.label == label)
+            .map(|r| Range::within_line(r.line, lines[r.line], r.start, r.end))
+            .collect()
+    } else {
+        notes(text)
+            .into_iter()
+            .filter(|note| note.label == label)
+            .take(1)
+            .map(|note| Range::within_line(note.line, lines[note.line], note.start, note.end))
+            .collect()
+    }
+}
+
+/// Every reference to the footnote under `position`, and its note when
+/// `include_note` is set.
+pub fn references_at(text: &str, position: Position, include_note: bool) -> Vec<Range> {
+    let Some(label) = label_at(text, position) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let notes = notes(text)
+        .into_iter()
+        .filter(|note| include_note && note.label == label)
+        .map(|note| (note.line, note.start, note.end));
+    let references = references(text)
+        .into_iter()
+        .filter(|reference| reference.label == label)
+        .map(|reference| (reference.line, reference.start, reference.end));
+    let mut ranges: Vec<Range> = notes
+        .chain(references)
+        .map(|(line, start, end)| Range::within_line(line, lines[line], start, end))
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    ranges
+}
+
+/// Renumbering when the cursor is on any footnote, and conversion of the
+/// inline or reference-style note under the cursor.
+pub fn code_actions(text: &str, range: Range) -> Vec<CodeAction> {
+    let lines: Vec<&str> = text.lines().collect();
+    let line = range.start.line as usize;
+    let Some(current) = lines.get(line) else {
+        return Vec::new();
+    };
+    let cursor = byte_offset(current, range.start.character);
+    let notes = notes(text);
+    let references = references(text);
+    let mut actions = Vec::new();
+
+    let on_footnote = references.iter().any(|r| r.line == line)
+        || notes
+            .iter()
+            .any(|note| note.line <= line && line <= note.end_line);
+    let renumbering = renumber_edits(&lines, &notes, &references);
+    if on_footnote && !renumbering.is_empty() {
+        actions.push(CodeAction {
+            title: "Renumber footnotes in order of use".to_string(),
+            kind: "source",
+            edits: renumbering,
+        });
+    }
+
+    if let Some(inline) = inline_notes(text)
+        .into_iter()
+        .find(|note| note.line == line && note.start <= cursor && cursor <= note.end)
+    {
+        let label = next_number(&notes, &references).to_string();
+        actions.push(CodeAction {
+            title: format!("Convert to footnote [^{}]", label),
+            kind: "refactor.rewrite",
+            edits: vec![
+                TextEdit::replace(
+                    Range::within_line(line, current, inline.start, inline.end),
+                    format!("[^{}]", label),
+                ),
+                append_note(text, &lines, &notes, &label, &inline.content),
+            ],
+        });
+    }
+
+    if let Some(reference) = references
+        .iter()
+        .find(|r| r.line == line && r.start <= cursor && cursor <= r.end)
+    {
+        let uses = references
+            .iter()
+            .filter(|other| other.label == reference.label)
+            .count();
+        let mut matching = notes.iter().filter(|note| note.label == reference.label);
+        if let (Some(note), None, 1) = (matching.next(), matching.next(), uses) {
+            if note.is_single_paragraph() {
+                let content = note.content.split('\n').collect::<Vec<_>>().join(" ");
+                actions.push(CodeAction {
+                    title: "Convert to inline note".to_string(),
+                    kind: "refactor.inline",
+                    edits: vec![
+                        TextEdit::replace(
+                            Range::within_line(line, current, reference.start, reference.end),
+                            format!("^[{}]", content),
+                        ),
+                        TextEdit::replace(
+                            Range::new(
+                                Position::new(note.line as u32, 0),
+                                Position::new(note.end_line as u32 + 1, 0),
+                            ),
+                            "",
+                        ),
+                    ],
+                });
+            }
+        }
+    }
+
+    actions
+}
+
+/// Relabels numeric footnotes 1, 2, … in order of first reference; notes that
+/// are never referenced follow in document order. Named labels are kept.
+fn renumber_edits(lines: &[&str], notes: &[Note], references: &[Reference]) -> Vec<TextEdit> {
+    let is_numeric = |label: &str| label.chars().all(|c| c.is_ascii_digit());
+    let mut order: Vec<&str> = Vec::new();
+    let labels = references
+        .iter()
+        .map(|reference| reference.label.as_str())
+        .chain(notes.iter().map(|note| note.label.as_str()));
+    for label in labels {
+        if is_numeric(label) && !order.contains(&label) {
+            order.push(label);
+        }
+    }
+    let new_label = |label: &str| {
+        order
+            .iter()
+            .position(|&old| old == label)
+            .map(|index| (index + 1).to_string())
+    };
+
+    let spans = references
+        .iter()
+        .map(|r| (r.label.as_str(), r.line, r.start, r.end))
+        .chain(
+            notes
+                .iter()
+                .map(|n| (n.label.as_str(), n.line, n.start, n.end)),
+        );
+    spans
+        .filter_map(|(label, line, start, end)| {
+            let renamed = new_label(label).filter(|renamed| renamed != label)?;
+            Some(TextEdit::replace(
+                Range::within_line(line, lines[line], start, end),
+                format!("[^{}]", renamed),
+            ))
+        })
+        .collect()
+}
+
+/// One more than the largest numeric label in use.
+fn next_number(notes: &[Note], references: &[Reference]) -> usize {
+    notes
+        .iter()
+        .map(|note| note.label.as_str())
+        .chain(references.iter().map(|r| r.label.as_str()))
+        .filter_map(|label| label.parse::<usize>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// `[^label]: content` at the end of the document, after a blank line unless
+/// the document already ends with a note.
+fn append_note(text: &str, lines: &[&str], notes: &[Note], label: &str, content: &str) -> TextEdit {
+    let ends_with_note = notes
+        .last()
+        .is_some_and(|note| note.end_line + 1 == lines.len());
+    let separator = if lines.is_empty() || ends_with_note {
+        ""
+    } else {
+        "\n"
+    };
+    let note = format!("[^{}]: {}\n", label, content);
+    if text.is_empty() || text.ends_with('\n') {
+        let end = Position::new(lines.len() as u32, 0);
+        TextEdit::replace(Range::new(end, end), format!("{}{}", separator, note))
+    } else {
+        let last = lines[lines.len() - 1];
+        let end = Position::new(lines.len() as u32 - 1, utf16_len(last));
+        TextEdit::replace(Range::new(end, end), format!("\n{}{}", separator, note))
+    }
+}
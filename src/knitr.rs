@@ -0,0 +1,257 @@
+//! Migration of knitr-style chunk headers to Quarto `#|` options.
+//!
+//! R Markdown puts options in the header: ```` ```{r setup, include=FALSE, fig.width=7} ````.
+//! Quarto prefers a bare ```` ```{r} ```` header followed by YAML option lines:
+//!
+//! ```text
+//! #| label: setup
+//! #| include: false
+//! #| fig-width: 7
+//! ```
+
+use crate::cells::{self, option_prefix, Cell};
+use crate::text::{apply_edits, CodeAction, Range, TextEdit};
+
+/// Options parsed from a knitr chunk header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkHeader {
+    pub label: Option<String>,
+    /// Option names as written (dotted knitr spelling) with raw R values.
+    pub options: Vec<(String, String)>,
+}
+
+/// Parses the part of a header after the engine name, e.g. `setup, include=FALSE`.
+pub fn parse_header(header: &str) -> ChunkHeader {
+    let mut parsed = ChunkHeader::default();
+    for part in split_top_level(header) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match part.split_once('=') {
+            Some((key, value)) => {
+                let key = key.trim();
+                let value = value.trim();
+                if key == "label" {
+                    parsed.label = Some(unquote(value).to_string());
+                } else {
+                    parsed.options.push((key.to_string(), value.to_string()));
+                }
+            }
+            // knitr only treats the first unnamed entry as the label.
+            None if parsed.label.is_none() => parsed.label = Some(unquote(part).to_string()),
+            None => {}
+        }
+    }
+    parsed
+}
+
+/// The raw R value of option `name` (dotted knitr spelling, or `label`) in a
+/// chunk header, borrowed from it.
+pub fn header_option<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    let mut unnamed_seen = false;
+    for part in split_top_level(header) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match part.split_once('=') {
+            Some((key, value)) if key.trim() == name => {
+                let value = value.trim();
+                return Some(if name == "label" {
+                    unquote(value)
+                } else {
+                    value
+                });
+            }
+            Some(_) => {}
+            None if !unnamed_seen => {
+                unnamed_seen = true;
+                if name == "label" {
+                    return Some(unquote(part));
+                }
+            }
+            None => {}
+        }
+    }
+    None
+}
+
+/// Quarto spelling of a knitr option: `fig.width` becomes `fig-width`.
+pub fn quarto_option_name(knitr_name: &str) -> String {
+    knitr_name.replace('.', "-")
+}
+
+/// knitr spelling of a Quarto option: `fig-width` becomes `fig.width`.
+pub fn knitr_option_name(quarto_name: &str) -> String {
+    quarto_name.replace('-', ".")
+}
+
+/// Converts an R value to YAML. Literals map directly; anything else is kept as
+/// an R expression with Quarto's `!expr` tag.
+pub fn yaml_value(r_value: &str) -> String {
+    let value = r_value.trim();
+    match value {
+        "TRUE" | "T" => return "true".to_string(),
+        "FALSE" | "F" => return "false".to_string(),
+        "NULL" | "NA" => return "null".to_string(),
+        _ => {}
+    }
+    if let Some(number) = r_number(value) {
+        return number.to_string();
+    }
+    if let Some(string) = quoted(value) {
+        let plain = !string.is_empty()
+            && string
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '%'));
+        return if plain {
+            string.to_string()
+        } else {
+            format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+        };
+    }
+    format!("!expr {value}")
+}
+
+/// A decimal R numeric literal (`7`, `-0.5`, `.5`, `1e-3`, `10L`) without its
+/// integer `L` suffix. Words `parse::<f64>` would accept, such as `Inf` and
+/// `NaN`, are R expressions and return `None`.
+fn r_number(value: &str) -> Option<&str> {
+    let number = value.strip_suffix('L').unwrap_or(value);
+    let unsigned = number.strip_prefix('-').unwrap_or(number);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let valid_mantissa =
+        !(whole.is_empty() && fraction.is_empty()) && digits(whole) && digits(fraction);
+    let valid_exponent = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !exponent.is_empty() && digits(exponent)
+    });
+    (valid_mantissa && valid_exponent).then_some(number)
+}
+
+/// The header line and option lines that replace a knitr-style header, or
+/// `None` when the cell has nothing to migrate.
+pub fn migrated_header(cell: &Cell) -> Option<String> {
+    if cell.header.is_empty() {
+        return None;
+    }
+    let header = parse_header(&cell.header);
+    let prefix = option_prefix(&cell.language);
+
+    let mut lines = vec![format!("{}{{{}}}", cell.fence, cell.language)];
+    // Options already written as `#|` lines win over the header.
+    if let Some(label) = header.label.filter(|_| cell.option("label").is_none()) {
+        lines.push(format!("{prefix} label: {label}"));
+    }
+    for (name, value) in &header.options {
+        let name = quarto_option_name(name);
+        if cell.option(&name).is_none() {
+            lines.push(format!("{prefix} {name}: {}", yaml_value(value)));
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+fn migration_edit(cell: &Cell, lines: &[&str]) -> Option<TextEdit> {
+    let header_line = lines[cell.open_line];
+    let indent = &header_line[..header_line.len() - header_line.trim_start().len()];
+    let replacement = migrated_header(cell)?
+        .lines()
+        .map(|line| format!("{indent}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(TextEdit::replace(
+        Range::whole_line(cell.open_line, header_line),
+        replacement,
+    ))
+}
+
+/// Edits that migrate every knitr-style header in `text`.
+pub fn migration_edits(text: &str) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.lines().collect();
+    cells::cells(text)
+        .iter()
+        .filter_map(|cell| migration_edit(cell, &lines))
+        .collect()
+}
+
+/// Rewrites every knitr-style header, returning the new text and the number of
+/// cells changed.
+pub fn migrate_document(text: &str) -> (String, usize) {
+    let edits = migration_edits(text);
+    (apply_edits(text, &edits), edits.len())
+}
+
+/// Migration actions for the cell under `range` and for the whole document.
+pub fn code_actions(text: &str, range: Range) -> Vec<CodeAction> {
+    let lines: Vec<&str> = text.lines().collect();
+    let line = range.start.line as usize;
+    let mut actions = Vec::new();
+
+    if let Some(edit) = cells::cells(text)
+        .iter()
+        .find(|cell| cell.contains(line))
+        .and_then(|cell| migration_edit(cell, &lines))
+    {
+        actions.push(CodeAction {
+            title: "Convert chunk header to #| options".to_string(),
+            kind: "refactor.rewrite",
+            edits: vec![edit],
+        });
+    }
+
+    let all = migration_edits(text);
+    if all.len() > 1 {
+        actions.push(CodeAction {
+            title: format!("Convert all {} chunk headers to #| options", all.len()),
+            kind: "source",
+            edits: all,
+        });
+    }
+
+    actions
+}
+
+/// Splits on commas that are not inside quotes or parentheses.
+fn split_top_level(source: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (index, ch) in source.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&source[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&source[start..]);
+    parts
+}
+
+fn quoted(value: &str) -> Option<&str> {
+    ['"', '\''].into_iter().find_map(|q| {
+        value
+            .strip_prefix(q)
+            .and_then(|v| v.strip_suffix(q))
+            .filter(|v| !v.contains(q))
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    quoted(value).unwrap_or(value)
+}
@@ -1,11 +1,74 @@
-use std::fs;
-use zed_extension_api as zed;
+pub mod attributes;
+pub mod bibliography;
+pub mod book;
+pub mod callouts;
+pub mod cells;
+pub mod execute;
+pub mod footnotes;
+pub mod knitr;
+pub mod links;
+pub mod lsp;
+pub mod notebook;
+pub mod outline;
+pub mod project;
+pub mod reference_links;
+pub mod repl;
+pub mod slash;
+pub mod templates;
+pub mod text;
+pub mod themes;
+pub mod xrefs;
 
-struct QuartoExtension;
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use slash::ProjectSummary;
+use text::fence_for;
+use zed_extension_api::{self as zed, settings::LspSettings};
+
+/// Binary name of the bundled language server (`src/bin/quarto-ls.rs`).
+const LANGUAGE_SERVER_BINARY: &str = "quarto-ls";
+
+struct QuartoExtension {
+    /// Root of the last worktree Zed handed us. Argument completion gets no
+    /// worktree, so `/quarto-cite` completions search this project's bibliographies.
+    worktree_root: Mutex<Option<String>>,
+    /// `quarto-ls` as resolved for that worktree, for the same reason.
+    language_server_path: Mutex<Option<String>>,
+}
 
 impl zed::Extension for QuartoExtension {
     fn new() -> Self {
-        Self
+        Self {
+            worktree_root: Mutex::new(None),
+            language_server_path: Mutex::new(None),
+        }
+    }
+
+    fn language_server_command(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> zed::Result<zed::Command> {
+        self.remember_worktree(worktree);
+
+        let command = language_server_path(worktree).ok_or_else(|| {
+            format!(
+                "{LANGUAGE_SERVER_BINARY} not found in PATH. Install it with: \
+                 cargo install --git https://github.com/ck37/zed-quarto-extension --bin {LANGUAGE_SERVER_BINARY}"
+            )
+        })?;
+
+        Ok(zed::Command {
+            command,
+            args: LspSettings::for_worktree(language_server_id.as_ref(), worktree)
+                .ok()
+                .and_then(|settings| settings.binary)
+                .and_then(|binary| binary.arguments)
+                .unwrap_or_default(),
+            env: worktree.shell_env(),
+        })
     }
 
     fn run_slash_command(
@@ -14,11 +77,48 @@ impl zed::Extension for QuartoExtension {
         args: Vec<String>,
         worktree: Option<&zed::Worktree>,
     ) -> Result<zed::SlashCommandOutput, String> {
+        if let Some(worktree) = worktree {
+            self.remember_worktree(worktree);
+        }
+
         match command.name.as_str() {
             "quarto-preview" => self.preview_quarto(&args, worktree),
+            "quarto-render" => self.render_quarto(&args, worktree),
+            "quarto-migrate-chunks" => self.migrate_chunks(&args, worktree),
+            "quarto-convert" => self.convert_notebook(&args, worktree),
+            "quarto-run-cell" => self.run_cell(&args, worktree),
+            "quarto-outline" => self.document_outline(&args, worktree),
+            "quarto-xrefs" => self.cross_references(&args, worktree),
+            "quarto-cite" => self.cite(&args),
+            "quarto-new" => self.new_from_template(&args),
+            "quarto-book" => self.book_contents(&args, worktree),
+            "quarto-check-links" => self.check_links(&args, worktree),
             _ => Err(format!("Unknown command: {}", command.name)),
         }
     }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>, String> {
+        match command.name.as_str() {
+            "quarto-preview" | "quarto-render" | "quarto-book" | "quarto-run-cell"
+            | "quarto-cite" => Ok(self
+                .complete_in_quarto_ls(&command.name, &args)
+                .unwrap_or_default()),
+            // Built-in templates complete even without a worktree or quarto-ls
+            "quarto-new" => Ok(self
+                .complete_in_quarto_ls(&command.name, &args)
+                .unwrap_or_else(|_| {
+                    slash::template_completions(None, &args)
+                        .into_iter()
+                        .map(into_completion)
+                        .collect()
+                })),
+            _ => Ok(Vec::new()),
+        }
+    }
 }
 
 impl QuartoExtension {
@@ -28,45 +128,48 @@ impl QuartoExtension {
         args: &[String],
         worktree: Option<&zed::Worktree>,
     ) -> Result<zed::SlashCommandOutput, String> {
-        // 1. Check Quarto CLI availability
-        if !self.is_quarto_available() {
-            return Err(
-                "Quarto CLI not found. Install from https://quarto.org/docs/get-started/"
-                    .to_string(),
-            );
-        }
-
-        // 2. Get current file path
-        let file_path = self.get_current_file(args, worktree)?;
+        // 1. Get current file path and the project it belongs to
+        let (profile, args) = project::take_profile_arg(args)?;
+        let file_path = absolute_path(&self.get_current_file(&args, worktree)?, worktree);
+        let project = self.project_or_standalone(&file_path, profile, worktree)?;
+        let is_project_root = project
+            .as_ref()
+            .is_some_and(|project| Path::new(&project.root) == Path::new(&file_path));
 
-        // 3. Validate file extension
-        if !file_path.ends_with(".qmd") {
-            return Err("Preview only works with .qmd files".to_string());
+        // 2. Validate file extension (a project root previews the whole project)
+        if !is_project_root && !file_path.ends_with(".qmd") && !file_path.ends_with(".Rmd") {
+            return Err("Preview only works with .qmd and .Rmd files".to_string());
         }
 
-        // 4. Validate file exists
-        if fs::metadata(&file_path).is_err() {
-            return Err(format!("File not found: {}", file_path));
+        // 3. Execute quarto preview through the sandbox, like render; quarto
+        //    itself reports a missing file
+        let mut command = zed::process::Command::new("quarto")
+            .args(["preview", &file_path])
+            .args(profile_args(project.as_ref()));
+        if let Some(wt) = worktree {
+            command = command.envs(wt.shell_env());
         }
+        let output = command.output().map_err(|e| {
+            format!(
+                "Quarto CLI not found ({}). Install from https://quarto.org/docs/get-started/",
+                e
+            )
+        })?;
 
-        // 5. Execute quarto preview
-        let output = std::process::Command::new("quarto")
-            .arg("preview")
-            .arg(&file_path)
-            .output()
-            .map_err(|e| format!("Failed to run quarto: {}", e))?;
-
-        if !output.status.success() {
+        if output.status != Some(0) {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(format!("Quarto preview failed: {}", stderr));
         }
 
-        // 6. Return success message
+        // 4. Return success message
         let filename = std::path::Path::new(&file_path)
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or(&file_path);
-        let text = format!("✓ Opening preview for {}", filename);
+        let mut text = format!("✓ Opening preview for {}", filename);
+        if let Some(project) = &project {
+            text.push_str(&format!(" ({})", project.description));
+        }
         Ok(zed::SlashCommandOutput {
             text: text.clone(),
             sections: vec![zed::SlashCommandOutputSection {
@@ -76,33 +179,53 @@ impl QuartoExtension {
         })
     }
 
-    /// Check if Quarto CLI is available in PATH
-    fn is_quarto_available(&self) -> bool {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            #[cfg(target_family = "unix")]
-            {
-                std::process::Command::new("which")
-                    .arg("quarto")
-                    .output()
-                    .map(|output| output.status.success())
-                    .unwrap_or(false)
-            }
-            #[cfg(target_family = "windows")]
-            {
-                std::process::Command::new("where")
-                    .arg("quarto")
-                    .output()
-                    .map(|output| output.status.success())
-                    .unwrap_or(false)
-            }
+    /// Render a document, or the whole project when no file is given
+    fn render_quarto(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let (profile, args) = project::take_profile_arg(args)?;
+        let target = absolute_path(&self.get_current_file(&args, worktree)?, worktree);
+        // Rendering the whole worktree needs to know it is a project; a single
+        // document renders on its own
+        let project = if args.is_empty() {
+            self.project_summary(&target, profile, worktree)?
+        } else {
+            self.project_or_standalone(&target, profile, worktree)?
+        };
+        if args.is_empty() && project.is_none() {
+            return Err(format!(
+                "{} is not a Quarto project (no {}). Specify a document to render.",
+                target,
+                project::PROJECT_FILE
+            ));
         }
-        #[cfg(target_arch = "wasm32")]
-        {
-            // In WASM, we can't run system commands during build
-            // The actual check will happen at runtime in Zed
-            false
+
+        let mut command = zed::process::Command::new("quarto")
+            .args(["render", &target])
+            .args(profile_args(project.as_ref()));
+        if let Some(wt) = worktree {
+            command = command.envs(wt.shell_env());
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("Failed to run quarto: {}", e))?;
+        if output.status != Some(0) {
+            return Err(format!(
+                "Quarto render failed:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
         }
+
+        let mut text = format!("✓ Rendered {}", display_name(&target));
+        if let Some(project) = &project {
+            text.push_str(&format!(" ({})", project.description));
+            if let Some(output_dir) = &project.output_dir {
+                text.push_str(&format!("\nOutput: {}", output_dir));
+            }
+        }
+        Ok(single_section(text, "Quarto Render".to_string()))
     }
 
     /// Get the current file path from arguments or worktree
@@ -123,6 +246,444 @@ impl QuartoExtension {
 
         Err("No file to preview. Please specify a file path or open a Quarto document.".to_string())
     }
+
+    /// Rewrite knitr-style chunk headers (`{r setup, include=FALSE}`) as `#|` options
+    fn migrate_chunks(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let (path, source) = self.read_document(args, worktree)?;
+        let filename = display_name(&path);
+
+        let (migrated, count) = knitr::migrate_document(&source);
+        if count == 0 {
+            let text = format!("No knitr-style chunk headers found in {}", filename);
+            return Ok(single_section(text, "Quarto Migrate Chunks".to_string()));
+        }
+
+        let fence = fence_for(&migrated);
+        let text = format!(
+            "Migrated {} chunk header(s) in {}:\n\n{}qmd\n{}\n{}\n",
+            count,
+            filename,
+            fence,
+            migrated.trim_end(),
+            fence
+        );
+        Ok(single_section(
+            text,
+            format!("{}: {} chunk header(s) migrated", filename, count),
+        ))
+    }
+
+    /// Convert a Jupyter notebook to a Quarto document, or the reverse
+    fn convert_notebook(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let (path, source) = self.read_document(args, worktree)?;
+        let filename = display_name(&path);
+
+        let (converted, extension, fence_language) = if path.ends_with(".ipynb") {
+            (notebook::ipynb_to_qmd(&source)?, "qmd", "qmd")
+        } else if path.ends_with(".qmd") {
+            (notebook::qmd_to_ipynb(&source), "ipynb", "json")
+        } else {
+            return Err("Convert only works with .ipynb and .qmd files".to_string());
+        };
+
+        let target = std::path::Path::new(filename).with_extension(extension);
+        let label = format!("{} (converted from {})", target.display(), filename);
+        let fence = fence_for(&converted);
+        let text = format!(
+            "{}:\n\n{}{}\n{}\n{}\n",
+            label,
+            fence,
+            fence_language,
+            converted.trim_end(),
+            fence
+        );
+        Ok(single_section(text, label))
+    }
+
+    /// Summarize a document's headings, cells, callouts and figure/table labels
+    fn document_outline(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let (path, source) = self.read_document(args, worktree)?;
+        let filename = display_name(&path);
+
+        let entries = outline::outline(&source);
+        if entries.is_empty() {
+            let text = format!("{} has no headings, cells or callouts", filename);
+            return Ok(single_section(text, "Quarto Outline".to_string()));
+        }
+
+        let header = format!("Outline of {}:\n\n", filename);
+        let (body, sections) = outline::render(&entries);
+        Ok(zed::SlashCommandOutput {
+            text: header.clone() + &body,
+            sections: sections
+                .into_iter()
+                .map(|(range, label)| zed::SlashCommandOutputSection {
+                    range: (header.len() + range.start..header.len() + range.end).into(),
+                    label,
+                })
+                .collect(),
+        })
+    }
+
+    /// Tabulate cross-reference labels for one document, or every document in
+    /// the worktree when no file is given
+    fn cross_references(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        if args.is_empty() {
+            let root = worktree
+                .map(|wt| wt.root_path())
+                .ok_or_else(|| "Please specify a file path".to_string())?;
+            return self
+                .quarto_ls::<slash::Output>(
+                    slash_args("quarto-xrefs", &root, args, worktree),
+                    worktree,
+                )
+                .map(into_output);
+        }
+
+        let (path, source) = self.read_document(args, worktree)?;
+        Ok(into_output(slash::cross_references(
+            display_name(&path),
+            &[(path.clone(), source)],
+        )))
+    }
+
+    /// Check relative links, anchors and website navigation in a document, or
+    /// in every document of the project when no file is given
+    fn check_links(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let root = worktree
+            .map(|wt| wt.root_path())
+            .or_else(|| self.remembered_root())
+            .ok_or_else(|| "Please specify a file path".to_string())?;
+        self.quarto_ls(
+            slash_args("quarto-check-links", &root, args, worktree),
+            worktree,
+        )
+        .map(into_output)
+    }
+
+    /// Search the project's bibliographies and return `[@key]` citations, one
+    /// section per matching reference
+    fn cite(&self, args: &[String]) -> Result<zed::SlashCommandOutput, String> {
+        let root = self
+            .remembered_root()
+            .ok_or_else(|| "Open a project folder to search its bibliography".to_string())?;
+        self.quarto_ls(slash_args("quarto-cite", &root, args, None), None)
+            .map(into_output)
+    }
+
+    /// Generate a document or project from a built-in or `.quarto-templates/`
+    /// template: `<template> <name> [engine]`. Only the built-in templates are
+    /// offered without a worktree or when `quarto-ls` cannot be run.
+    fn new_from_template(&self, args: &[String]) -> Result<zed::SlashCommandOutput, String> {
+        self.remembered_root()
+            .ok_or_else(|| "No worktree for custom templates".to_string())
+            .and_then(|root| self.quarto_ls(slash_args("quarto-new", &root, args, None), None))
+            .or_else(|_| slash::new_from_template(None, args))
+            .map(into_output)
+    }
+
+    /// The book's parts, chapters and sections in reading order, numbered as
+    /// Quarto numbers them
+    fn book_contents(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let root = worktree
+            .map(|wt| wt.root_path())
+            .or_else(|| self.remembered_root())
+            .ok_or_else(|| "Open a Quarto book project to list its chapters".to_string())?;
+        self.quarto_ls(slash_args("quarto-book", &root, args, worktree), worktree)
+            .map(into_output)
+    }
+
+    /// Completions computed by `quarto-ls complete` in the remembered worktree
+    fn complete_in_quarto_ls(
+        &self,
+        command: &str,
+        args: &[String],
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>, String> {
+        let root = self
+            .remembered_root()
+            .ok_or_else(|| "No worktree to complete in".to_string())?;
+        let mut complete_args = vec!["complete".to_string(), command.to_string(), root];
+        complete_args.extend(args.iter().cloned());
+        let completions: Vec<slash::Completion> = self.quarto_ls(complete_args, None)?;
+        Ok(completions.into_iter().map(into_completion).collect())
+    }
+
+    fn remembered_root(&self) -> Option<String> {
+        self.worktree_root.lock().ok().and_then(|root| root.clone())
+    }
+
+    fn remember_worktree(&self, worktree: &zed::Worktree) {
+        if let Ok(mut root) = self.worktree_root.lock() {
+            *root = Some(worktree.root_path());
+        }
+        if let Ok(mut path) = self.language_server_path.lock() {
+            *path = language_server_path(worktree);
+        }
+    }
+
+    /// The project containing `path`, with `profile` merged in. Without one, the
+    /// `lsp.quarto-ls.settings.profile` setting applies when the project has it.
+    fn project_summary(
+        &self,
+        path: &str,
+        profile: Option<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<Option<ProjectSummary>, String> {
+        let mut args = vec!["project".to_string()];
+        if let Some(default) = default_profile(worktree) {
+            args.extend([slash::DEFAULT_PROFILE_FLAG.to_string(), default]);
+        }
+        args.push(path.to_string());
+        if let Some(profile) = profile {
+            args.extend([project::PROFILE_FLAG.to_string(), profile]);
+        }
+        self.quarto_ls(args, worktree)
+    }
+
+    /// Like `project_summary`, but a document is treated as standalone when
+    /// `quarto-ls` cannot say which project it is in, unless a profile was
+    /// asked for explicitly.
+    fn project_or_standalone(
+        &self,
+        path: &str,
+        profile: Option<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<Option<ProjectSummary>, String> {
+        let explicit = profile.is_some();
+        match self.project_summary(path, profile, worktree) {
+            Err(_) if !explicit => Ok(None),
+            result => result,
+        }
+    }
+
+    /// Runs `quarto-ls` with `args` and parses the JSON it prints. Anything that
+    /// walks the project runs there, natively, since the extension's sandbox
+    /// cannot list worktree directories (see `src/slash.rs`).
+    fn quarto_ls<T: DeserializeOwned>(
+        &self,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<T, String> {
+        let program = worktree
+            .and_then(language_server_path)
+            .or_else(|| {
+                self.language_server_path
+                    .lock()
+                    .ok()
+                    .and_then(|path| path.clone())
+            })
+            .unwrap_or_else(|| LANGUAGE_SERVER_BINARY.to_string());
+        let mut command = zed::process::Command::new(program).args(args);
+        if let Some(wt) = worktree {
+            command = command.envs(wt.shell_env());
+        }
+        let output = command.output().map_err(|e| {
+            format!(
+                "Failed to run {LANGUAGE_SERVER_BINARY} ({e}). Install it with: \
+                 cargo install --git https://github.com/ck37/zed-quarto-extension --bin {LANGUAGE_SERVER_BINARY}"
+            )
+        })?;
+        if output.status != Some(0) {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| format!("Unexpected output from {}: {}", LANGUAGE_SERVER_BINARY, e))
+    }
+
+    /// Run the cell labelled in a `<file>#<label>` argument and return its output
+    fn run_cell(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput, String> {
+        let target = args
+            .first()
+            .ok_or_else(|| "Please specify a cell as <file>#<label>".to_string())?;
+        let (file, label) = execute::parse_target(target)?;
+        let (path, source) = self.read_document(&[file.to_string()], worktree)?;
+
+        let cell = execute::find_cell(&source, label)?;
+        let lines: Vec<&str> = source.lines().collect();
+
+        // Run from the document's directory, or the project root with
+        // `execute-dir: project`, as Quarto does when rendering
+        let absolute = absolute_path(&path, worktree);
+        let directory = match self.project_or_standalone(&absolute, None, worktree)? {
+            Some(project) if project.execute_in_root => project.root,
+            _ => Path::new(&absolute)
+                .parent()
+                .and_then(|dir| dir.to_str())
+                .filter(|dir| !dir.is_empty())
+                .unwrap_or(".")
+                .to_string(),
+        };
+
+        let (program, command_args) =
+            execute::run_command(&directory, &cell.language, &cell.code(&lines))?;
+        let mut command = zed::process::Command::new(program).args(command_args);
+        if let Some(wt) = worktree {
+            command = command.envs(wt.shell_env());
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("Failed to run {} cell: {}", cell.language, e))?;
+
+        let (text, sections) = execute::format_output(
+            label,
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+            output.status,
+        );
+        Ok(zed::SlashCommandOutput {
+            text,
+            sections: sections
+                .into_iter()
+                .map(|(range, label)| zed::SlashCommandOutputSection {
+                    range: range.into(),
+                    label,
+                })
+                .collect(),
+        })
+    }
+
+    /// Read a document named by the first argument, either absolute or relative
+    /// to the worktree root
+    fn read_document(
+        &self,
+        args: &[String],
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<(String, String), String> {
+        let path = args
+            .first()
+            .ok_or_else(|| "Please specify a file path".to_string())?;
+
+        // The sandbox reaches worktree files only through the worktree
+        let wt = worktree.ok_or_else(|| format!("Open a worktree to read {}", path))?;
+        let root = wt.root_path();
+        let relative = path
+            .strip_prefix(&root)
+            .map(|rest| rest.trim_start_matches('/'))
+            .unwrap_or(path);
+        let source = wt
+            .read_text_file(relative)
+            .map_err(|e| format!("Could not read {}: {}", path, e))?;
+
+        Ok((path.clone(), source))
+    }
+}
+
+/// Slash command output with one section spanning all of `text`
+fn single_section(text: String, label: String) -> zed::SlashCommandOutput {
+    zed::SlashCommandOutput {
+        sections: vec![zed::SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label,
+        }],
+        text,
+    }
+}
+
+/// `path` made absolute against the worktree root when it is relative
+fn absolute_path(path: &str, worktree: Option<&zed::Worktree>) -> String {
+    match worktree {
+        Some(wt) if !path.starts_with('/') => format!("{}/{}", wt.root_path(), path),
+        _ => path.to_string(),
+    }
+}
+
+/// `quarto-ls slash <command> <root> <args>`, with the profile from settings
+fn slash_args(
+    command: &str,
+    root: &str,
+    args: &[String],
+    worktree: Option<&zed::Worktree>,
+) -> Vec<String> {
+    let mut slash_args = vec!["slash".to_string()];
+    if let Some(default) = default_profile(worktree) {
+        slash_args.extend([slash::DEFAULT_PROFILE_FLAG.to_string(), default]);
+    }
+    slash_args.extend([command.to_string(), root.to_string()]);
+    slash_args.extend(args.iter().cloned());
+    slash_args
+}
+
+fn into_completion(completion: slash::Completion) -> zed::SlashCommandArgumentCompletion {
+    zed::SlashCommandArgumentCompletion {
+        label: completion.label,
+        new_text: completion.new_text,
+        run_command: completion.run_command,
+    }
+}
+
+fn into_output(output: slash::Output) -> zed::SlashCommandOutput {
+    zed::SlashCommandOutput {
+        text: output.text,
+        sections: output
+            .sections
+            .into_iter()
+            .map(|section| zed::SlashCommandOutputSection {
+                range: section.range.into(),
+                label: section.label,
+            })
+            .collect(),
+    }
+}
+
+/// The `quarto-ls` executable: `lsp.quarto-ls.binary.path` from Zed settings,
+/// else the one on the worktree's PATH
+fn language_server_path(worktree: &zed::Worktree) -> Option<String> {
+    LspSettings::for_worktree(LANGUAGE_SERVER_BINARY, worktree)
+        .ok()
+        .and_then(|settings| settings.binary)
+        .and_then(|binary| binary.path)
+        .or_else(|| worktree.which(LANGUAGE_SERVER_BINARY))
+}
+
+/// `lsp.quarto-ls.settings.profile` from Zed settings
+fn default_profile(worktree: Option<&zed::Worktree>) -> Option<String> {
+    let settings = LspSettings::for_worktree(LANGUAGE_SERVER_BINARY, worktree?)
+        .ok()?
+        .settings?;
+    settings["profile"].as_str().map(str::to_string)
+}
+
+/// `--profile <name>` for the quarto CLI when the project has a profile active
+fn profile_args(project: Option<&ProjectSummary>) -> Vec<String> {
+    match project.and_then(|project| project.profile.clone()) {
+        Some(profile) => vec![project::PROFILE_FLAG.to_string(), profile],
+        None => Vec::new(),
+    }
+}
+
+fn display_name(path: &str) -> &str {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
 }
 
 zed::register_extension!(QuartoExtension);
@@ -0,0 +1,458 @@
+//! Relative links between pages for `/quarto-check-links` and quarto-ls.
+//!
+//! Links are the inline `[text](target)` and `![alt](target)` forms plus the
+//! targets of reference definitions (`[ref]: target`), found by scanning each
+//! line outside code, since the WASM extension has no grammar to parse with.
+//! External URLs are not followed; relative targets must name an existing
+//! file, and `#anchors` an id in the target page.
+
+use std::collections::HashMap;
+use std::ops::Range as ByteRange;
+use std::path::{Path, PathBuf};
+
+use crate::attributes::Attributes;
+use crate::outline::{self, EntryKind};
+use crate::project::{self, NavItem, Project};
+use crate::text::{
+    closing_delimiter, outside_code_spans, percent_decode, verbatim_lines, Diagnostic, Range,
+    Severity,
+};
+
+/// Extensions of pages Quarto renders to `.html`.
+const PAGE_EXTENSIONS: [&str; 5] = ["qmd", "md", "Rmd", "rmd", "ipynb"];
+
+/// A link target in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub target: String,
+    /// Zero-based line and the byte range of the target within it.
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// The linked file does not exist.
+    MissingFile,
+    /// The file exists but has no element with the anchor's id.
+    MissingAnchor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub link: Link,
+    pub problem: Problem,
+    pub message: String,
+}
+
+/// A navbar or sidebar entry whose `href:` points nowhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenNavItem {
+    pub href: String,
+    pub text: Option<String>,
+    pub problem: Problem,
+    pub message: String,
+}
+
+/// Inline link and image targets and reference definition targets in `text`,
+/// skipping code.
+pub fn links(text: &str) -> Vec<Link> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut found = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        if let Some(link) = reference_definition(line) {
+            found.push(Link {
+                line: index,
+                ..link
+            });
+            continue;
+        }
+        let brackets: Vec<usize> = outside_code_spans(line)
+            .filter(|&(offset, c)| c == '[' && !line[..offset].ends_with('\\'))
+            .map(|(offset, _)| offset)
+            .collect();
+        for open in brackets {
+            let Some(close) = closing_delimiter(line, open, '[', ']') else {
+                continue;
+            };
+            if !line[close + 1..].starts_with('(') {
+                continue;
+            }
+            let Some(end) = closing_delimiter(line, close + 1, '(', ')') else {
+                continue;
+            };
+            if let Some((start, end)) = destination(line, close + 2, end) {
+                found.push(Link {
+                    target: line[start..end].to_string(),
+                    line: index,
+                    start,
+                    end,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// `[label]: target "title"`, not footnotes.
+fn reference_definition(line: &str) -> Option<Link> {
+    let indent = line.len() - line.trim_start().len();
+    if indent > 3 || !line[indent..].starts_with('[') || line[indent..].starts_with("[^") {
+        return None;
+    }
+    let close = closing_delimiter(line, indent, '[', ']')?;
+    if !line[close + 1..].starts_with(':') {
+        return None;
+    }
+    let (start, end) = destination(line, close + 2, line.len())?;
+    Some(Link {
+        target: line[start..end].to_string(),
+        line: 0,
+        start,
+        end,
+    })
+}
+
+/// Byte range of the destination between `start` and `end`: `<target>` or
+/// the first word, before any title.
+fn destination(line: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let inner = &line[start..end];
+    let skipped = inner.len() - inner.trim_start().len();
+    let inner = inner.trim_start();
+    let (offset, length) = match inner.strip_prefix('<') {
+        Some(rest) => (1, rest.find('>')?),
+        None => (0, inner.find(char::is_whitespace).unwrap_or(inner.len())),
+    };
+    (length > 0).then(|| {
+        let start = start + skipped + offset;
+        (start, start + length)
+    })
+}
+
+/// Ids a link can point at: headings (explicit or generated from the text as
+/// Pandoc does), any `{#id}` attribute and cell labels.
+pub fn anchors(text: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut generated: HashMap<String, usize> = HashMap::new();
+    for entry in outline::outline(text) {
+        if let EntryKind::Heading { id, .. } = entry.kind {
+            let id = id.unwrap_or_else(|| {
+                let base = heading_id(entry.title.as_deref().unwrap_or_default());
+                let count = generated.entry(base.clone()).or_insert(0);
+                *count += 1;
+                match *count {
+                    1 => base,
+                    n => format!("{}-{}", base, n - 1),
+                }
+            });
+            ids.push(id);
+        }
+    }
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(label) = trimmed
+            .strip_prefix("#|")
+            .and_then(|option| option.trim_start().strip_prefix("label:"))
+        {
+            ids.push(label.trim().trim_matches(['"', '\'']).to_string());
+            continue;
+        }
+        let mut rest = line;
+        while let Some(open) = rest.find('{') {
+            let Some(close) = rest[open..].find('}') else {
+                break;
+            };
+            let attributes = &rest[open..open + close + 1];
+            if let Some(id) = Attributes::parse(attributes).id {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            rest = &rest[open + close + 1..];
+        }
+    }
+    ids
+}
+
+/// The identifier Pandoc generates for a heading: link targets and
+/// punctuation other than `_`, `-` and `.` are dropped, spaces become
+/// hyphens, everything before the first letter is removed.
+pub fn heading_id(title: &str) -> String {
+    let mut text = String::new();
+    let mut rest = title;
+    // `[text](target)` keeps only its text
+    while let Some(open) = rest.find('[') {
+        text.push_str(&rest[..open]);
+        match closing_delimiter(rest, open, '[', ']') {
+            Some(close) => {
+                text.push_str(&rest[open + 1..close]);
+                rest = &rest[close + 1..];
+                if rest.starts_with('(') {
+                    if let Some(end) = closing_delimiter(rest, 0, '(', ')') {
+                        rest = &rest[end + 1..];
+                    }
+                }
+            }
+            None => {
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    let id: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|&c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .flat_map(char::to_lowercase)
+        .skip_while(|c| !c.is_alphabetic())
+        .collect();
+    if id.is_empty() {
+        "section".to_string()
+    } else {
+        id
+    }
+}
+
+/// Broken links in the document at `path`. Targets starting with `/` are
+/// resolved from `root`, the project root or the document's directory.
+pub fn check_document(root: &Path, path: &Path, text: &str) -> Vec<BrokenLink> {
+    let directory = path.parent().unwrap_or(root);
+    let own_anchors = anchors(text);
+    links(text)
+        .into_iter()
+        .filter_map(|link| {
+            let (problem, message) = check(root, directory, &link.target, Some(&own_anchors))?;
+            Some(BrokenLink {
+                link,
+                problem,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Navbar and sidebar entries of a website whose `href:` is broken. Hrefs are
+/// relative to the project root.
+pub fn check_navigation(project: &Project) -> Vec<BrokenNavItem> {
+    let Some(website) = &project.website else {
+        return Vec::new();
+    };
+    website
+        .nav_items()
+        .into_iter()
+        .filter_map(|item: &NavItem| {
+            let href = item.href.as_deref()?;
+            let (problem, message) = check(&project.root, &project.root, href, None)?;
+            Some(BrokenNavItem {
+                href: href.to_string(),
+                text: item.text.clone(),
+                problem,
+                message,
+            })
+        })
+        .collect()
+}
+
+/// Why `target` is broken, or `None` when it resolves or is not checked.
+/// `own_anchors` are the ids of the linking document, for `#anchor` targets.
+fn check(
+    root: &Path,
+    directory: &Path,
+    target: &str,
+    own_anchors: Option<&[String]>,
+) -> Option<(Problem, String)> {
+    if is_external(target) || target.contains("{{") {
+        return None;
+    }
+    let target = target.split('?').next().unwrap_or(target);
+    let (file, anchor) = match target.split_once('#') {
+        Some((file, anchor)) => (file, Some(anchor)),
+        None => (target, None),
+    };
+
+    if file.is_empty() {
+        let anchor = anchor.filter(|anchor| !anchor.is_empty())?;
+        let anchors = own_anchors?;
+        return (!anchors.iter().any(|id| id == anchor)).then(|| {
+            (
+                Problem::MissingAnchor,
+                format!("No heading or element with id #{} in this document", anchor),
+            )
+        });
+    }
+
+    let decoded = percent_decode(file).unwrap_or_else(|| file.to_string());
+    let base = match decoded.strip_prefix('/') {
+        Some(from_root) => root.join(from_root),
+        None => directory.join(&decoded),
+    };
+    let Some(found) = resolve(&base) else {
+        return Some((Problem::MissingFile, format!("{} does not exist", file)));
+    };
+
+    let anchor = anchor.filter(|anchor| !anchor.is_empty())?;
+    let is_page = found
+        .extension()
+        .is_some_and(|extension| ["qmd", "md", "Rmd", "rmd"].iter().any(|e| extension == *e));
+    let text = std::fs::read_to_string(&found).ok().filter(|_| is_page)?;
+    (!anchors(&text).iter().any(|id| id == anchor)).then(|| {
+        (
+            Problem::MissingAnchor,
+            format!("No heading or element with id #{} in {}", anchor, file),
+        )
+    })
+}
+
+/// The file a relative link lands on: the file itself, the page rendered to
+/// a `.html` target, or a directory's index page.
+fn resolve(base: &Path) -> Option<PathBuf> {
+    let with_page_extension = |stem: &Path| {
+        PAGE_EXTENSIONS
+            .iter()
+            .map(|extension| stem.with_extension(extension))
+            .chain([stem.with_extension("html")])
+            .find(|candidate| candidate.is_file())
+    };
+    if base.is_file() {
+        return Some(base.to_path_buf());
+    }
+    if base.is_dir() {
+        return with_page_extension(&base.join("index"));
+    }
+    match base.extension().and_then(|extension| extension.to_str()) {
+        Some("html" | "htm") => with_page_extension(base),
+        _ => None,
+    }
+}
+
+/// `https://…`, `mailto:…` and protocol-relative `//host/…` links.
+fn is_external(target: &str) -> bool {
+    target.starts_with("//")
+        || target.split_once(':').is_some_and(|(scheme, _)| {
+            !scheme.is_empty()
+                && !scheme.contains(['/', '#', '.'])
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-".contains(c))
+        })
+}
+
+/// Warnings on the targets of broken links in `text`. Links whose target is
+/// no longer where it was checked (the text was edited since) are skipped.
+pub fn diagnostics(broken: &[BrokenLink], text: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = text.lines().collect();
+    broken
+        .iter()
+        .filter_map(|broken| {
+            let line = lines.get(broken.link.line)?;
+            if line.get(broken.link.start..broken.link.end) != Some(broken.link.target.as_str()) {
+                return None;
+            }
+            Some(Diagnostic {
+                range: Range::within_line(
+                    broken.link.line,
+                    line,
+                    broken.link.start,
+                    broken.link.end,
+                ),
+                severity: Severity::Warning,
+                code: problem_code(broken.problem),
+                message: broken.message.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Warnings on the `_quarto.yml` lines of broken navbar and sidebar entries.
+pub fn nav_diagnostics(broken: &[BrokenNavItem], config: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = config.lines().collect();
+    broken
+        .iter()
+        .map(|item| {
+            let range = lines
+                .iter()
+                .enumerate()
+                .find_map(|(index, line)| {
+                    let found = project::config_value_range(line, &["href"], &item.href)?;
+                    Some(Range::within_line(index, line, found.start, found.end))
+                })
+                .unwrap_or_else(|| Range::at_line_start(0));
+            Diagnostic {
+                range,
+                severity: Severity::Warning,
+                code: "broken-nav-link",
+                message: format!("Navigation entry points nowhere: {}", item.message),
+            }
+        })
+        .collect()
+}
+
+fn problem_code(problem: Problem) -> &'static str {
+    match problem {
+        Problem::MissingFile => "broken-link",
+        Problem::MissingAnchor => "broken-anchor",
+    }
+}
+
+/// Report for `/quarto-check-links`: broken navigation, then one section per
+/// document with broken links.
+pub fn render(
+    documents: &[(String, Vec<BrokenLink>)],
+    navigation: &[BrokenNavItem],
+    checked: usize,
+) -> (String, Vec<(ByteRange<usize>, String)>) {
+    let broken = navigation.len()
+        + documents
+            .iter()
+            .map(|(_, links)| links.len())
+            .sum::<usize>();
+    let mut text = format!(
+        "Checked {} links in {} documents: {} broken.\n",
+        checked,
+        documents.len(),
+        broken
+    );
+    let mut sections = Vec::new();
+
+    if !navigation.is_empty() {
+        let start = text.len();
+        text.push_str("\nNavigation (_quarto.yml):\n");
+        for item in navigation {
+            let text_label = item
+                .text
+                .as_deref()
+                .map(|label| format!(" ({})", label))
+                .unwrap_or_default();
+            text.push_str(&format!(
+                "- {}{}: {}\n",
+                item.href, text_label, item.message
+            ));
+        }
+        sections.push((start..text.len(), "Navigation".to_string()));
+    }
+
+    for (path, links) in documents.iter().filter(|(_, links)| !links.is_empty()) {
+        let start = text.len();
+        text.push_str(&format!("\n{}:\n", path));
+        for broken in links {
+            text.push_str(&format!(
+                "- line {}: {}: {}\n",
+                broken.link.line + 1,
+                broken.link.target,
+                broken.message
+            ));
+        }
+        sections.push((start..text.len(), format!("{} ({})", path, links.len())));
+    }
+    (text, sections)
+}
@@ -0,0 +1,596 @@
+//! A small Language Server Protocol server for Quarto documents.
+//!
+//! Zed launches the `quarto-ls` binary (`src/bin/quarto-ls.rs`) for Quarto
+//! buffers. Documents are synced in full and every feature is computed from the
+//! plain text by the modules in this crate, so the server needs neither the C
+//! grammar nor any embedded-language servers.
+//!
+//! Run actions (`src/repl.rs`) cannot drive Zed's REPL directly. Instead the
+//! server asks the client to select the cell body with `window/showDocument`,
+//! so `repl: run` sends exactly that code to the cell's kernel. Only "Run cell"
+//! is offered: one selection cannot cover several cells, and the cursor cannot
+//! be moved after `repl: run`, so "run all above" is left to the `quarto-cell
+//! --above` task and "run and advance" is not offered at all.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::book::{self, ItemKind};
+use crate::project::{self, Project};
+use crate::repl::{self, RunPlan, RunScope};
+use crate::text::{percent_decode, CodeAction, Diagnostic, Position, Range, Severity, TextEdit};
+use crate::{callouts, footnotes, knitr, links, reference_links};
+
+/// LSP `SymbolKind`s for book parts, chapters and sections.
+const SYMBOL_FILE: u32 = 1;
+const SYMBOL_NAMESPACE: u32 = 3;
+const SYMBOL_STRING: u32 = 15;
+
+/// The only run action: select the cell body for `repl: run`.
+const RUN_CELL_COMMAND: &str = "quarto.runCell";
+
+/// Serves LSP requests read from `input` until the client sends `exit`.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut input)? {
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        if server.exited {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one `Content-Length` framed JSON-RPC message, or `None` at end of input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    /// Project of each open document, read from `_quarto.yml` when it opens.
+    projects: HashMap<String, Result<Option<Project>, String>>,
+    /// Broken links of each open document. Checking reads the linked files,
+    /// so it runs when the document opens and is saved, not on every change.
+    broken_links: HashMap<String, Vec<links::BrokenLink>>,
+    /// Id for the next request the server sends to the client.
+    next_request_id: u64,
+    /// Set once the client sends `exit`.
+    pub exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Text of an open document.
+    pub fn document(&self, uri: &str) -> Option<&str> {
+        self.documents.get(uri).map(String::as_str)
+    }
+
+    /// The Quarto project an open document belongs to, if any.
+    pub fn project(&self, uri: &str) -> Option<&Project> {
+        self.projects.get(uri)?.as_ref().ok()?.as_ref()
+    }
+
+    /// Handles one incoming message and returns the messages to send back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        // Responses to our own requests (`window/showDocument`) need no reply.
+        let Some(method) = message["method"].as_str() else {
+            return Vec::new();
+        };
+        let params = &message["params"];
+
+        let Some(id) = message.get("id").cloned() else {
+            return self.handle_notification(method, params);
+        };
+
+        let mut requests = Vec::new();
+        let result = match method {
+            "initialize" => Ok(initialize_result()),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/codeAction" => Ok(self.code_actions(params)),
+            "textDocument/definition" => Ok(self.definition(params)),
+            "textDocument/references" => Ok(self.references(params)),
+            "workspace/symbol" => Ok(self.workspace_symbols(params)),
+            "workspace/executeCommand" => self.execute_command(params, &mut requests),
+            _ => Err(json!({
+                "code": -32601,
+                "message": format!("Unhandled method: {method}"),
+            })),
+        };
+
+        let mut replies = vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+        }];
+        replies.extend(requests);
+        replies
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.clone(), text.to_string());
+                if let Some(path) = uri_to_path(&uri) {
+                    self.projects
+                        .insert(uri.clone(), Project::discover(&path, None));
+                }
+                self.check_links(&uri);
+                let mut replies = vec![self.publish_diagnostics(&uri)];
+                replies.extend(self.publish_config_diagnostics(&uri));
+                replies
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change carries the whole document.
+                let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return Vec::new();
+                };
+                self.documents.insert(uri.clone(), text.to_string());
+                vec![self.publish_diagnostics(&uri)]
+            }
+            "textDocument/didSave" => {
+                self.check_links(&uri);
+                vec![self.publish_diagnostics(&uri)]
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.projects.remove(&uri);
+                self.broken_links.remove(&uri);
+                vec![notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )]
+            }
+            "exit" => {
+                self.exited = true;
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// All diagnostics for an open document.
+    pub fn diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
+        let Some(text) = self.document(uri) else {
+            return Vec::new();
+        };
+        let mut diagnostics = callouts::diagnostics(text);
+        diagnostics.extend(reference_links::diagnostics(text));
+        diagnostics.extend(footnotes::diagnostics(text));
+        diagnostics.extend(self.project_diagnostics(uri));
+        if let Some(broken) = self.broken_links.get(uri) {
+            diagnostics.extend(links::diagnostics(broken, text));
+        }
+        diagnostics
+    }
+
+    /// Checks the links of an open document against the files on disk.
+    fn check_links(&mut self, uri: &str) {
+        let (Some(text), Some(path)) = (self.document(uri), uri_to_path(uri)) else {
+            return;
+        };
+        let root = match self.project(uri) {
+            Some(project) => project.root.clone(),
+            None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let broken = links::check_document(&root, &path, text);
+        self.broken_links.insert(uri.to_string(), broken);
+    }
+
+    /// Problems with the document's `_quarto.yml`, reported on the first line.
+    fn project_diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
+        let range = Range::at_line_start(0);
+        match self.projects.get(uri) {
+            Some(Err(error)) => vec![Diagnostic {
+                range,
+                severity: Severity::Warning,
+                code: "invalid-project",
+                message: error.clone(),
+            }],
+            Some(Ok(Some(project))) => {
+                let Some(relative) = uri_to_path(uri).and_then(|path| project.relative(&path))
+                else {
+                    return Vec::new();
+                };
+                if book::is_unlisted(project, &relative) {
+                    return vec![Diagnostic {
+                        range,
+                        severity: Severity::Information,
+                        code: "not-in-book",
+                        message: format!(
+                            "Not listed in the book's chapters or appendices in {}",
+                            project::PROJECT_FILE
+                        ),
+                    }];
+                }
+                if project.render.is_empty() || project.renders(&relative) {
+                    return Vec::new();
+                }
+                vec![Diagnostic {
+                    range,
+                    severity: Severity::Information,
+                    code: "not-rendered",
+                    message: format!(
+                        "Not in the `render:` list of {}; `quarto render` skips this document",
+                        project::PROJECT_FILE
+                    ),
+                }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Value {
+        let diagnostics: Vec<Value> = self.diagnostics(uri).iter().map(diagnostic_json).collect();
+        notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    /// Missing and unlisted book chapters and broken website navigation,
+    /// published against `_quarto.yml` when a document of the project opens.
+    fn publish_config_diagnostics(&self, uri: &str) -> Option<Value> {
+        let project = self.project(uri)?;
+        if project.book.is_none() && project.website.is_none() {
+            return None;
+        }
+        let config_path = project.config_path();
+        let config = std::fs::read_to_string(&config_path).ok()?;
+        let mut diagnostics = match self.book_contents(project) {
+            Some(contents) => book::config_diagnostics(&contents, &config),
+            None => Vec::new(),
+        };
+        diagnostics.extend(links::nav_diagnostics(
+            &links::check_navigation(project),
+            &config,
+        ));
+        let diagnostics: Vec<Value> = diagnostics.iter().map(diagnostic_json).collect();
+        Some(notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": path_to_uri(&config_path), "diagnostics": diagnostics }),
+        ))
+    }
+
+    /// The book's contents, reading chapters from open documents first.
+    fn book_contents(&self, project: &Project) -> Option<book::Contents> {
+        book::contents(project, |path| {
+            let full = project.root.join(path);
+            match self.document(&path_to_uri(&full)) {
+                Some(text) => Some(text.to_string()),
+                None => std::fs::read_to_string(full).ok(),
+            }
+        })
+    }
+
+    /// Parts, chapters and sections of every book with an open document, in
+    /// reading order, filtered by the query as a subsequence of the name.
+    fn workspace_symbols(&self, params: &Value) -> Value {
+        let query = params["query"].as_str().unwrap_or_default().to_lowercase();
+        let mut roots: Vec<&Path> = Vec::new();
+        let mut symbols = Vec::new();
+
+        for project in self
+            .projects
+            .values()
+            .filter_map(|project| project.as_ref().ok()?.as_ref())
+        {
+            if roots.contains(&project.root.as_path()) {
+                continue;
+            }
+            roots.push(&project.root);
+            let Some(contents) = self.book_contents(project) else {
+                continue;
+            };
+
+            let mut symbol = |name: String, kind: u32, path: &str, line: usize, container: &str| {
+                let lowercase = name.to_lowercase();
+                let mut chars = lowercase.chars();
+                if query.chars().all(|c| chars.any(|n| n == c)) {
+                    symbols.push(json!({
+                        "name": name,
+                        "kind": kind,
+                        "location": {
+                            "uri": path_to_uri(&project.root.join(path)),
+                            "range": range_json(Range::at_line_start(line)),
+                        },
+                        "containerName": container,
+                    }));
+                }
+            };
+
+            let mut part = String::new();
+            for (index, item) in contents.items.iter().enumerate() {
+                if item.missing {
+                    continue;
+                }
+                if item.kind == ItemKind::Part {
+                    part = item.title.clone();
+                    // A part without a page points at its first chapter.
+                    let target = item.path.as_deref().or_else(|| {
+                        contents.items[index + 1..]
+                            .iter()
+                            .find(|next| !next.missing && next.kind != ItemKind::Part)
+                            .and_then(|next| next.path.as_deref())
+                    });
+                    if let Some(path) = target {
+                        let line = if item.path.is_some() { item.line } else { 0 };
+                        symbol(item.title.clone(), SYMBOL_NAMESPACE, path, line, "");
+                    }
+                    continue;
+                }
+                if !item.in_part {
+                    part.clear();
+                }
+                let path = item.path.as_deref().unwrap_or_default();
+                let heading = item.heading();
+                symbol(heading.clone(), SYMBOL_FILE, path, item.line, &part);
+                for section in &item.sections {
+                    let name = match &section.number {
+                        Some(number) => format!("{} {}", number, section.title),
+                        None => section.title.clone(),
+                    };
+                    symbol(name, SYMBOL_STRING, path, section.line, &heading);
+                }
+            }
+        }
+        Value::Array(symbols)
+    }
+
+    /// Footnote navigation: a reference leads to its note, a note's marker
+    /// back to its references.
+    fn definition(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (Some(text), Some(position)) =
+            (self.document(uri), parse_position(&params["position"]))
+        else {
+            return Value::Null;
+        };
+        locations_json(uri, &footnotes::navigate(text, position))
+    }
+
+    fn references(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (Some(text), Some(position)) =
+            (self.document(uri), parse_position(&params["position"]))
+        else {
+            return Value::Null;
+        };
+        let include_note = params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(true);
+        locations_json(uri, &footnotes::references_at(text, position, include_note))
+    }
+
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let (Some(text), Some(range)) = (self.document(uri), parse_range(&params["range"])) else {
+            return json!([]);
+        };
+
+        let mut actions = callouts::code_actions(text, range);
+        actions.extend(knitr::code_actions(text, range));
+        actions.extend(reference_links::code_actions(text, range));
+        actions.extend(footnotes::code_actions(text, range));
+
+        let line = range.start.line as usize;
+        let runs = repl::plan(text, line, RunScope::Cell).map(|_| run_action_json(uri, line));
+
+        Value::Array(
+            actions
+                .iter()
+                .map(|action| code_action_json(uri, action))
+                .chain(runs)
+                .collect(),
+        )
+    }
+
+    /// Runs `quarto.runCell`. The result is the run plan, and a
+    /// `window/showDocument` request selecting the code is queued too.
+    fn execute_command(
+        &mut self,
+        params: &Value,
+        requests: &mut Vec<Value>,
+    ) -> Result<Value, Value> {
+        let command = params["command"].as_str().unwrap_or_default();
+        if command != RUN_CELL_COMMAND {
+            return Err(
+                json!({ "code": -32602, "message": format!("Unknown command: {command}") }),
+            );
+        }
+        let uri = params["arguments"][0].as_str().unwrap_or_default();
+        let line = params["arguments"][1].as_u64().unwrap_or_default() as usize;
+
+        let Some(plan) = self
+            .document(uri)
+            .and_then(|text| repl::plan(text, line, RunScope::Cell))
+        else {
+            return Ok(Value::Null);
+        };
+
+        for run in &plan.runs {
+            self.next_request_id += 1;
+            requests.push(json!({
+                "jsonrpc": "2.0",
+                "id": self.next_request_id,
+                "method": "window/showDocument",
+                "params": {
+                    "uri": uri,
+                    "takeFocus": true,
+                    "selection": range_json(run.range),
+                },
+            }));
+        }
+        Ok(run_plan_json(&plan))
+    }
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1, "save": {} },
+            "workspaceSymbolProvider": true,
+            "definitionProvider": true,
+            "referencesProvider": true,
+            "codeActionProvider": {
+                "codeActionKinds": ["quickfix", "refactor.inline", "refactor.rewrite", "source"],
+            },
+            "executeCommandProvider": {
+                "commands": [RUN_CELL_COMMAND],
+            },
+        },
+        "serverInfo": {
+            "name": "quarto-ls",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// Local path of a `file://` URI.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    percent_decode(uri.strip_prefix("file://")?).map(PathBuf::from)
+}
+
+/// `file://` URI of a local path, percent-encoding what URIs do not allow.
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub fn parse_position(value: &Value) -> Option<Position> {
+    Some(Position::new(
+        value["line"].as_u64()? as u32,
+        value["character"].as_u64()? as u32,
+    ))
+}
+
+pub fn parse_range(value: &Value) -> Option<Range> {
+    Some(Range::new(
+        parse_position(&value["start"])?,
+        parse_position(&value["end"])?,
+    ))
+}
+
+fn position_json(position: Position) -> Value {
+    json!({ "line": position.line, "character": position.character })
+}
+
+pub fn range_json(range: Range) -> Value {
+    json!({ "start": position_json(range.start), "end": position_json(range.end) })
+}
+
+/// Locations in one document; `null` when there are none.
+fn locations_json(uri: &str, ranges: &[Range]) -> Value {
+    if ranges.is_empty() {
+        return Value::Null;
+    }
+    ranges
+        .iter()
+        .map(|&range| json!({ "uri": uri, "range": range_json(range) }))
+        .collect()
+}
+
+fn text_edit_json(edit: &TextEdit) -> Value {
+    json!({ "range": range_json(edit.range), "newText": edit.new_text })
+}
+
+pub fn diagnostic_json(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "range": range_json(diagnostic.range),
+        "severity": diagnostic.severity as u8,
+        "code": diagnostic.code,
+        "source": "quarto",
+        "message": diagnostic.message,
+    })
+}
+
+pub fn code_action_json(uri: &str, action: &CodeAction) -> Value {
+    let edits: Vec<Value> = action.edits.iter().map(text_edit_json).collect();
+    json!({
+        "title": action.title,
+        "kind": action.kind,
+        "edit": { "changes": { uri: edits } },
+    })
+}
+
+fn run_action_json(uri: &str, line: usize) -> Value {
+    json!({
+        "title": "Run cell",
+        "kind": "source",
+        "command": {
+            "title": "Run cell",
+            "command": RUN_CELL_COMMAND,
+            "arguments": [uri, line],
+        },
+    })
+}
+
+pub fn run_plan_json(plan: &RunPlan) -> Value {
+    let runs: Vec<Value> = plan
+        .runs
+        .iter()
+        .map(
+            |run| json!({ "kernel": run.kernel, "code": run.code, "range": range_json(run.range) }),
+        )
+        .collect();
+    json!({ "runs": runs })
+}
@@ -0,0 +1,323 @@
+//! Conversion between Jupyter notebooks (`.ipynb`, nbformat 4) and `.qmd` documents.
+//!
+//! Follows `quarto convert`: markdown cells become text, code cells become
+//! executable cells in the kernel's language, and cell metadata becomes `#|`
+//! options. Going back, a notebook's YAML front matter is kept as a leading raw
+//! cell, `#| tags` returns to cell metadata and other options stay in the
+//! source, where Quarto reads them, and are copied into the metadata too so a
+//! round trip keeps it. Outputs are dropped in both directions.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::cells::{self, option_prefix};
+use crate::text::fence_for;
+
+/// Cell metadata that only matters to Jupyter front ends.
+const FRONTEND_METADATA: [&str; 9] = [
+    "collapsed",
+    "scrolled",
+    "jupyter",
+    "execution",
+    "editable",
+    "deletable",
+    "slideshow",
+    "vscode",
+    "trusted",
+];
+
+/// Converts notebook JSON to `.qmd` text.
+pub fn ipynb_to_qmd(notebook_json: &str) -> Result<String, String> {
+    let notebook: Value =
+        serde_json::from_str(notebook_json).map_err(|e| format!("Invalid notebook JSON: {}", e))?;
+    if notebook["nbformat"].as_u64() != Some(4) {
+        return Err("Only nbformat 4 notebooks are supported".to_string());
+    }
+
+    let kernel = notebook["metadata"]["kernelspec"]["name"].as_str();
+    let language = notebook["metadata"]["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| notebook["metadata"]["language_info"]["name"].as_str())
+        .unwrap_or("python")
+        .to_lowercase();
+
+    let cells = notebook["cells"]
+        .as_array()
+        .ok_or_else(|| "Notebook has no cells array".to_string())?;
+
+    let mut blocks = Vec::new();
+    let mut has_front_matter = false;
+
+    for (index, cell) in cells.iter().enumerate() {
+        let source = cell_source(&cell["source"]);
+        match cell["cell_type"].as_str() {
+            Some("code") => blocks.push(code_block(&language, &cell["metadata"], &source)),
+            Some("raw") if index == 0 && source.starts_with("---") => {
+                has_front_matter = true;
+                blocks.push(with_jupyter_key(&source, kernel));
+            }
+            _ => {
+                if !source.trim().is_empty() {
+                    blocks.push(source.trim_end().to_string());
+                }
+            }
+        }
+    }
+
+    if let (false, Some(kernel)) = (has_front_matter, kernel) {
+        blocks.insert(0, format!("---\njupyter: {}\n---", kernel));
+    }
+
+    Ok(blocks.join("\n\n") + "\n")
+}
+
+/// Converts `.qmd` text to pretty-printed nbformat 4 JSON.
+pub fn qmd_to_ipynb(qmd: &str) -> String {
+    let lines: Vec<&str> = qmd.lines().collect();
+    let mut notebook_cells = Vec::new();
+    let mut markdown_start = 0;
+
+    let front_matter_end = front_matter_end(&lines);
+    if let Some(end) = front_matter_end {
+        notebook_cells.push(("raw", lines[..=end].join("\n"), Map::new()));
+        markdown_start = end + 1;
+    }
+
+    let cells = cells::cells(qmd);
+    for cell in &cells {
+        push_markdown(&mut notebook_cells, &lines[markdown_start..cell.open_line]);
+
+        let mut metadata = Map::new();
+        let tags = cell.options.iter().find(|option| option.key == "tags");
+        if let Some(tags) = tags {
+            metadata.insert("tags".to_string(), json!(parse_tags(&tags.value)));
+        }
+        for option in cell.options.iter().filter(|option| option.key != "tags") {
+            metadata.insert(option.key.clone(), metadata_value(&option.value));
+        }
+        let source: Vec<&str> = cell
+            .content_lines(lines.len())
+            .filter(|index| !tags.is_some_and(|tags| (tags.line..=tags.end_line).contains(index)))
+            .map(|index| lines[index])
+            .collect();
+        notebook_cells.push(("code", source.join("\n"), metadata));
+        markdown_start = cell.close_line.map_or(lines.len(), |close| close + 1);
+    }
+    push_markdown(
+        &mut notebook_cells,
+        &lines[markdown_start.min(lines.len())..],
+    );
+
+    let language = cells
+        .first()
+        .map(|cell| cell.language.to_lowercase())
+        .unwrap_or_else(|| "python".to_string());
+    let kernel = front_matter_end
+        .and_then(|end| {
+            lines[1..end]
+                .iter()
+                .find_map(|line| line.strip_prefix("jupyter:"))
+                .map(|value| value.trim().trim_matches('"').to_string())
+        })
+        .filter(|kernel| !kernel.is_empty())
+        .unwrap_or_else(|| default_kernel(&language).0.to_string());
+
+    let cells: Vec<Value> = notebook_cells
+        .into_iter()
+        .enumerate()
+        .map(|(index, (cell_type, source, metadata))| {
+            let mut cell = json!({
+                "cell_type": cell_type,
+                "id": format!("cell-{}", index),
+                "metadata": metadata,
+                "source": source_lines(&source),
+            });
+            if cell_type == "code" {
+                cell["execution_count"] = Value::Null;
+                cell["outputs"] = json!([]);
+            }
+            cell
+        })
+        .collect();
+
+    let notebook = json!({
+        "cells": cells,
+        "metadata": {
+            "kernelspec": {
+                "name": kernel,
+                "language": language,
+                "display_name": default_kernel(&language).1,
+            },
+            "language_info": { "name": language },
+        },
+        "nbformat": 4,
+        "nbformat_minor": 5,
+    });
+
+    // Jupyter writes notebooks with one-space indentation.
+    let mut buffer = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    notebook
+        .serialize(&mut serializer)
+        .expect("notebook JSON serializes");
+    String::from_utf8(buffer).expect("serde_json writes UTF-8") + "\n"
+}
+
+/// Kernel name and display name Jupyter uses by default for `language`.
+fn default_kernel(language: &str) -> (&'static str, &'static str) {
+    match language {
+        "r" => ("ir", "R"),
+        "julia" => ("julia", "Julia"),
+        "bash" => ("bash", "Bash"),
+        _ => ("python3", "Python 3"),
+    }
+}
+
+/// Notebook sources are either a string or a list of lines with their newlines.
+fn cell_source(source: &Value) -> String {
+    match source {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn source_lines(source: &str) -> Vec<&str> {
+    source.split_inclusive('\n').collect()
+}
+
+fn code_block(language: &str, metadata: &Value, source: &str) -> String {
+    let prefix = option_prefix(language);
+    let fence = fence_for(source);
+    let mut lines = vec![format!("{}{{{}}}", fence, language)];
+
+    if let Some(metadata) = metadata.as_object() {
+        if let Some(tags) = metadata.get("tags").filter(|tags| !is_empty(tags)) {
+            lines.push(format!("{} tags: {}", prefix, yaml_inline(tags)));
+        }
+        // Options already in the source (copied there by qmd_to_ipynb) win.
+        let declared = source_option_keys(prefix, source);
+        for (key, value) in metadata {
+            if key != "tags"
+                && !FRONTEND_METADATA.contains(&key.as_str())
+                && !declared.contains(&key.as_str())
+                && !is_empty(value)
+            {
+                lines.push(format!("{} {}: {}", prefix, key, yaml_inline(value)));
+            }
+        }
+    }
+
+    let source = source.trim_end_matches('\n');
+    if !source.is_empty() {
+        lines.push(source.to_string());
+    }
+    lines.push(fence);
+    lines.join("\n")
+}
+
+/// Keys of the `#|` option lines that open a cell's source.
+fn source_option_keys<'a>(prefix: &str, source: &'a str) -> Vec<&'a str> {
+    source
+        .lines()
+        .map_while(|line| line.strip_prefix(prefix))
+        .filter_map(|option| option.split_once(':'))
+        .map(|(key, _)| key.trim())
+        .filter(|key| !key.is_empty() && !key.contains(' '))
+        .collect()
+}
+
+/// A `#|` option value as cell metadata: parsed as YAML, or kept as text when
+/// it is not valid YAML on its own.
+fn metadata_value(value: &str) -> Value {
+    serde_yaml_ng::from_str(value).unwrap_or_else(|_| Value::String(value.trim().to_string()))
+}
+
+/// Adds `jupyter: <kernel>` to front matter that lacks it.
+fn with_jupyter_key(front_matter: &str, kernel: Option<&str>) -> String {
+    let front_matter = front_matter.trim_end();
+    match kernel {
+        Some(kernel) if !front_matter.lines().any(|l| l.starts_with("jupyter:")) => {
+            let body = front_matter.trim_end_matches("---").trim_end();
+            format!("{}\njupyter: {}\n---", body, kernel)
+        }
+        _ => front_matter.to_string(),
+    }
+}
+
+fn front_matter_end(lines: &[&str]) -> Option<usize> {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return None;
+    }
+    lines
+        .iter()
+        .skip(1)
+        .position(|line| matches!(line.trim_end(), "---" | "..."))
+        .map(|offset| offset + 1)
+}
+
+fn push_markdown(cells: &mut Vec<(&'static str, String, Map<String, Value>)>, lines: &[&str]) {
+    let text = lines.join("\n");
+    let text = text.trim_matches('\n');
+    if !text.trim().is_empty() {
+        cells.push(("markdown", text.to_string(), Map::new()));
+    }
+}
+
+/// Reads `[a, b]`, `a` or a YAML block list.
+fn parse_tags(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let items: Vec<&str> = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(inner) => inner.split(',').collect(),
+        None if value.starts_with("- ") => value
+            .lines()
+            .map(|line| line.trim().trim_start_matches("- "))
+            .collect(),
+        None => vec![value],
+    };
+    items
+        .into_iter()
+        .map(|item| item.trim().trim_matches('"').trim_matches('\'').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Single-line YAML for a JSON value. JSON strings are valid YAML scalars, so
+/// only strings that need quoting keep their quotes.
+fn yaml_inline(value: &Value) -> String {
+    match value {
+        Value::String(text) => {
+            let plain = !text.is_empty()
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+            if plain && !matches!(text.as_str(), "true" | "false" | "null") {
+                text.clone()
+            } else {
+                value.to_string()
+            }
+        }
+        Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(yaml_inline).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Object(map) => format!(
+            "{{{}}}",
+            map.iter()
+                .map(|(k, v)| format!("{}: {}", k, yaml_inline(v)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,365 @@
+//! A compact document outline for the `/quarto-outline` slash command.
+//!
+//! Covers the structure `outline.scm` and `tags.scm` expose to Zed (ATX and
+//! setext headings, fenced divs with ids) plus executable cells, callouts and
+//! figure/table labels, so the assistant can be handed the shape of a long
+//! report instead of its full text.
+//!
+//! The outline is scanned from the text, not parsed with tree-sitter-quarto:
+//! `/quarto-outline` runs in the WASM extension, which has no grammar to parse
+//! with, and `quarto-ls` builds its anchors and book numbering on the same
+//! entries. `tests/outline.rs` checks that headings, cells and fenced divs
+//! start where the grammar's `atx_heading`/`setext_heading`,
+//! `executable_code_cell` and `fenced_div` nodes do.
+
+use std::ops::Range as ByteRange;
+
+use crate::attributes::Attributes;
+use crate::callouts::{self, Callout};
+use crate::cells;
+use crate::text::verbatim_lines;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    Heading {
+        level: usize,
+        /// `#sec-...` or other id from the heading's attribute list.
+        id: Option<String>,
+        /// Marked `{.unnumbered}` or `{-}`, so Quarto gives it no number.
+        unnumbered: bool,
+    },
+    Cell {
+        language: String,
+        label: Option<String>,
+    },
+    Callout {
+        kind: String,
+    },
+    /// A labelled figure or table that is not a cell: an image, a pipe table
+    /// caption or a fenced div such as `::: {#fig-map}`.
+    Float {
+        label: String,
+    },
+}
+
+/// One line of the outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+    pub kind: EntryKind,
+    /// Zero-based first and last line the entry covers. A heading covers its
+    /// whole section.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Heading text, callout title or caption.
+    pub title: Option<String>,
+    /// Nesting under headings, zero for top-level entries.
+    pub depth: usize,
+}
+
+/// Outline entries in document order.
+pub fn outline(text: &str) -> Vec<OutlineEntry> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let callouts = callouts::callouts(text);
+
+    // Headings that open a callout are its title, not a section.
+    let title_lines: Vec<usize> = callouts
+        .iter()
+        .filter_map(|callout| title_heading_line(callout, &lines))
+        .collect();
+
+    let mut entries = Vec::new();
+    for (index, (level, title, attributes)) in headings(&lines, &verbatim) {
+        if title_lines.contains(&index) {
+            continue;
+        }
+        let unnumbered = attributes.has_class("unnumbered") || attributes.has_class("-");
+        entries.push(OutlineEntry {
+            kind: EntryKind::Heading {
+                level,
+                id: attributes.id,
+                unnumbered,
+            },
+            start_line: index,
+            end_line: index,
+            title: Some(title),
+            depth: 0,
+        });
+    }
+
+    for cell in cells::cells(text) {
+        let caption = ["fig-cap", "tbl-cap", "lst-cap"]
+            .iter()
+            .find_map(|key| cell.option_or_header(key))
+            .map(|caption| caption.trim_matches('"').to_string());
+        entries.push(OutlineEntry {
+            kind: EntryKind::Cell {
+                language: cell.language.clone(),
+                label: cell.label().map(str::to_string),
+            },
+            start_line: cell.open_line,
+            end_line: cell.close_line.unwrap_or(lines.len().saturating_sub(1)),
+            title: caption,
+            depth: 0,
+        });
+    }
+
+    for callout in &callouts {
+        entries.push(OutlineEntry {
+            kind: EntryKind::Callout {
+                kind: callout.kind.clone(),
+            },
+            start_line: callout.div.open_line,
+            end_line: callout
+                .div
+                .close_line
+                .unwrap_or(lines.len().saturating_sub(1)),
+            title: callout_title(callout, &lines),
+            depth: 0,
+        });
+    }
+
+    for div in callouts::fenced_divs(text).divs {
+        if let Some(label) = div.attributes.id.clone().filter(|id| is_float_label(id)) {
+            let end_line = div.close_line.unwrap_or(lines.len().saturating_sub(1));
+            // Pandoc takes the caption from the last paragraph of the div.
+            let caption = lines
+                .get(div.open_line + 1..end_line)
+                .unwrap_or_default()
+                .iter()
+                .rev()
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty() && !line.starts_with("!["))
+                .map(str::to_string);
+            entries.push(OutlineEntry {
+                kind: EntryKind::Float { label },
+                start_line: div.open_line,
+                end_line,
+                title: caption,
+                depth: 0,
+            });
+        }
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        if let Some((label, caption)) = inline_float(line) {
+            entries.push(OutlineEntry {
+                kind: EntryKind::Float { label },
+                start_line: index,
+                end_line: index,
+                title: caption,
+                depth: 0,
+            });
+        }
+    }
+
+    entries.sort_by_key(|entry| (entry.start_line, std::cmp::Reverse(entry.end_line)));
+    nest(&mut entries, lines.len());
+    entries
+}
+
+/// Renders the outline as one line per entry, returning the text and a
+/// `(byte range, label)` section for each entry.
+pub fn render(entries: &[OutlineEntry]) -> (String, Vec<(ByteRange<usize>, String)>) {
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for entry in entries {
+        let start = text.len();
+        let indent = "  ".repeat(entry.depth);
+        let summary = summary(entry);
+        let lines = if entry.start_line == entry.end_line {
+            format!("line {}", entry.start_line + 1)
+        } else {
+            format!("lines {}-{}", entry.start_line + 1, entry.end_line + 1)
+        };
+        text.push_str(&format!("{indent}{summary} ({lines})\n"));
+        sections.push((start..text.len(), format!("{summary} ({lines})")));
+    }
+
+    (text, sections)
+}
+
+fn summary(entry: &OutlineEntry) -> String {
+    let title = entry.title.as_deref();
+    match &entry.kind {
+        EntryKind::Heading { level, id, .. } => {
+            let mut summary = format!("{} {}", "#".repeat(*level), title.unwrap_or_default());
+            if let Some(id) = id {
+                summary.push_str(&format!(" {{#{id}}}"));
+            }
+            summary
+        }
+        EntryKind::Cell { language, label } => {
+            let mut summary = format!("cell {{{language}}}");
+            if let Some(label) = label {
+                summary.push_str(&format!(" #{label}"));
+            }
+            if let Some(caption) = title {
+                summary.push_str(&format!(": {caption}"));
+            }
+            summary
+        }
+        EntryKind::Callout { kind } => match title {
+            Some(title) => format!("callout-{kind}: {title}"),
+            None => format!("callout-{kind}"),
+        },
+        EntryKind::Float { label } => match title {
+            Some(caption) => format!("@{label}: {caption}"),
+            None => format!("@{label}"),
+        },
+    }
+}
+
+/// Extends headings over their sections and nests every entry under the
+/// headings that contain it.
+fn nest(entries: &mut [OutlineEntry], line_count: usize) {
+    let headings: Vec<(usize, usize)> = entries
+        .iter()
+        .filter_map(|entry| match entry.kind {
+            EntryKind::Heading { level, .. } => Some((entry.start_line, level)),
+            _ => None,
+        })
+        .collect();
+
+    for entry in entries.iter_mut() {
+        if let EntryKind::Heading { level, .. } = entry.kind {
+            entry.end_line = headings
+                .iter()
+                .find(|&&(line, other)| line > entry.start_line && other <= level)
+                .map_or(line_count.saturating_sub(1), |&(line, _)| line - 1);
+        }
+    }
+
+    // Depth is the number of enclosing headings, plus one per enclosing
+    // callout or div for entries nested inside them.
+    let spans: Vec<(usize, usize, bool)> = entries
+        .iter()
+        .map(|entry| {
+            let is_heading = matches!(entry.kind, EntryKind::Heading { .. });
+            (entry.start_line, entry.end_line, is_heading)
+        })
+        .collect();
+    for (index, entry) in entries.iter_mut().enumerate() {
+        let is_heading = matches!(entry.kind, EntryKind::Heading { .. });
+        entry.depth = spans
+            .iter()
+            .enumerate()
+            .filter(|&(other, &(start, end, other_is_heading))| {
+                other != index
+                    && start <= entry.start_line
+                    && entry.end_line <= end
+                    && (start, std::cmp::Reverse(end))
+                        < (entry.start_line, std::cmp::Reverse(entry.end_line))
+                    && (other_is_heading || !is_heading)
+            })
+            .count();
+    }
+}
+
+/// A heading's level, text and attributes.
+type Heading = (usize, String, Attributes);
+
+/// ATX and setext headings with their line.
+fn headings(lines: &[&str], verbatim: &[bool]) -> Vec<(usize, Heading)> {
+    let mut headings = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        if let Some(heading) = atx_heading(line) {
+            headings.push((index, heading));
+            continue;
+        }
+        let underline = line.trim();
+        let level = match underline.chars().next() {
+            Some('=') if underline.chars().all(|c| c == '=') => 1,
+            Some('-') if underline.len() >= 3 && underline.chars().all(|c| c == '-') => 2,
+            _ => continue,
+        };
+        let Some(previous) = index.checked_sub(1) else {
+            continue;
+        };
+        let text = lines[previous].trim();
+        let starts_paragraph = previous == 0 || lines[previous - 1].trim().is_empty();
+        if !verbatim[previous]
+            && !text.is_empty()
+            && starts_paragraph
+            && atx_heading(text).is_none()
+        {
+            let (title, attributes) = split_attributes(text);
+            headings.push((previous, (level, title, attributes)));
+        }
+    }
+    headings
+}
+
+fn atx_heading(line: &str) -> Option<Heading> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let rest = rest.trim().trim_end_matches('#').trim_end();
+    let (title, attributes) = split_attributes(rest);
+    Some((level, title, attributes))
+}
+
+/// Splits `Title {#sec-x .unnumbered}` into the title and its attributes.
+fn split_attributes(text: &str) -> (String, Attributes) {
+    match text.rfind(" {").filter(|_| text.ends_with('}')) {
+        Some(start) => (
+            text[..start].trim().to_string(),
+            Attributes::parse(&text[start..]),
+        ),
+        None => (text.to_string(), Attributes::default()),
+    }
+}
+
+fn title_heading_line(callout: &Callout, lines: &[&str]) -> Option<usize> {
+    let end = callout.div.close_line.unwrap_or(lines.len());
+    (callout.div.open_line + 1..end)
+        .find(|&index| !lines[index].trim().is_empty())
+        .filter(|&index| atx_heading(lines[index]).is_some())
+}
+
+fn callout_title(callout: &Callout, lines: &[&str]) -> Option<String> {
+    if let Some(title) = callout.div.attributes.get("title") {
+        return Some(title.to_string());
+    }
+    title_heading_line(callout, lines)
+        .and_then(|index| atx_heading(lines[index]))
+        .map(|(_, title, _)| title)
+}
+
+/// Cross-reference prefixes for figures, tables and listings.
+fn is_float_label(id: &str) -> bool {
+    ["fig-", "tbl-", "lst-"]
+        .iter()
+        .any(|prefix| id.starts_with(prefix))
+}
+
+/// `![Caption](plot.png){#fig-plot}` or a table caption `: Caption {#tbl-x}`.
+fn inline_float(line: &str) -> Option<(String, Option<String>)> {
+    let trimmed = line.trim();
+    let attributes_start = trimmed.rfind('{').filter(|_| trimmed.ends_with('}'))?;
+    let label = Attributes::parse(&trimmed[attributes_start..])
+        .id
+        .filter(|id| is_float_label(id))?;
+
+    let caption = if let Some(image) = trimmed.strip_prefix("![") {
+        image.find("](").map(|end| image[..end].to_string())
+    } else if let Some(caption) = trimmed.strip_prefix(": ") {
+        Some(caption[..attributes_start - 2].trim().to_string())
+    } else {
+        return None;
+    };
+    Some((label, caption.filter(|caption| !caption.is_empty())))
+}
@@ -0,0 +1,838 @@
+//! The Quarto project a document belongs to, read from `_quarto.yml`.
+//!
+//! Only the settings the extension acts on are modelled: project type, output
+//! directory, render list, execution directory and freezing, bibliography,
+//! book chapters and the website navbar. A `_quarto-<profile>.yml` next to
+//! `_quarto.yml` is merged over it when that profile is requested. Quarto
+//! also accepts the `.yaml` extension for both.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_yaml_ng::Value;
+
+/// Project configuration file, as named in messages.
+pub const PROJECT_FILE: &str = "_quarto.yml";
+
+/// Extensions Quarto accepts for `_quarto` and `_quarto-<profile>` files.
+const CONFIG_EXTENSIONS: [&str; 2] = ["yml", "yaml"];
+
+/// Extensions of the Markdown-based documents Quarto renders.
+pub const SOURCE_EXTENSIONS: [&str; 4] = ["qmd", "Rmd", "rmd", "md"];
+
+/// Slash command flag that selects a profile: `--profile dev` or `--profile=dev`.
+pub const PROFILE_FLAG: &str = "--profile";
+
+/// Rendered output and caches that are never project sources.
+const OUTPUT_DIRECTORIES: [&str; 4] = ["_site", "_book", "_manuscript", "_freeze"];
+
+/// Package and environment directories Quarto ignores when listing a project.
+const VENDORED_DIRECTORIES: [&str; 6] = [
+    "node_modules",
+    "renv",
+    "packrat",
+    "rsconnect",
+    "venv",
+    "env",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectType {
+    /// `type: default`, or no type at all.
+    Default,
+    Website,
+    Book,
+    Manuscript,
+    /// An extension-provided type such as `confluence`.
+    Other(String),
+}
+
+/// `execute: freeze:` — whether computations are re-run on project render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freeze {
+    /// Always re-run (`false`, the default).
+    Off,
+    /// Never re-run on project render (`true`).
+    On,
+    /// Re-run only when the source changed (`auto`).
+    Auto,
+}
+
+/// `project: execute-dir:` — where code cells run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecuteDir {
+    /// The document's directory (the default).
+    File,
+    /// The project root.
+    Project,
+}
+
+/// One entry of `book: chapters:` or `book: appendices:`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chapter {
+    File(String),
+    /// `- part: "Title"` (or `part: intro.qmd`) with its own chapters.
+    Part {
+        part: String,
+        chapters: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    pub title: Option<String>,
+    pub chapters: Vec<Chapter>,
+    pub appendices: Vec<Chapter>,
+}
+
+impl Book {
+    /// Chapter files in reading order, including part pages and appendices.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files = Vec::new();
+        for entry in self.chapters.iter().chain(&self.appendices) {
+            match entry {
+                Chapter::File(file) => files.push(file.as_str()),
+                Chapter::Part { part, chapters } => {
+                    if is_source_file(part) {
+                        files.push(part.as_str());
+                    }
+                    files.extend(chapters.iter().map(String::as_str));
+                }
+            }
+        }
+        files
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NavItem {
+    pub text: Option<String>,
+    pub href: Option<String>,
+    /// Items of a dropdown menu.
+    pub menu: Vec<NavItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Navbar {
+    pub left: Vec<NavItem>,
+    pub right: Vec<NavItem>,
+}
+
+/// One `website: sidebar:` entry. Sections are items whose `menu` holds
+/// their `contents:`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sidebar {
+    pub title: Option<String>,
+    pub contents: Vec<NavItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Website {
+    pub title: Option<String>,
+    pub navbar: Option<Navbar>,
+    pub sidebar: Vec<Sidebar>,
+}
+
+impl Website {
+    /// Every navbar and sidebar item, menus and sections included.
+    pub fn nav_items(&self) -> Vec<&NavItem> {
+        fn collect<'a>(items: &'a [NavItem], all: &mut Vec<&'a NavItem>) {
+            for item in items {
+                all.push(item);
+                collect(&item.menu, all);
+            }
+        }
+        let mut all = Vec::new();
+        if let Some(navbar) = &self.navbar {
+            collect(&navbar.left, &mut all);
+            collect(&navbar.right, &mut all);
+        }
+        for sidebar in &self.sidebar {
+            collect(&sidebar.contents, &mut all);
+        }
+        all
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// Directory that holds `_quarto.yml`.
+    pub root: PathBuf,
+    /// Profile merged into the configuration, if any.
+    pub profile: Option<String>,
+    pub kind: ProjectType,
+    /// `project: output-dir:` as written.
+    pub output_dir: Option<String>,
+    /// `project: render:` patterns; empty means every source file.
+    pub render: Vec<String>,
+    pub execute_dir: ExecuteDir,
+    pub freeze: Freeze,
+    /// `number-sections:`, on by default for books.
+    pub number_sections: bool,
+    /// `number-depth:`, the deepest heading level that gets a number.
+    pub number_depth: Option<usize>,
+    /// Bibliography files, relative to the root.
+    pub bibliography: Vec<String>,
+    pub book: Option<Book>,
+    pub website: Option<Website>,
+}
+
+impl Project {
+    /// Nearest directory at or above `path` that contains `_quarto.yml` or
+    /// `_quarto.yaml`.
+    pub fn find_root(path: &Path) -> Option<PathBuf> {
+        let start = if path.is_dir() { path } else { path.parent()? };
+        start
+            .ancestors()
+            .find(|dir| config_file(dir, "_quarto").is_some())
+            .map(Path::to_path_buf)
+    }
+
+    /// The project's `_quarto.yml`, or `_quarto.yaml` when that is the one used.
+    pub fn config_path(&self) -> PathBuf {
+        config_file(&self.root, "_quarto").unwrap_or_else(|| self.root.join(PROJECT_FILE))
+    }
+
+    /// The project containing `path`, or `None` for a standalone document.
+    pub fn discover(path: &Path, profile: Option<&str>) -> Result<Option<Self>, String> {
+        match Self::find_root(path) {
+            Some(root) => Self::load(&root, profile).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads `_quarto.yml` in `root`, merged with `_quarto-<profile>.yml`.
+    pub fn load(root: &Path, profile: Option<&str>) -> Result<Self, String> {
+        let read = |stem: &str| {
+            let path = config_file(root, stem).unwrap_or_else(|| root.join(format!("{stem}.yml")));
+            fs::read_to_string(&path).map_err(|e| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                format!("Could not read {}: {}", name, e)
+            })
+        };
+        let base = read("_quarto")?;
+        let profile_yaml = match profile {
+            Some(profile) => Some(read(&format!("_quarto-{}", profile))?),
+            None => None,
+        };
+        Self::parse(root.to_path_buf(), &base, profile_yaml.as_deref()).map(|project| Self {
+            profile: profile.map(str::to_string),
+            ..project
+        })
+    }
+
+    /// Builds the project from `_quarto.yml` text and an optional profile file.
+    pub fn parse(root: PathBuf, yaml: &str, profile_yaml: Option<&str>) -> Result<Self, String> {
+        let mut config: Value = parse_yaml(PROJECT_FILE, yaml)?;
+        if let Some(profile_yaml) = profile_yaml {
+            merge(&mut config, parse_yaml("profile", profile_yaml)?);
+        }
+        let raw: RawConfig = serde_yaml_ng::from_value(config)
+            .map_err(|e| format!("Invalid {}: {}", PROJECT_FILE, e))?;
+
+        let kind = match raw.project.kind.as_deref() {
+            None | Some("default") => ProjectType::Default,
+            Some("website") => ProjectType::Website,
+            Some("book") => ProjectType::Book,
+            Some("manuscript") => ProjectType::Manuscript,
+            Some(other) => ProjectType::Other(other.to_string()),
+        };
+        let freeze = match raw.execute.freeze {
+            Some(Value::Bool(true)) => Freeze::On,
+            Some(Value::String(value)) if value == "auto" => Freeze::Auto,
+            _ => Freeze::Off,
+        };
+        let execute_dir = match raw.project.execute_dir.as_deref() {
+            Some("project") => ExecuteDir::Project,
+            _ => ExecuteDir::File,
+        };
+
+        let number_sections = raw.number_sections.unwrap_or(kind == ProjectType::Book);
+
+        Ok(Self {
+            root,
+            profile: None,
+            kind,
+            output_dir: raw.project.output_dir,
+            render: raw.project.render,
+            execute_dir,
+            freeze,
+            number_sections,
+            number_depth: raw.number_depth,
+            bibliography: raw.bibliography.into_vec(),
+            book: raw.book.map(|book| Book {
+                title: book.title,
+                chapters: book
+                    .chapters
+                    .into_iter()
+                    .map(RawChapter::into_chapter)
+                    .collect(),
+                appendices: book
+                    .appendices
+                    .into_iter()
+                    .map(RawChapter::into_chapter)
+                    .collect(),
+            }),
+            website: raw.website.map(|website| Website {
+                title: website.title,
+                navbar: website.navbar.map(|navbar| Navbar {
+                    left: navbar.left.into_iter().map(RawNavItem::into_item).collect(),
+                    right: navbar
+                        .right
+                        .into_iter()
+                        .map(RawNavItem::into_item)
+                        .collect(),
+                }),
+                sidebar: match website.sidebar {
+                    None => Vec::new(),
+                    Some(RawSidebars::One(sidebar)) => vec![sidebar],
+                    Some(RawSidebars::Many(sidebars)) => sidebars,
+                }
+                .into_iter()
+                .map(|sidebar| Sidebar {
+                    title: sidebar.title,
+                    contents: nav_contents(sidebar.contents),
+                })
+                .collect(),
+            }),
+        })
+    }
+
+    /// Profiles with a `_quarto-<profile>.yml` (or `.yaml`) file in `root`.
+    pub fn profiles(root: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(root) else {
+            return Vec::new();
+        };
+        let mut profiles: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let (stem, extension) = name.strip_prefix("_quarto-")?.rsplit_once('.')?;
+                (!stem.is_empty() && CONFIG_EXTENSIONS.contains(&extension))
+                    .then(|| stem.to_string())
+            })
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        profiles
+    }
+
+    /// Where `quarto render` writes output, relative to the root. `None` when
+    /// output is written next to each document.
+    pub fn output_dir(&self) -> Option<&str> {
+        self.output_dir.as_deref().or(match self.kind {
+            ProjectType::Website => Some("_site"),
+            ProjectType::Book => Some("_book"),
+            ProjectType::Manuscript => Some("_manuscript"),
+            _ => None,
+        })
+    }
+
+    /// `path` relative to the project root, with `/` separators.
+    pub fn relative(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(
+            relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    /// Whether `quarto render` renders the source at `relative`.
+    ///
+    /// Quarto skips files whose name starts with `_` or `.`, and a book without
+    /// a render list renders only its chapters. With a render list, a file is
+    /// rendered if the last pattern that matches it is not negated.
+    pub fn renders(&self, relative: &str) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        if name.starts_with(['_', '.']) || !is_source_file(relative) {
+            return false;
+        }
+        if self.render.is_empty() {
+            return match &self.book {
+                Some(book) if self.kind == ProjectType::Book => {
+                    relative == "index.qmd" || book.files().contains(&relative)
+                }
+                _ => true,
+            };
+        }
+        self.render
+            .iter()
+            .fold(false, |rendered, pattern| match pattern.strip_prefix('!') {
+                Some(negated) if glob_matches(negated, relative) => false,
+                None if glob_matches(pattern, relative) => true,
+                _ => rendered,
+            })
+    }
+
+    /// Source documents `quarto render` would render, as `(relative path, text)`.
+    pub fn documents(&self) -> Vec<(String, String)> {
+        files(&self.root, &SOURCE_EXTENSIONS)
+            .into_iter()
+            .filter(|(path, _)| {
+                self.output_dir()
+                    .is_none_or(|dir| !path.starts_with(&format!("{dir}/")))
+            })
+            .filter(|(path, _)| self.renders(path))
+            .collect()
+    }
+
+    /// Bibliography files as paths under the root.
+    pub fn bibliography_paths(&self) -> Vec<PathBuf> {
+        self.bibliography
+            .iter()
+            .map(|path| self.root.join(path))
+            .collect()
+    }
+
+    /// One-line description for slash command output: `book project "My Book"`.
+    pub fn describe(&self) -> String {
+        let kind = match &self.kind {
+            ProjectType::Default => "project",
+            ProjectType::Website => "website",
+            ProjectType::Book => "book",
+            ProjectType::Manuscript => "manuscript",
+            ProjectType::Other(other) => other,
+        };
+        let title = self
+            .book
+            .as_ref()
+            .and_then(|book| book.title.as_deref())
+            .or_else(|| self.website.as_ref().and_then(|site| site.title.as_deref()));
+        let mut description = match title {
+            Some(title) => format!("{} \"{}\"", kind, title),
+            None => kind.to_string(),
+        };
+        if let Some(profile) = &self.profile {
+            description.push_str(&format!(", profile: {}", profile));
+        }
+        description
+    }
+}
+
+/// Splits `--profile <name>` (or `--profile=<name>`) out of slash command
+/// arguments, returning the profile and the remaining arguments.
+pub fn take_profile_arg(args: &[String]) -> Result<(Option<String>, Vec<String>), String> {
+    let mut profile = None;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let name = if arg == PROFILE_FLAG {
+            args.next().cloned()
+        } else if let Some(name) = arg
+            .strip_prefix(PROFILE_FLAG)
+            .and_then(|n| n.strip_prefix('='))
+        {
+            Some(name.to_string())
+        } else {
+            rest.push(arg.clone());
+            continue;
+        };
+        match name.filter(|name| !name.is_empty() && !name.starts_with('-')) {
+            Some(name) if profile.is_none() => profile = Some(name),
+            Some(_) => return Err(format!("{} can only be given once", PROFILE_FLAG)),
+            None => return Err(format!("{} needs a profile name", PROFILE_FLAG)),
+        }
+    }
+    Ok((profile, rest))
+}
+
+/// Completions for the argument being typed (the last of `args`) when it is
+/// a profile: the flag itself, or the profiles of the project containing the
+/// document argument, resolved against `worktree_root`, or the worktree.
+pub fn profile_completions(args: &[String], worktree_root: &Path) -> Vec<String> {
+    let Some((current, before)) = args.split_last() else {
+        return Vec::new();
+    };
+    let document = before
+        .iter()
+        .enumerate()
+        .find(|(index, arg)| {
+            !arg.starts_with('-') && (*index == 0 || before[index - 1] != PROFILE_FLAG)
+        })
+        .map(|(_, arg)| worktree_root.join(arg));
+    let profiles = || match Project::find_root(document.as_deref().unwrap_or(worktree_root)) {
+        Some(root) => Project::profiles(&root),
+        None => Vec::new(),
+    };
+
+    if before.last().is_some_and(|arg| arg == PROFILE_FLAG) {
+        profiles()
+            .into_iter()
+            .filter(|profile| profile.starts_with(current.as_str()))
+            .collect()
+    } else if let Some(partial) = current
+        .strip_prefix(PROFILE_FLAG)
+        .and_then(|rest| rest.strip_prefix('='))
+    {
+        profiles()
+            .into_iter()
+            .filter(|profile| profile.starts_with(partial))
+            .map(|profile| format!("{}={}", PROFILE_FLAG, profile))
+            .collect()
+    } else if current.starts_with('-') && PROFILE_FLAG.starts_with(current.as_str()) {
+        vec![PROFILE_FLAG.to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Files under `root` with one of `extensions`, as `(path relative to root,
+/// text)` pairs sorted by path.
+///
+/// Skips hidden directories, rendered output (`_site`, `_book`, `_freeze`, …)
+/// and vendored packages (`node_modules`, `renv`, …).
+pub fn files(root: &Path, extensions: &[&str]) -> Vec<(String, String)> {
+    let mut documents = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            if name.starts_with('.') || OUTPUT_DIRECTORIES.contains(&name) {
+                continue;
+            }
+            if path.is_dir() {
+                if VENDORED_DIRECTORIES.contains(&name) {
+                    continue;
+                }
+                pending.push(path);
+            } else if extensions
+                .iter()
+                .any(|extension| path.extension().is_some_and(|e| e == *extension))
+            {
+                if let Ok(text) = fs::read_to_string(&path) {
+                    let relative = path.strip_prefix(root).unwrap_or(&path);
+                    let relative = relative
+                        .components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    documents.push((relative, text));
+                }
+            }
+        }
+    }
+    documents.sort();
+    documents
+}
+
+/// Byte range of the value on a `_quarto.yml` line when it is exactly `value`:
+/// a list item (`- intro.qmd`) or the value of one of `keys`
+/// (`- href: "about.qmd"`), quoted or not, ignoring a trailing comment.
+pub fn config_value_range(
+    line: &str,
+    keys: &[&str],
+    value: &str,
+) -> Option<std::ops::Range<usize>> {
+    let mut rest = line.trim_start();
+    if let Some(item) = rest.strip_prefix("- ") {
+        rest = item.trim_start();
+    }
+    if let Some((key, after)) = rest.split_once(':') {
+        if keys.contains(&key.trim()) {
+            rest = after.trim_start();
+        }
+    }
+    let start = line.len() - rest.len();
+    let mut written = rest.split(" #").next().unwrap_or_default().trim_end();
+    let mut offset = start;
+    for quote in ['"', '\''] {
+        if let Some(inner) = written
+            .strip_prefix(quote)
+            .and_then(|inner| inner.strip_suffix(quote))
+        {
+            written = inner;
+            offset += 1;
+            break;
+        }
+    }
+    (written == value).then(|| offset..offset + value.len())
+}
+
+fn is_source_file(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| SOURCE_EXTENSIONS.contains(&extension))
+}
+
+/// `*` and `?` match within a path segment, `**` across segments. A pattern
+/// without a `/` matches the file name anywhere, as in Quarto's render lists.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./");
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return segment_matches(pattern.as_bytes(), name.as_bytes()) || pattern == path;
+    }
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            segments_match(&pattern[1..], path)
+                || (!path.is_empty() && segments_match(pattern, &path[1..]))
+        }
+        (Some(segment), Some(name)) => {
+            segment_matches(segment.as_bytes(), name.as_bytes())
+                && segments_match(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+fn segment_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            segment_matches(&pattern[1..], name)
+                || (!name.is_empty() && segment_matches(pattern, &name[1..]))
+        }
+        (Some(b'?'), Some(_)) => segment_matches(&pattern[1..], &name[1..]),
+        (Some(a), Some(b)) if a == b => segment_matches(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// A document's YAML front matter: the lines between a leading `---` and the
+/// next `---` or `...`. `None` without front matter or when it is not YAML.
+pub fn front_matter(text: &str) -> Option<Value> {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.first()?.trim_end() != "---" {
+        return None;
+    }
+    let end = lines
+        .iter()
+        .skip(1)
+        .position(|line| matches!(line.trim_end(), "---" | "..."))?;
+    serde_yaml_ng::from_str(&lines[1..end + 1].join("\n")).ok()
+}
+
+/// `<stem>.yml` or `<stem>.yaml` in `dir`, whichever exists, `.yml` first.
+fn config_file(dir: &Path, stem: &str) -> Option<PathBuf> {
+    CONFIG_EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{stem}.{extension}")))
+        .find(|path| path.is_file())
+}
+
+fn parse_yaml(name: &str, yaml: &str) -> Result<Value, String> {
+    if yaml.trim().is_empty() {
+        return Ok(Value::Mapping(Default::default()));
+    }
+    serde_yaml_ng::from_str(yaml).map_err(|e| format!("Invalid {}: {}", name, e))
+}
+
+/// Merges a profile over the base configuration the way Quarto does: mappings
+/// merge key by key, lists are concatenated without duplicates, and other
+/// values are replaced.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(overlay)) => {
+            for value in overlay {
+                if !base.contains(&value) {
+                    base.push(value);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawConfig {
+    project: RawProject,
+    execute: RawExecute,
+    #[serde(rename = "number-sections")]
+    number_sections: Option<bool>,
+    #[serde(rename = "number-depth")]
+    number_depth: Option<usize>,
+    bibliography: OneOrMany,
+    book: Option<RawBook>,
+    website: Option<RawWebsite>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "kebab-case")]
+struct RawProject {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    output_dir: Option<String>,
+    render: Vec<String>,
+    execute_dir: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawExecute {
+    freeze: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(untagged)]
+enum OneOrMany {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::None => Vec::new(),
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawBook {
+    title: Option<String>,
+    chapters: Vec<RawChapter>,
+    appendices: Vec<RawChapter>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawChapter {
+    File(String),
+    Part {
+        part: String,
+        #[serde(default)]
+        chapters: Vec<RawChapter>,
+    },
+    Link {
+        href: String,
+    },
+}
+
+impl RawChapter {
+    fn into_chapter(self) -> Chapter {
+        match self {
+            RawChapter::File(file) | RawChapter::Link { href: file } => Chapter::File(file),
+            RawChapter::Part { part, chapters } => Chapter::Part {
+                part,
+                chapters: chapters
+                    .into_iter()
+                    .filter_map(|chapter| match chapter.into_chapter() {
+                        Chapter::File(file) => Some(file),
+                        Chapter::Part { .. } => None,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawWebsite {
+    title: Option<String>,
+    navbar: Option<RawNavbar>,
+    sidebar: Option<RawSidebars>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSidebars {
+    One(RawSidebar),
+    Many(Vec<RawSidebar>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawSidebar {
+    title: Option<String>,
+    contents: Option<Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawNavbar {
+    left: Vec<RawNavItem>,
+    right: Vec<RawNavItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawNavItem {
+    Href(String),
+    Item {
+        #[serde(default)]
+        text: Option<String>,
+        #[serde(default)]
+        href: Option<String>,
+        #[serde(default)]
+        menu: Vec<RawNavItem>,
+        /// Sidebar sections use `section:` and `contents:` instead.
+        #[serde(default)]
+        section: Option<String>,
+        #[serde(default)]
+        contents: Option<Value>,
+    },
+}
+
+impl RawNavItem {
+    fn into_item(self) -> NavItem {
+        match self {
+            RawNavItem::Href(href) => NavItem {
+                text: None,
+                href: Some(href),
+                menu: Vec::new(),
+            },
+            RawNavItem::Item {
+                text,
+                href,
+                menu,
+                section,
+                contents,
+            } => NavItem {
+                text: text.or(section),
+                href,
+                menu: menu
+                    .into_iter()
+                    .map(RawNavItem::into_item)
+                    .chain(nav_contents(contents))
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Items of a sidebar `contents:` list. `contents: auto` and entries that are
+/// not items (such as `- auto: "*.qmd"`) have no links to follow.
+fn nav_contents(contents: Option<Value>) -> Vec<NavItem> {
+    let Some(Value::Sequence(items)) = contents else {
+        return Vec::new();
+    };
+    items
+        .into_iter()
+        .filter_map(|item| serde_yaml_ng::from_value::<RawNavItem>(item).ok())
+        .map(RawNavItem::into_item)
+        .collect()
+}
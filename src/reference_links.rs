@@ -0,0 +1,323 @@
+//! Reference-style links: `[text][ref]`, `[text][]` and `[ref]` uses matched
+//! against `[ref]: url "title"` definitions.
+//!
+//! `tests/reference_links.rs` covers how the grammar parses these forms and
+//! `tags.scm` marks `link_reference_definition` as a definition. Labels match
+//! case-insensitively with whitespace collapsed, as in Pandoc, and the first
+//! of several definitions for a label wins.
+
+use crate::text::{
+    byte_offset, closing_delimiter, outside_code_spans, utf16_len, verbatim_lines, CodeAction,
+    Diagnostic, Position, Range, Severity, TextEdit,
+};
+
+/// `[ref]: url "title"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// The label as written.
+    pub label: String,
+    pub url: String,
+    pub title: Option<String>,
+    /// Zero-based.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UseKind {
+    /// `[text][ref]`
+    Full,
+    /// `[text][]`
+    Collapsed,
+    /// `[ref]`, only a link when `ref` is defined.
+    Shortcut,
+}
+
+/// A reference link or image in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Use {
+    pub kind: UseKind,
+    pub text: String,
+    /// The label as written; the text for collapsed and shortcut uses.
+    pub label: String,
+    pub image: bool,
+    /// Zero-based line and the byte range of the whole link, `!` included.
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The key labels are matched by: lowercase, with runs of whitespace collapsed.
+pub fn normalize(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Reference definitions in document order, footnotes excluded.
+pub fn definitions(text: &str) -> Vec<Definition> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    lines
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| !verbatim[index])
+        .filter_map(|(index, line)| {
+            definition(line).map(|definition| Definition {
+                line: index,
+                ..definition
+            })
+        })
+        .collect()
+}
+
+fn definition(line: &str) -> Option<Definition> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    if indent > 3 || !rest.starts_with('[') || rest.starts_with("[^") {
+        return None;
+    }
+    let close = closing_delimiter(rest, 0, '[', ']')?;
+    let label = &rest[1..close];
+    let after = rest[close + 1..].strip_prefix(':')?.trim();
+    if label.trim().is_empty() || after.is_empty() {
+        return None;
+    }
+
+    let (url, title) = match after.strip_prefix('<') {
+        Some(inside) => {
+            let end = inside.find('>')?;
+            (&inside[..end], inside[end + 1..].trim())
+        }
+        None => match after.split_once(char::is_whitespace) {
+            Some((url, title)) => (url, title.trim()),
+            None => (after, ""),
+        },
+    };
+    let title = ["\"\"", "''", "()"].iter().find_map(|quotes| {
+        let (open, close) = (quotes.chars().next()?, quotes.chars().nth(1)?);
+        title
+            .strip_prefix(open)?
+            .strip_suffix(close)
+            .map(str::to_string)
+    });
+    Some(Definition {
+        label: label.to_string(),
+        url: url.to_string(),
+        title,
+        line: 0,
+    })
+}
+
+/// Reference uses outside code. Shortcut uses are only reported when their
+/// label is defined, since `[text]` is otherwise plain text.
+pub fn uses(text: &str, definitions: &[Definition]) -> Vec<Use> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let defined: Vec<String> = definitions.iter().map(|d| normalize(&d.label)).collect();
+    let mut found = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] || definition(line).is_some() {
+            continue;
+        }
+        // Second brackets of `[text][ref]`, already part of a use
+        let mut consumed = Vec::new();
+        let brackets: Vec<usize> = outside_code_spans(line)
+            .filter(|&(offset, c)| c == '[' && !line[..offset].ends_with('\\'))
+            .map(|(offset, _)| offset)
+            .collect();
+
+        for open in brackets {
+            if consumed.contains(&open) {
+                continue;
+            }
+            let Some(close) = closing_delimiter(line, open, '[', ']') else {
+                continue;
+            };
+            let link_text = &line[open + 1..close];
+            if link_text.starts_with(['@', '^', '-']) || link_text.trim().is_empty() {
+                continue;
+            }
+            let image = line[..open].ends_with('!');
+            let start = if image { open - 1 } else { open };
+            let after = &line[close + 1..];
+
+            let (kind, label, end) = if after.starts_with('(') || after.starts_with('{') {
+                continue;
+            } else if after.starts_with('[') {
+                let Some(label_close) = closing_delimiter(line, close + 1, '[', ']') else {
+                    continue;
+                };
+                consumed.push(close + 1);
+                let label = &line[close + 2..label_close];
+                if label.is_empty() {
+                    (UseKind::Collapsed, link_text, label_close + 1)
+                } else {
+                    (UseKind::Full, label, label_close + 1)
+                }
+            } else {
+                if !defined.contains(&normalize(link_text)) {
+                    continue;
+                }
+                (UseKind::Shortcut, link_text, close + 1)
+            };
+
+            found.push(Use {
+                kind,
+                text: link_text.to_string(),
+                label: label.to_string(),
+                image,
+                line: index,
+                start,
+                end,
+            });
+        }
+    }
+    found
+}
+
+/// The definition a label resolves to: the first one with a matching label.
+fn resolve<'a>(definitions: &'a [Definition], label: &str) -> Option<&'a Definition> {
+    let label = normalize(label);
+    definitions.iter().find(|d| normalize(&d.label) == label)
+}
+
+/// Undefined references, unused definitions and duplicate labels.
+pub fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = text.lines().collect();
+    let definitions = definitions(text);
+    let uses = uses(text, &definitions);
+    let mut diagnostics = Vec::new();
+
+    for found in &uses {
+        if resolve(&definitions, &found.label).is_none() {
+            diagnostics.push(Diagnostic {
+                range: Range::within_line(found.line, lines[found.line], found.start, found.end),
+                severity: Severity::Warning,
+                code: "undefined-reference",
+                message: format!("No definition for reference [{}]", found.label),
+            });
+        }
+    }
+
+    for (index, definition) in definitions.iter().enumerate() {
+        let key = normalize(&definition.label);
+        let range = Range::whole_line(definition.line, lines[definition.line]);
+        if let Some(first) = definitions[..index]
+            .iter()
+            .find(|earlier| normalize(&earlier.label) == key)
+        {
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Warning,
+                code: "duplicate-reference",
+                message: format!(
+                    "[{}] is already defined on line {}; the first definition is used",
+                    definition.label,
+                    first.line + 1
+                ),
+            });
+        } else if !uses.iter().any(|found| normalize(&found.label) == key) {
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Information,
+                code: "unused-reference",
+                message: format!("Reference definition [{}] is never used", definition.label),
+            });
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    diagnostics
+}
+
+/// For the reference under the cursor: add a missing definition at the end of
+/// the document, or replace a defined reference with an inline link.
+pub fn code_actions(text: &str, range: Range) -> Vec<CodeAction> {
+    let lines: Vec<&str> = text.lines().collect();
+    let definitions = definitions(text);
+    let uses = uses(text, &definitions);
+    let line = range.start.line as usize;
+    let Some(current) = lines.get(line) else {
+        return Vec::new();
+    };
+    let cursor = byte_offset(current, range.start.character);
+    let Some(found) = uses
+        .iter()
+        .find(|found| found.line == line && found.start <= cursor && cursor <= found.end)
+    else {
+        return Vec::new();
+    };
+
+    let Some(definition) = resolve(&definitions, &found.label) else {
+        return vec![CodeAction {
+            title: format!("Add a definition for [{}]", found.label),
+            kind: "quickfix",
+            edits: vec![append_definition(text, &lines, &found.label)],
+        }];
+    };
+
+    let destination = match &definition.title {
+        Some(title) => format!("{} \"{}\"", definition.url, title.replace('"', "\\\"")),
+        None => definition.url.clone(),
+    };
+    let inline = format!(
+        "{}[{}]({})",
+        if found.image { "!" } else { "" },
+        found.text,
+        destination
+    );
+    let mut edits = vec![TextEdit::replace(
+        Range::within_line(found.line, current, found.start, found.end),
+        inline,
+    )];
+
+    // Drop the definition once its only use is inlined
+    let key = normalize(&definition.label);
+    let remaining = uses
+        .iter()
+        .filter(|other| normalize(&other.label) == key)
+        .count();
+    if remaining == 1 {
+        edits.push(TextEdit::replace(
+            Range::new(
+                Position::new(definition.line as u32, 0),
+                Position::new(definition.line as u32 + 1, 0),
+            ),
+            "",
+        ));
+    }
+
+    vec![CodeAction {
+        title: format!("Inline link to {}", definition.url),
+        kind: "refactor.inline",
+        edits,
+    }]
+}
+
+/// `[label]: ` on a new line at the end, after a blank line unless the
+/// document already ends with definitions.
+fn append_definition(text: &str, lines: &[&str], label: &str) -> TextEdit {
+    let last = lines.last().copied().unwrap_or_default();
+    let separator = if lines.is_empty() || definition(last).is_some() {
+        ""
+    } else {
+        "\n"
+    };
+    let new_definition = format!("[{}]: https://", label);
+    if text.is_empty() || text.ends_with('\n') {
+        let end = Position::new(lines.len() as u32, 0);
+        TextEdit::replace(
+            Range::new(end, end),
+            format!("{}{}\n", separator, new_definition),
+        )
+    } else {
+        let end = Position::new(lines.len() as u32 - 1, utf16_len(last));
+        TextEdit::replace(
+            Range::new(end, end),
+            format!("\n{}{}\n", separator, new_definition),
+        )
+    }
+}
@@ -0,0 +1,102 @@
+//! Cell boundaries for sending code to a Jupyter kernel through Zed's REPL.
+//!
+//! Zed's REPL runs the selected text against the kernel for the language under
+//! the cursor, which inside a cell is the injected cell language. These helpers
+//! work out exactly which text to send: the cell body without its `#|` option
+//! lines, or every earlier cell for the same kernel (for `quarto-cell --above`).
+
+use crate::cells::{self, Cell};
+use crate::text::{utf16_len, Position, Range};
+
+/// What a run action covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunScope {
+    /// The cell under the cursor.
+    Cell,
+    /// Every cell above the cursor that uses the same kernel.
+    AllAbove,
+}
+
+/// Code from one cell, ready to send to a kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellRun {
+    /// Kernel language, e.g. `python` for a `{python3}` cell.
+    pub kernel: &'static str,
+    pub code: String,
+    /// The code in the document, excluding fences and option lines.
+    pub range: Range,
+}
+
+/// The cells a run action sends, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunPlan {
+    pub runs: Vec<CellRun>,
+}
+
+/// Jupyter kernel language for a cell engine, or `None` for engines that do
+/// not run in a kernel (`ojs`, `mermaid`, `dot`).
+pub fn kernel_language(engine: &str) -> Option<&'static str> {
+    match engine.to_lowercase().as_str() {
+        "python" | "python3" | "ipython" => Some("python"),
+        "r" => Some("r"),
+        "julia" => Some("julia"),
+        "bash" | "sh" | "shell" => Some("bash"),
+        "sql" => Some("sql"),
+        _ => None,
+    }
+}
+
+/// The code of `cell` as a run, or `None` if the cell is empty or has no kernel.
+pub fn cell_run(cell: &Cell, lines: &[&str]) -> Option<CellRun> {
+    let kernel = kernel_language(&cell.language)?;
+    let code_lines = cell.code_lines(lines.len());
+    if lines[code_lines.clone()]
+        .iter()
+        .all(|l| l.trim().is_empty())
+    {
+        return None;
+    }
+    let last = code_lines.end - 1;
+    Some(CellRun {
+        kernel,
+        code: cell.code(lines),
+        range: Range::new(
+            Position::new(code_lines.start as u32, 0),
+            Position::new(last as u32, utf16_len(lines[last])),
+        ),
+    })
+}
+
+/// Works out what a run action at `line` (zero-based) sends.
+pub fn plan(text: &str, line: usize, scope: RunScope) -> Option<RunPlan> {
+    let lines: Vec<&str> = text.lines().collect();
+    let cells = cells::cells(text);
+    let current = cells.iter().position(|cell| cell.contains(line));
+
+    match scope {
+        RunScope::Cell => Some(RunPlan {
+            runs: vec![cell_run(&cells[current?], &lines)?],
+        }),
+        RunScope::AllAbove => {
+            let above = match current {
+                Some(index) => &cells[..index],
+                None => {
+                    let end = cells.iter().take_while(|c| c.open_line < line).count();
+                    &cells[..end]
+                }
+            };
+            // Inside a cell, only cells for the same kernel run; elsewhere the
+            // kernel of the nearest cell above decides.
+            let kernel = current
+                .map(|index| &cells[index])
+                .or_else(|| above.last())
+                .and_then(|cell| kernel_language(&cell.language))?;
+            let runs: Vec<CellRun> = above
+                .iter()
+                .filter_map(|cell| cell_run(cell, &lines))
+                .filter(|run| run.kernel == kernel)
+                .collect();
+            (!runs.is_empty()).then_some(RunPlan { runs })
+        }
+    }
+}
@@ -0,0 +1,511 @@
+//! Slash commands that read the project from disk, run natively as
+//! `quarto-ls <subcommand> …` on behalf of the extension.
+//!
+//! The extension itself runs in Zed's WASM sandbox, which can read worktree
+//! files one at a time through `Worktree::read_text_file` but cannot list a
+//! directory or reach the host filesystem. Whatever needs the project model —
+//! `_quarto.yml` and its profiles, every document in the project, its
+//! bibliographies, `.quarto-templates/`, book chapters, link targets — is
+//! computed here instead, and printed as JSON for the extension to pass on to
+//! Zed:
+//!
+//! - `quarto-ls project [--default-profile <name>] <path> [--profile <name>]`
+//!   prints the [`ProjectSummary`] of the project containing `path`, or `null`.
+//! - `quarto-ls slash [--default-profile <name>] <command> <root> [args…]`
+//!   prints the [`Output`] of a slash command run in the worktree at `root`.
+//! - `quarto-ls complete <command> <root> [args…]` prints the [`Completion`]s
+//!   for the argument being typed.
+//!
+//! `--default-profile` carries the `lsp.quarto-ls.settings.profile` setting,
+//! which applies only when the project has that profile.
+
+use std::ops::Range as ByteRange;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bibliography;
+use crate::project::{self, ExecuteDir, Project, ProjectType};
+use crate::{book, execute, links, templates, xrefs};
+
+/// Flag carrying the profile from Zed settings, used when none is requested.
+pub const DEFAULT_PROFILE_FLAG: &str = "--default-profile";
+
+/// Most references `/quarto-cite` returns or offers as completions.
+const MAX_CITATIONS: usize = 10;
+
+/// Slash command output: the text and the sections Zed folds it into.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Output {
+    pub text: String,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Section {
+    pub range: ByteRange<usize>,
+    pub label: String,
+}
+
+impl Output {
+    /// `text` as a single section.
+    pub fn single(text: String, label: String) -> Self {
+        Self {
+            sections: vec![Section {
+                range: 0..text.len(),
+                label,
+            }],
+            text,
+        }
+    }
+
+    /// `header` followed by a rendered body, whose section ranges are
+    /// relative to the body.
+    pub fn with_header(
+        header: String,
+        body: String,
+        sections: Vec<(ByteRange<usize>, String)>,
+    ) -> Self {
+        let offset = header.len();
+        Self {
+            text: header + &body,
+            sections: sections
+                .into_iter()
+                .map(|(range, label)| Section {
+                    range: offset + range.start..offset + range.end,
+                    label,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A slash command argument completion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Completion {
+    pub label: String,
+    pub new_text: String,
+    pub run_command: bool,
+}
+
+/// What the extension needs of the project a document belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    pub root: String,
+    /// [`Project::describe`], e.g. `book project "Thesis"`.
+    pub description: String,
+    /// The profile merged into the configuration.
+    pub profile: Option<String>,
+    /// Absolute output directory, when output is not written next to each
+    /// document.
+    pub output_dir: Option<String>,
+    /// Whether code cells run in the project root (`execute-dir: project`)
+    /// rather than the document's directory.
+    pub execute_in_root: bool,
+}
+
+impl From<&Project> for ProjectSummary {
+    fn from(project: &Project) -> Self {
+        Self {
+            root: project.root.to_string_lossy().into_owned(),
+            description: project.describe(),
+            profile: project.profile.clone(),
+            output_dir: project
+                .output_dir()
+                .map(|dir| project.root.join(dir).to_string_lossy().into_owned()),
+            execute_in_root: project.execute_dir == ExecuteDir::Project,
+        }
+    }
+}
+
+/// Runs a `quarto-ls` subcommand and returns the JSON to print, or `None`
+/// when `args` do not name one (the language server is started instead).
+pub fn main(args: &[String]) -> Option<Result<String, String>> {
+    let (subcommand, rest) = args.split_first()?;
+    let (default_profile, rest) = match rest {
+        [flag, name, rest @ ..] if flag == DEFAULT_PROFILE_FLAG => (Some(name.as_str()), rest),
+        _ => (None, rest),
+    };
+    let json = match (subcommand.as_str(), rest) {
+        ("project", [path, args @ ..]) => {
+            project::take_profile_arg(args).and_then(|(profile, _)| {
+                let project = project_with_profile(Path::new(path), profile, default_profile)?;
+                Ok(to_json(&project.as_ref().map(ProjectSummary::from)))
+            })
+        }
+        ("slash", [command, root, args @ ..]) => {
+            run(command, Path::new(root), args, default_profile).map(|output| to_json(&output))
+        }
+        ("complete", [command, root, args @ ..]) => {
+            Ok(to_json(&complete(command, Path::new(root), args)))
+        }
+        ("project" | "slash" | "complete", _) => Err(format!(
+            "Usage: quarto-ls {} [{} <name>] …",
+            subcommand, DEFAULT_PROFILE_FLAG
+        )),
+        _ => return None,
+    };
+    Some(json)
+}
+
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("slash command output serializes")
+}
+
+/// Runs the slash command `command` in the worktree at `root`, with
+/// `default_profile` from settings.
+pub fn run(
+    command: &str,
+    root: &Path,
+    args: &[String],
+    default_profile: Option<&str>,
+) -> Result<Output, String> {
+    match command {
+        "quarto-xrefs" if args.is_empty() => project_cross_references(root),
+        "quarto-cite" => cite(root, args),
+        "quarto-new" => new_from_template(Some(root), args),
+        "quarto-book" => book_contents(root, args, default_profile),
+        "quarto-check-links" => check_links(root, args),
+        _ => Err(format!("{} does not run in quarto-ls", command)),
+    }
+}
+
+/// Completions for the last of `args` to `command`, in the worktree at `root`.
+pub fn complete(command: &str, root: &Path, args: &[String]) -> Vec<Completion> {
+    match command {
+        "quarto-preview" | "quarto-render" | "quarto-book" => {
+            project::profile_completions(args, root)
+                .into_iter()
+                .map(|new_text| Completion {
+                    label: new_text.clone(),
+                    run_command: new_text != project::PROFILE_FLAG,
+                    new_text,
+                })
+                .collect()
+        }
+        "quarto-cite" => project_bibliography(root)
+            .map(|entries| {
+                bibliography::search(&entries, &args.join(" "))
+                    .into_iter()
+                    .take(MAX_CITATIONS)
+                    .map(|entry| Completion {
+                        label: format!(
+                            "{}: {} {}",
+                            entry.key,
+                            entry.short_authors(),
+                            entry.title.as_deref().unwrap_or_default()
+                        ),
+                        new_text: entry.key.clone(),
+                        run_command: true,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "quarto-new" => template_completions(Some(root), args),
+        "quarto-run-cell" => cell_label_completions(root, args),
+        _ => Vec::new(),
+    }
+}
+
+/// The project containing `path`, with `profile` merged in. Without one,
+/// `default_profile` applies when the project has it.
+pub fn project_with_profile(
+    path: &Path,
+    profile: Option<String>,
+    default_profile: Option<&str>,
+) -> Result<Option<Project>, String> {
+    let Some(root) = Project::find_root(path) else {
+        return match profile {
+            Some(profile) => Err(format!(
+                "Profile {} needs a Quarto project, but {} is not in one (no {})",
+                profile,
+                path.display(),
+                project::PROJECT_FILE
+            )),
+            None => Ok(None),
+        };
+    };
+
+    let profiles = Project::profiles(&root);
+    let profile = match profile {
+        Some(profile) if !profiles.contains(&profile) => {
+            return Err(format!(
+                "Unknown profile {}: no _quarto-{}.yml in {} (available: {})",
+                profile,
+                profile,
+                root.display(),
+                if profiles.is_empty() {
+                    "none".to_string()
+                } else {
+                    profiles.join(", ")
+                }
+            ))
+        }
+        Some(profile) => Some(profile),
+        None => default_profile
+            .filter(|profile| profiles.iter().any(|p| p == profile))
+            .map(str::to_string),
+    };
+    Project::load(&root, profile.as_deref()).map(Some)
+}
+
+/// Cross-reference table for every document in the project at `root`, or
+/// in the worktree when it is not a project.
+fn project_cross_references(root: &Path) -> Result<Output, String> {
+    let (scope, documents) = match Project::discover(root, None)? {
+        Some(project) => (project.describe(), project.documents()),
+        None => (
+            display_name(root),
+            project::files(root, &project::SOURCE_EXTENSIONS),
+        ),
+    };
+    if documents.is_empty() {
+        return Err(format!("No Quarto documents found in {}", root.display()));
+    }
+    Ok(cross_references(&scope, &documents))
+}
+
+/// `/quarto-xrefs` output for `documents`, described as `scope`.
+pub fn cross_references(scope: &str, documents: &[(String, String)]) -> Output {
+    let table = xrefs::table(documents);
+    if table.entries.is_empty() && table.dangling.is_empty() {
+        let text = format!("{} has no cross-reference labels", scope);
+        return Output::single(text, "Quarto Cross-references".to_string());
+    }
+
+    let text = format!(
+        "Cross-references in {}:\n\n{}",
+        scope,
+        xrefs::render(&table, documents.len() > 1)
+    );
+    let label = format!(
+        "Cross-references: {} labels, {} unused, {} dangling",
+        table.entries.len(),
+        table.unused().count(),
+        table.dangling.len()
+    );
+    Output::single(text, label)
+}
+
+/// Search the project's bibliographies and return `[@key]` citations, one
+/// section per matching reference.
+fn cite(root: &Path, args: &[String]) -> Result<Output, String> {
+    let query = args.join(" ");
+    if query.trim().is_empty() {
+        return Err("Please specify an author, title, year or key to search for".to_string());
+    }
+    let entries = project_bibliography(root)?;
+    let matches = bibliography::search(&entries, &query);
+    if matches.is_empty() {
+        return Err(format!("No references match '{}'", query));
+    }
+
+    let mut text = String::new();
+    let mut sections = Vec::new();
+    for entry in matches.into_iter().take(MAX_CITATIONS) {
+        let start = text.len();
+        text.push_str(&format!("{}\n{}\n", entry.citation(), entry.reference()));
+        sections.push(Section {
+            range: start..text.len(),
+            label: format!("@{}: {}", entry.key, entry.short_authors()),
+        });
+        text.push('\n');
+    }
+    Ok(Output { text, sections })
+}
+
+fn project_bibliography(root: &Path) -> Result<Vec<bibliography::Entry>, String> {
+    let entries = bibliography::project_entries(root)?;
+    if entries.is_empty() {
+        return Err(format!(
+            "No .bib or CSL-JSON bibliography found in {}",
+            root.display()
+        ));
+    }
+    Ok(entries)
+}
+
+/// `/quarto-new <template> <name> [engine]`: files from a built-in template,
+/// or from `.quarto-templates/` in the worktree at `root`.
+pub fn new_from_template(root: Option<&Path>, args: &[String]) -> Result<Output, String> {
+    let (template, name) = match args {
+        [template, name, ..] => (template.as_str(), name.as_str()),
+        _ => return Err("Usage: /quarto-new <template> <name> [python|r|julia]".to_string()),
+    };
+    let engine = templates::Engine::named(args.get(2).map_or("python", String::as_str))?;
+
+    // Custom templates take precedence, so teams can override built-ins
+    let files = match root.and_then(|root| templates::load_custom(root, template)) {
+        Some(files) => templates::custom(&files, name, engine),
+        None => templates::builtin(template, name, engine)?,
+    };
+
+    let mut header = format!("New {} from the {} template.", name, template);
+    let project = match root {
+        Some(root) => Project::discover(root, None)?,
+        None => None,
+    };
+    match project {
+        Some(project) if project.kind == ProjectType::Book => header.push_str(&format!(
+            " Add {}.qmd to `book: chapters:` in {} to include it in the {}.",
+            name,
+            project::PROJECT_FILE,
+            project.describe()
+        )),
+        Some(project) if project.kind == ProjectType::Website => header.push_str(&format!(
+            " Link {}.qmd from the navbar in {} to make it reachable in the {}.",
+            name,
+            project::PROJECT_FILE,
+            project.describe()
+        )),
+        _ => {}
+    }
+    header.push_str(" Save each file below:\n\n");
+    let (body, sections) = templates::render(&files);
+    Ok(Output::with_header(header, body, sections))
+}
+
+/// Template names for `/quarto-new`, custom ones from `root` first, then
+/// engines for the third argument.
+pub fn template_completions(root: Option<&Path>, args: &[String]) -> Vec<Completion> {
+    let completion = |label: String, new_text: &str, run_command| Completion {
+        label,
+        new_text: new_text.to_string(),
+        run_command,
+    };
+
+    match args.len() {
+        0 | 1 => {
+            let custom = root
+                .map(templates::custom_template_names)
+                .unwrap_or_default();
+            custom
+                .iter()
+                .map(|name| {
+                    completion(
+                        format!("{}: {}", name, templates::CUSTOM_TEMPLATE_DIR),
+                        name,
+                        false,
+                    )
+                })
+                .chain(
+                    templates::BUILTIN_TEMPLATES
+                        .iter()
+                        .filter(|(name, _)| !custom.iter().any(|c| c == name))
+                        .map(|(name, description)| {
+                            completion(format!("{}: {}", name, description), name, false)
+                        }),
+                )
+                .collect()
+        }
+        3 => templates::ENGINES
+            .iter()
+            .map(|engine| completion(engine.to_string(), engine, true))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The reading order of the book project at `root`.
+fn book_contents(
+    root: &Path,
+    args: &[String],
+    default_profile: Option<&str>,
+) -> Result<Output, String> {
+    let (profile, _) = project::take_profile_arg(args)?;
+    let project = project_with_profile(root, profile, default_profile)?
+        .filter(|project| project.kind == ProjectType::Book)
+        .ok_or_else(|| format!("{} is not a Quarto book project", root.display()))?;
+    let contents = book::contents(&project, book::read_from(&project.root))
+        .ok_or_else(|| format!("{} has no `book:` section", project::PROJECT_FILE))?;
+
+    let header = format!("Reading order of the {}:\n\n", project.describe());
+    let (body, sections) = book::render(&contents);
+    Ok(Output::with_header(header, body, sections))
+}
+
+/// Relative links, anchors and website navigation in the document named by
+/// `args`, or in every document of the project at `root` when there is none.
+fn check_links(root: &Path, args: &[String]) -> Result<Output, String> {
+    let (scope, root, documents, navigation) = match args.first() {
+        None => match Project::discover(root, None)? {
+            Some(project) => (
+                project.describe(),
+                project.root.clone(),
+                project.documents(),
+                links::check_navigation(&project),
+            ),
+            None => (
+                display_name(root),
+                root.to_path_buf(),
+                project::files(root, &project::SOURCE_EXTENSIONS),
+                Vec::new(),
+            ),
+        },
+        Some(file) => {
+            let path = root.join(file);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+            let root = match Project::find_root(&path) {
+                Some(root) => root,
+                None => path.parent().unwrap_or(Path::new("/")).to_path_buf(),
+            };
+            let relative = path.strip_prefix(&root).map_or_else(
+                |_| path.to_string_lossy().into_owned(),
+                |relative| relative.to_string_lossy().into_owned(),
+            );
+            (
+                display_name(&path),
+                root,
+                vec![(relative, source)],
+                Vec::new(),
+            )
+        }
+    };
+
+    let mut checked = 0;
+    let results: Vec<(String, Vec<links::BrokenLink>)> = documents
+        .iter()
+        .map(|(path, text)| {
+            checked += links::links(text).len();
+            let broken = links::check_document(&root, &root.join(path), text);
+            (path.clone(), broken)
+        })
+        .collect();
+
+    let header = format!("Links in {}: ", scope);
+    let (body, sections) = links::render(&results, &navigation, checked);
+    let mut output = Output::with_header(header, body, sections);
+    if output.sections.is_empty() {
+        output.sections.push(Section {
+            range: 0..output.text.len(),
+            label: "Quarto Links".to_string(),
+        });
+    }
+    Ok(output)
+}
+
+/// Completes `<file>#` with every `#| label:` in the file, relative to `root`.
+fn cell_label_completions(root: &Path, args: &[String]) -> Vec<Completion> {
+    let Some((file, _)) = args.last().and_then(|arg| arg.rsplit_once('#')) else {
+        return Vec::new();
+    };
+    let Ok(source) = std::fs::read_to_string(root.join(file)) else {
+        return Vec::new();
+    };
+    execute::labels(&source)
+        .into_iter()
+        .map(|label| Completion {
+            new_text: format!("{}#{}", file, label),
+            label,
+            run_command: true,
+        })
+        .collect()
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
@@ -0,0 +1,485 @@
+//! Document and project scaffolding for the `/quarto-new` slash command.
+//!
+//! Built-in templates cover the common Quarto formats; teams can add their own
+//! under `.quarto-templates/` in the worktree, either as a single `.qmd` file or
+//! as a directory of files for a whole project.
+
+use std::fs;
+use std::ops::Range as ByteRange;
+use std::path::Path;
+
+use crate::text::fence_for;
+
+/// Directory in the worktree that holds custom templates.
+pub const CUSTOM_TEMPLATE_DIR: &str = ".quarto-templates";
+
+/// Built-in templates with a short description.
+pub const BUILTIN_TEMPLATES: [(&str, &str); 6] = [
+    ("article", "HTML/PDF article with a figure and a table"),
+    ("presentation", "Reveal.js slides"),
+    ("book-chapter", "Chapter for a Quarto book"),
+    ("dashboard", "Dashboard with value boxes and cards"),
+    (
+        "manuscript",
+        "Manuscript project with _quarto.yml and references",
+    ),
+    ("website-page", "Page for a Quarto website"),
+];
+
+/// Engines the templates can write example cells for.
+pub const ENGINES: [&str; 3] = ["python", "r", "julia"];
+
+/// A file the template produces, relative to the worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// Example cells in one engine's language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Engine {
+    pub language: &'static str,
+    /// Front matter key that selects the engine, if one is needed.
+    front_matter: &'static str,
+    setup: &'static str,
+    plot: &'static str,
+    table: &'static str,
+    value: &'static str,
+}
+
+impl Engine {
+    pub fn named(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "python" | "jupyter" => Ok(Self {
+                language: "python",
+                front_matter: "jupyter: python3",
+                setup: "import numpy as np\nimport pandas as pd\nimport matplotlib.pyplot as plt",
+                plot: "x = np.linspace(0, 10, 200)\nplt.plot(x, np.sin(x))\nplt.show()",
+                table: "pd.DataFrame({\"x\": [1, 2, 3], \"y\": [2.1, 3.9, 6.2]})",
+                value: "dict(value = 42)",
+            }),
+            "r" | "knitr" => Ok(Self {
+                language: "r",
+                front_matter: "engine: knitr",
+                setup: "library(ggplot2)",
+                plot: "ggplot(mtcars, aes(wt, mpg)) +\n  geom_point()",
+                table: "knitr::kable(head(mtcars))",
+                value: "list(value = nrow(mtcars))",
+            }),
+            "julia" => Ok(Self {
+                language: "julia",
+                front_matter: "engine: julia",
+                setup: "using Plots",
+                plot: "plot(sin, 0, 2π)",
+                table: "[(x = i, y = i^2) for i in 1:3]",
+                value: "Dict(\"value\" => 42)",
+            }),
+            other => Err(format!(
+                "Unknown engine '{}'. Use one of: {}",
+                other,
+                ENGINES.join(", ")
+            )),
+        }
+    }
+
+    fn cell(&self, options: &[(&str, &str)], code: &str) -> String {
+        let mut cell = format!("```{{{}}}\n", self.language);
+        for (key, value) in options {
+            cell.push_str(&format!("#| {}: {}\n", key, value));
+        }
+        cell.push_str(code);
+        cell.push_str("\n```\n");
+        cell
+    }
+}
+
+/// Files for the built-in `template`, named after `name` (`my-analysis`).
+pub fn builtin(template: &str, name: &str, engine: Engine) -> Result<Vec<GeneratedFile>, String> {
+    let title = title_from_name(name);
+    let file = |contents: String| {
+        vec![GeneratedFile {
+            path: format!("{}.qmd", name),
+            contents,
+        }]
+    };
+
+    match template {
+        "article" => Ok(file(article(&title, name, engine))),
+        "presentation" | "revealjs" => Ok(file(presentation(&title, engine))),
+        "book-chapter" | "chapter" => Ok(file(book_chapter(&title, name, engine))),
+        "dashboard" => Ok(file(dashboard(&title, engine))),
+        "manuscript" => Ok(manuscript(&title, name, engine)),
+        "website-page" | "page" => Ok(file(website_page(&title, engine))),
+        other => Err(format!(
+            "Unknown template '{}'. Built-in templates: {}",
+            other,
+            BUILTIN_TEMPLATES
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// A template found in `.quarto-templates/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomTemplate {
+    /// `.quarto-templates/<template>.qmd`
+    Document(String),
+    /// `.quarto-templates/<template>/`, as `(path within the directory, contents)`.
+    Project(Vec<(String, String)>),
+}
+
+/// Files for a custom template. A document becomes `<name>.qmd`; a project
+/// is placed under `<name>/`. `{{title}}`, `{{name}}` and `{{engine}}` in
+/// paths and contents are replaced.
+pub fn custom(template: &CustomTemplate, name: &str, engine: Engine) -> Vec<GeneratedFile> {
+    let title = title_from_name(name);
+    let fill = |text: &str| {
+        text.replace("{{title}}", &title)
+            .replace("{{name}}", name)
+            .replace("{{engine}}", engine.language)
+    };
+
+    match template {
+        CustomTemplate::Document(contents) => vec![GeneratedFile {
+            path: format!("{}.qmd", name),
+            contents: fill(contents),
+        }],
+        CustomTemplate::Project(files) => files
+            .iter()
+            .map(|(path, contents)| GeneratedFile {
+                path: format!("{}/{}", name, fill(path)),
+                contents: fill(contents),
+            })
+            .collect(),
+    }
+}
+
+/// Names of the custom templates under `root`: `.qmd` files and directories
+/// in `.quarto-templates/`.
+pub fn custom_template_names(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join(CUSTOM_TEMPLATE_DIR)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                path.file_name()?.to_str().map(str::to_string)
+            } else if path.extension().is_some_and(|ext| ext == "qmd") {
+                path.file_stem()?.to_str().map(str::to_string)
+            } else {
+                None
+            }
+        })
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The custom template `template` under `root`, or `None` if there is none.
+pub fn load_custom(root: &Path, template: &str) -> Option<CustomTemplate> {
+    let dir = root.join(CUSTOM_TEMPLATE_DIR);
+    if let Ok(contents) = fs::read_to_string(dir.join(format!("{}.qmd", template))) {
+        return Some(CustomTemplate::Document(contents));
+    }
+
+    let template_dir = dir.join(template);
+    if !template_dir.is_dir() {
+        return None;
+    }
+    let mut files = Vec::new();
+    let mut pending = vec![template_dir.clone()];
+    while let Some(directory) = pending.pop() {
+        for entry in fs::read_dir(&directory).ok()?.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(contents) = fs::read_to_string(&path) {
+                let relative = path.strip_prefix(&template_dir).unwrap_or(&path);
+                files.push((relative.to_string_lossy().into_owned(), contents));
+            }
+        }
+    }
+    files.sort();
+    Some(CustomTemplate::Project(files))
+}
+
+/// `my-first_analysis` → `My First Analysis`.
+pub fn title_from_name(name: &str) -> String {
+    name.rsplit('/')
+        .next()
+        .unwrap_or(name)
+        .split(['-', '_', ' '])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Slash command text listing each file in a fenced block, with one labelled
+/// byte range per file.
+pub fn render(files: &[GeneratedFile]) -> (String, Vec<(ByteRange<usize>, String)>) {
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for file in files {
+        let start = text.len();
+        let language = match file.path.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("yml" | "yaml") => "yaml",
+            Some("bib") => "bibtex",
+            Some(ext) => ext,
+            None => "",
+        };
+        let fence = fence_for(&file.contents);
+        text.push_str(&format!(
+            "{}:\n\n{}{}\n{}\n{}\n",
+            file.path,
+            fence,
+            language,
+            file.contents.trim_end(),
+            fence
+        ));
+        sections.push((start..text.len(), file.path.clone()));
+        text.push('\n');
+    }
+
+    (text, sections)
+}
+
+fn article(title: &str, name: &str, engine: Engine) -> String {
+    format!(
+        "---
+title: \"{title}\"
+author: \"\"
+date: today
+format:
+  html:
+    toc: true
+  pdf: default
+{front_matter}
+---
+
+## Introduction {{#sec-introduction}}
+
+Describe the question and the data. @fig-{name} shows the main result and
+@tbl-{name} summarises it.
+
+{setup}
+## Results {{#sec-results}}
+
+{plot}
+{table}",
+        front_matter = engine.front_matter,
+        setup = engine.cell(&[("label", "setup"), ("include", "false")], engine.setup),
+        plot = engine.cell(
+            &[
+                ("label", &format!("fig-{name}")),
+                ("fig-cap", "\"Main result\"")
+            ],
+            engine.plot
+        ),
+        table = engine.cell(
+            &[
+                ("label", &format!("tbl-{name}")),
+                ("tbl-cap", "\"Summary\"")
+            ],
+            engine.table
+        ),
+    )
+}
+
+fn presentation(title: &str, engine: Engine) -> String {
+    format!(
+        "---
+title: \"{title}\"
+author: \"\"
+format:
+  revealjs:
+    slide-number: true
+    incremental: false
+{front_matter}
+---
+
+{setup}
+## Overview
+
+- First point
+- Second point
+
+## Code and output
+
+{plot}
+## Summary
+
+::: {{.incremental}}
+- What we found
+- What comes next
+:::
+",
+        front_matter = engine.front_matter,
+        setup = engine.cell(&[("include", "false")], engine.setup),
+        plot = engine.cell(
+            &[("echo", "true"), ("output-location", "fragment")],
+            engine.plot
+        ),
+    )
+}
+
+fn book_chapter(title: &str, name: &str, engine: Engine) -> String {
+    format!(
+        "# {title} {{#sec-{name}}}
+
+Introduce the chapter here. Refer to other chapters with `@sec-` labels.
+
+{setup}
+## Example {{#sec-{name}-example}}
+
+{plot}
+Add the chapter to `book: chapters:` in `_quarto.yml`.
+",
+        setup = engine.cell(&[("include", "false")], engine.setup),
+        plot = engine.cell(
+            &[
+                ("label", &format!("fig-{name}")),
+                ("fig-cap", "\"An example figure\"")
+            ],
+            engine.plot
+        ),
+    )
+}
+
+fn dashboard(title: &str, engine: Engine) -> String {
+    format!(
+        "---
+title: \"{title}\"
+format: dashboard
+{front_matter}
+---
+
+{setup}
+## Row {{height=25%}}
+
+{value}
+## Row {{height=75%}}
+
+{plot}
+{table}",
+        front_matter = engine.front_matter,
+        setup = engine.cell(&[("include", "false")], engine.setup),
+        value = engine.cell(
+            &[
+                ("content", "valuebox"),
+                ("title", "\"Observations\""),
+                ("icon", "table"),
+                ("color", "primary"),
+            ],
+            engine.value
+        ),
+        plot = engine.cell(&[("title", "\"Trend\"")], engine.plot),
+        table = engine.cell(&[("title", "\"Data\"")], engine.table),
+    )
+}
+
+fn manuscript(title: &str, name: &str, engine: Engine) -> Vec<GeneratedFile> {
+    let config = "project:
+  type: manuscript
+
+manuscript:
+  article: index.qmd
+
+format:
+  html:
+    comments:
+      hypothesis: true
+  docx: default
+  jats: default
+
+execute:
+  freeze: true
+"
+    .to_string();
+
+    let article = format!(
+        "---
+title: \"{title}\"
+author:
+  - name: \"\"
+    affiliations:
+      - name: \"\"
+keywords: []
+abstract: |
+  Summarise the work in a paragraph.
+bibliography: references.bib
+{front_matter}
+---
+
+## Introduction
+
+Cite prior work like this [@example2024].
+
+{setup}
+## Results
+
+{plot}
+## References {{.unnumbered}}
+
+::: {{#refs}}
+:::
+",
+        front_matter = engine.front_matter,
+        setup = engine.cell(&[("label", "setup"), ("include", "false")], engine.setup),
+        plot = engine.cell(
+            &[("label", "fig-main"), ("fig-cap", "\"Main result\"")],
+            engine.plot
+        ),
+    );
+
+    let references = "@misc{example2024,
+  title  = {An Example Reference},
+  author = {Doe, Jane},
+  year   = {2024}
+}
+"
+    .to_string();
+
+    [
+        ("_quarto.yml", config),
+        ("index.qmd", article),
+        ("references.bib", references),
+    ]
+    .into_iter()
+    .map(|(path, contents)| GeneratedFile {
+        path: format!("{}/{}", name, path),
+        contents,
+    })
+    .collect()
+}
+
+fn website_page(title: &str, engine: Engine) -> String {
+    format!(
+        "---
+title: \"{title}\"
+description: \"\"
+{front_matter}
+---
+
+Write the page here. Link it from the `website: navbar:` or `sidebar:` in
+`_quarto.yml`.
+
+{plot}",
+        front_matter = engine.front_matter,
+        plot = engine.cell(&[("echo", "false")], engine.plot),
+    )
+}
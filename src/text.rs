@@ -0,0 +1,283 @@
+//! Line-oriented text primitives shared by the language server and slash commands.
+//!
+//! Positions follow the Language Server Protocol: zero-based lines and
+//! character offsets counted in UTF-16 code units.
+
+/// A zero-based position in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    pub fn new(line: u32, character: u32) -> Self {
+        Self { line, character }
+    }
+}
+
+/// A half-open range between two positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// Empty range at the start of `line`, used for insertions.
+    pub fn at_line_start(line: usize) -> Self {
+        let position = Position::new(line as u32, 0);
+        Self::new(position, position)
+    }
+
+    /// Range covering the whole of `line`, excluding its line terminator.
+    pub fn whole_line(line: usize, content: &str) -> Self {
+        Self::new(
+            Position::new(line as u32, 0),
+            Position::new(line as u32, utf16_len(content)),
+        )
+    }
+
+    /// Range covering the bytes `start..end` of `line`.
+    pub fn within_line(line: usize, content: &str, start: usize, end: usize) -> Self {
+        Self::new(
+            Position::new(line as u32, utf16_len(&content[..start])),
+            Position::new(line as u32, utf16_len(&content[..end])),
+        )
+    }
+
+    /// Whether `line` falls inside this range.
+    pub fn contains_line(&self, line: usize) -> bool {
+        let line = line as u32;
+        self.start.line <= line && line <= self.end.line
+    }
+}
+
+/// A replacement of `range` with `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    pub fn insert(line: usize, new_text: impl Into<String>) -> Self {
+        Self {
+            range: Range::at_line_start(line),
+            new_text: new_text.into(),
+        }
+    }
+
+    pub fn replace(range: Range, new_text: impl Into<String>) -> Self {
+        Self {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error = 1,
+    Warning = 2,
+    Information = 3,
+    Hint = 4,
+}
+
+/// A problem found in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: Severity,
+    /// Stable identifier such as `unknown-callout`, used by code actions.
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// A named set of edits offered to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeAction {
+    pub title: String,
+    /// LSP code action kind, e.g. `quickfix` or `refactor.rewrite`.
+    pub kind: &'static str,
+    pub edits: Vec<TextEdit>,
+}
+
+/// Length of `s` in UTF-16 code units.
+pub fn utf16_len(s: &str) -> u32 {
+    s.encode_utf16().count() as u32
+}
+
+/// Byte offset into `line` for a UTF-16 `character` offset, clamped to the line.
+pub fn byte_offset(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (index, ch) in line.char_indices() {
+        if units >= character {
+            return index;
+        }
+        units += ch.len_utf16() as u32;
+    }
+    line.len()
+}
+
+/// Marks lines that belong to YAML front matter or fenced code (delimiters included).
+///
+/// Markdown-level scanners use this to ignore `:::`, `[^1]` and similar syntax that
+/// happens to appear inside code.
+pub fn verbatim_lines(lines: &[&str]) -> Vec<bool> {
+    let mut mask = vec![false; lines.len()];
+
+    let mut start = 0;
+    if lines.first().map(|line| line.trim_end()) == Some("---") {
+        if let Some(end) = lines
+            .iter()
+            .skip(1)
+            .position(|line| matches!(line.trim_end(), "---" | "..."))
+        {
+            for flag in mask.iter_mut().take(end + 2) {
+                *flag = true;
+            }
+            start = end + 2;
+        }
+    }
+
+    let mut open: Option<(char, usize)> = None;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let trimmed = line.trim_start();
+        match open {
+            Some((fence_char, fence_len)) => {
+                mask[index] = true;
+                let run = trimmed.chars().take_while(|&c| c == fence_char).count();
+                if run >= fence_len && trimmed[run..].trim().is_empty() {
+                    open = None;
+                }
+            }
+            None => {
+                if let Some(fence) = code_fence(trimmed) {
+                    mask[index] = true;
+                    open = Some(fence);
+                }
+            }
+        }
+    }
+
+    mask
+}
+
+/// Returns the fence character and length when `trimmed` opens a code block.
+fn code_fence(trimmed: &str) -> Option<(char, usize)> {
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let run = trimmed.chars().take_while(|&c| c == fence_char).count();
+    if run < 3 {
+        return None;
+    }
+    // Backtick fences cannot have backticks in their info string.
+    if fence_char == '`' && trimmed[run..].contains('`') {
+        return None;
+    }
+    Some((fence_char, run))
+}
+
+/// Characters of `line` with their byte offsets, skipping inline code spans.
+pub fn outside_code_spans(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut in_code: Option<usize> = None;
+    let mut chars = line.char_indices().peekable();
+    std::iter::from_fn(move || loop {
+        let (index, ch) = chars.next()?;
+        if ch == '`' {
+            let mut run = 1;
+            while chars.next_if(|&(_, c)| c == '`').is_some() {
+                run += 1;
+            }
+            in_code = match in_code {
+                Some(open) if open == run => None,
+                None => Some(run),
+                other => other,
+            };
+            continue;
+        }
+        if in_code.is_none() {
+            return Some((index, ch));
+        }
+    })
+}
+
+/// Offset of the `closer` that balances the `opener` at `open`, e.g. the `]`
+/// closing a `[`. Delimiters escaped with a backslash are not counted.
+pub fn closing_delimiter(line: &str, open: usize, opener: char, closer: char) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, c) in line[open..].char_indices() {
+        if line[..open + offset].ends_with('\\') {
+            continue;
+        }
+        if c == opener {
+            depth += 1;
+        } else if c == closer {
+            depth -= 1;
+            if depth == 0 {
+                return Some(open + offset);
+            }
+        }
+    }
+    None
+}
+
+/// A backtick fence longer than any run of backticks inside `content`.
+pub fn fence_for(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// `text` with `%XX` escapes decoded, as in URIs and link targets. `None` when
+/// the decoded bytes are not UTF-8; a `%` without two hex digits is kept.
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Applies `edits` to `text`. Edits must not overlap.
+pub fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let offset = |position: Position| {
+        let Some(&start) = line_starts.get(position.line as usize) else {
+            return text.len();
+        };
+        let end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+        start + byte_offset(&text[start..end], position.character)
+    };
+
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+
+    let mut result = text.to_string();
+    for edit in edits {
+        let start = offset(edit.range.start);
+        let end = offset(edit.range.end);
+        result.replace_range(start..end, &edit.new_text);
+    }
+    result
+}
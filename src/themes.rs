@@ -0,0 +1,154 @@
+//! How Zed themes style the captures in our highlight queries.
+//!
+//! Zed maps a capture to the theme's `syntax` key whose dot-separated parts
+//! all occur in the capture name, preferring the key with the most parts: with
+//! no `text.title` key, `@text.title` takes the style of `title`. A capture
+//! that matches no key, or only keys without a style, renders as plain text.
+
+use serde_json::Value;
+
+/// One theme from a Zed theme family file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    /// `dark` or `light`.
+    pub appearance: String,
+    /// Syntax keys, with whether each sets a color, background, font style or
+    /// weight.
+    pub syntax: Vec<(String, bool)>,
+}
+
+/// What a capture looks like in a theme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// The theme styles the capture under its own name.
+    Styled,
+    /// The capture takes the style of a shorter key.
+    Fallback(String),
+    /// The capture renders as plain text; the matching key, if any, sets no style.
+    Unstyled(Option<String>),
+}
+
+impl Resolution {
+    pub fn is_styled(&self) -> bool {
+        matches!(self, Resolution::Styled | Resolution::Fallback(_))
+    }
+}
+
+/// Parses a theme family file (`{"name": ..., "themes": [...]}`).
+pub fn parse_family(json: &str) -> Result<Vec<Theme>, String> {
+    let family: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let themes = family
+        .get("themes")
+        .and_then(Value::as_array)
+        .ok_or("no \"themes\" array")?;
+
+    themes
+        .iter()
+        .map(|theme| {
+            let name = theme
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or("theme without a name")?;
+            let syntax = theme
+                .pointer("/style/syntax")
+                .and_then(Value::as_object)
+                .map(|syntax| {
+                    syntax
+                        .iter()
+                        .map(|(key, style)| (key.clone(), has_style(style)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Ok(Theme {
+                name: name.to_string(),
+                appearance: theme
+                    .get("appearance")
+                    .and_then(Value::as_str)
+                    .unwrap_or("dark")
+                    .to_string(),
+                syntax,
+            })
+        })
+        .collect()
+}
+
+fn has_style(style: &Value) -> bool {
+    ["color", "background_color", "font_style", "font_weight"]
+        .iter()
+        .any(|field| style.get(field).is_some_and(|value| !value.is_null()))
+}
+
+impl Theme {
+    /// The syntax key Zed picks for `capture`, the way Zed's highlight map
+    /// does: every part of the key must be a part of the capture, and the
+    /// key with the most parts wins, the later key on a tie.
+    pub fn key_for(&self, capture: &str) -> Option<&str> {
+        let capture_parts: Vec<&str> = capture.split('.').collect();
+        let mut best: Option<(&str, usize)> = None;
+        for (key, _) in &self.syntax {
+            let parts = key.split('.').count();
+            if key.split('.').all(|part| capture_parts.contains(&part))
+                && best.is_none_or(|(_, best_parts)| parts >= best_parts)
+            {
+                best = Some((key, parts));
+            }
+        }
+        best.map(|(key, _)| key)
+    }
+
+    pub fn resolve(&self, capture: &str) -> Resolution {
+        let Some(key) = self.key_for(capture) else {
+            return Resolution::Unstyled(None);
+        };
+        let styled = self
+            .syntax
+            .iter()
+            .any(|(name, styled)| name == key && *styled);
+        match (styled, key == capture) {
+            (false, _) => Resolution::Unstyled(Some(key.to_string())),
+            (true, true) => Resolution::Styled,
+            (true, false) => Resolution::Fallback(key.to_string()),
+        }
+    }
+}
+
+/// Highlight captures used by a query, in order of first use. Comments,
+/// strings and `@_private` captures (only used in predicates) are skipped.
+pub fn captures(query: &str) -> Vec<String> {
+    let mut captures: Vec<String> = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            ';' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '@' => {
+                let mut end = start + 1;
+                while let Some((index, c)) =
+                    chars.next_if(|&(_, c)| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+                {
+                    end = index + c.len_utf8();
+                }
+                let name = &query[start + 1..end];
+                if !name.is_empty()
+                    && !name.starts_with('_')
+                    && !captures.iter().any(|capture| capture == name)
+                {
+                    captures.push(name.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    captures
+}
@@ -0,0 +1,308 @@
+//! Cross-reference labels (`@fig-plot`, `@sec-intro`, …) for `/quarto-xrefs`.
+//!
+//! Definitions come from the outline (section ids, cell labels, figure and table
+//! floats) plus equations, listings and theorem-like divs. References are
+//! `@label` citations whose prefix is a Quarto cross-reference type.
+
+use std::collections::BTreeMap;
+
+use crate::attributes::Attributes;
+use crate::callouts;
+use crate::outline::{self, EntryKind};
+use crate::text::{outside_code_spans, verbatim_lines};
+
+/// Label prefixes Quarto resolves as cross-references.
+pub const XREF_PREFIXES: [&str; 15] = [
+    "fig", "tbl", "sec", "eq", "lst", "thm", "lem", "cor", "prp", "cnj", "def", "exm", "exr",
+    "sol", "rem",
+];
+
+/// A line in one of the scanned documents.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub path: String,
+    /// Zero-based.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub label: String,
+    pub location: Location,
+    pub caption: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub label: String,
+    pub location: Location,
+}
+
+/// A defined label and every place that references it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XrefEntry {
+    pub definition: Definition,
+    pub references: Vec<Location>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XrefTable {
+    pub entries: Vec<XrefEntry>,
+    /// References to labels that are not defined in any scanned document.
+    pub dangling: Vec<Reference>,
+    /// Labels defined more than once, with every definition after the first.
+    pub duplicates: Vec<Definition>,
+}
+
+impl XrefTable {
+    pub fn unused(&self) -> impl Iterator<Item = &XrefEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.references.is_empty())
+    }
+}
+
+/// Whether `label` starts with a cross-reference prefix such as `fig-`.
+pub fn is_xref_label(label: &str) -> bool {
+    label
+        .split_once('-')
+        .is_some_and(|(prefix, rest)| XREF_PREFIXES.contains(&prefix) && !rest.is_empty())
+}
+
+/// Labels defined in `text` as `(label, zero-based line, caption)`.
+pub fn definitions(text: &str) -> Vec<(String, usize, Option<String>)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut found = Vec::new();
+
+    for entry in outline::outline(text) {
+        let label = match entry.kind {
+            EntryKind::Heading { id, .. } => id,
+            EntryKind::Cell { label, .. } => label,
+            EntryKind::Float { label } => Some(label),
+            EntryKind::Callout { .. } => None,
+        };
+        if let Some(label) = label.filter(|label| is_xref_label(label)) {
+            found.push((label, entry.start_line, entry.title));
+        }
+    }
+
+    // Theorem-like divs: `::: {#thm-main name="Main result"}`.
+    for div in callouts::fenced_divs(text).divs {
+        let Some(label) = div.attributes.id.as_deref().filter(|id| is_xref_label(id)) else {
+            continue;
+        };
+        if found
+            .iter()
+            .any(|(existing, line, _)| existing == label && *line == div.open_line)
+        {
+            continue;
+        }
+        let caption = div.attributes.get("name").map(str::to_string).or_else(|| {
+            lines
+                .get(div.open_line + 1)
+                .and_then(|line| line.trim_start().strip_prefix('#'))
+                .map(|heading| heading.trim_start_matches('#').trim().to_string())
+        });
+        found.push((label.to_string(), div.open_line, caption));
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        // Listings: ```{#lst-load .python lst-cap="Load the data"}
+        if verbatim[index] && trimmed.starts_with("```") {
+            let info = trimmed.trim_start_matches('`');
+            if info.starts_with("{#") {
+                let attributes = Attributes::parse(info);
+                if let Some(label) = attributes.id.clone().filter(|id| is_xref_label(id)) {
+                    let caption = attributes.get("lst-cap").map(str::to_string);
+                    found.push((label, index, caption));
+                }
+            }
+            continue;
+        }
+        // Equations: $$ ... $$ {#eq-energy}
+        if !verbatim[index] && trimmed.ends_with('}') {
+            if let Some(start) = trimmed.rfind("$$") {
+                let attributes = trimmed[start + 2..].trim();
+                if attributes.starts_with("{#") {
+                    if let Some(label) = Attributes::parse(attributes).id {
+                        if label.starts_with("eq-") {
+                            found.push((label, index, None));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    found.sort_by_key(|(_, line, _)| *line);
+    found
+}
+
+/// Cross-references in `text` as `(label, zero-based line)`.
+///
+/// Matches `@fig-x`, `[@fig-x]`, `[-@fig-x]` and the capitalized `@Fig-x`,
+/// skipping code, e-mail addresses and labels without a cross-reference prefix.
+pub fn references(text: &str) -> Vec<(String, usize)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let verbatim = verbatim_lines(&lines);
+    let mut found = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if verbatim[index] {
+            continue;
+        }
+        for (start, _) in outside_code_spans(line).filter(|&(_, c)| c == '@') {
+            let preceded_by_word = line[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if preceded_by_word {
+                continue;
+            }
+            let rest = &line[start + 1..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+                .unwrap_or(rest.len());
+            // Trailing punctuation ends the sentence, not the label.
+            let label = rest[..end].trim_end_matches(['.', ':']);
+            let label = lowercase_prefix(label);
+            if is_xref_label(&label) {
+                found.push((label, index));
+            }
+        }
+    }
+    found
+}
+
+/// Builds the table for `documents`, given as `(path, text)` pairs.
+pub fn table(documents: &[(String, String)]) -> XrefTable {
+    let mut by_label: BTreeMap<String, XrefEntry> = BTreeMap::new();
+    let mut order = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for (path, text) in documents {
+        for (label, line, caption) in definitions(text) {
+            let definition = Definition {
+                label: label.clone(),
+                location: Location {
+                    path: path.clone(),
+                    line,
+                },
+                caption,
+            };
+            if by_label.contains_key(&label) {
+                duplicates.push(definition);
+                continue;
+            }
+            order.push(label.clone());
+            by_label.insert(
+                label,
+                XrefEntry {
+                    definition,
+                    references: Vec::new(),
+                },
+            );
+        }
+    }
+
+    let mut dangling = Vec::new();
+    for (path, text) in documents {
+        for (label, line) in references(text) {
+            let location = Location {
+                path: path.clone(),
+                line,
+            };
+            match by_label.get_mut(&label) {
+                Some(entry) => entry.references.push(location),
+                None => dangling.push(Reference { label, location }),
+            }
+        }
+    }
+
+    XrefTable {
+        entries: order
+            .into_iter()
+            .filter_map(|label| by_label.remove(&label))
+            .collect(),
+        dangling,
+        duplicates,
+    }
+}
+
+/// Markdown report: one table row per label, then the problems.
+pub fn render(table: &XrefTable, multiple_files: bool) -> String {
+    let location = |location: &Location| {
+        if multiple_files {
+            format!("{}:{}", location.path, location.line + 1)
+        } else {
+            (location.line + 1).to_string()
+        }
+    };
+
+    let mut text = String::from("| Label | Defined | Caption | Refs | Referenced from |\n");
+    text.push_str("|-------|---------|---------|------|-----------------|\n");
+    for entry in &table.entries {
+        let definition = &entry.definition;
+        let referenced_from = entry
+            .references
+            .iter()
+            .map(location)
+            .collect::<Vec<_>>()
+            .join(", ");
+        text.push_str(&format!(
+            "| @{} | {} | {} | {} | {} |\n",
+            definition.label,
+            location(&definition.location),
+            definition
+                .caption
+                .as_deref()
+                .unwrap_or_default()
+                .replace('|', "\\|"),
+            entry.references.len(),
+            referenced_from
+        ));
+    }
+
+    let unused: Vec<&XrefEntry> = table.unused().collect();
+    if !unused.is_empty() {
+        text.push_str("\nUnused labels:\n");
+        for entry in unused {
+            text.push_str(&format!(
+                "- @{} (defined at {})\n",
+                entry.definition.label,
+                location(&entry.definition.location)
+            ));
+        }
+    }
+    if !table.dangling.is_empty() {
+        text.push_str("\nDangling references (no definition found):\n");
+        for reference in &table.dangling {
+            text.push_str(&format!(
+                "- @{} at {}\n",
+                reference.label,
+                location(&reference.location)
+            ));
+        }
+    }
+    if !table.duplicates.is_empty() {
+        text.push_str("\nDuplicate definitions:\n");
+        for definition in &table.duplicates {
+            text.push_str(&format!(
+                "- @{} again at {}\n",
+                definition.label,
+                location(&definition.location)
+            ));
+        }
+    }
+    text
+}
+
+/// `Fig-plot` refers to `fig-plot`; Quarto capitalizes the rendered prefix.
+fn lowercase_prefix(label: &str) -> String {
+    match label.split_once('-') {
+        Some((prefix, rest)) => format!("{}-{}", prefix.to_lowercase(), rest),
+        None => label.to_string(),
+    }
+}
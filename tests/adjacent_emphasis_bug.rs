@@ -1,18 +1,17 @@
+mod support;
+
+use support::language;
+
 /// Test to diagnose the adjacent emphasis parsing issue
 ///
 /// Issue: The pattern *italic***bold***italic* breaks syntax highlighting
 /// in Zed after this line. This test checks if it's a grammar parsing error.
-use tree_sitter::{Language, Parser};
-
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use tree_sitter::Parser;
 
 #[test]
 #[ignore] // This test documents a grammar bug (https://github.com/ck37/tree-sitter-quarto/issues/8)
 fn test_adjacent_emphasis_parsing() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
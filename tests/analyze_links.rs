@@ -1,17 +1,16 @@
+mod support;
+
+use support::language;
+
 /// Analyze link highlighting to understand why links aren't working
-use tree_sitter::{Language, Parser};
+use tree_sitter::Parser;
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
 #[test]
 fn analyze_link_structure_and_captures() {
     let source = "[link text](https://example.com)\n";
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
 
     // First, see the AST structure
     let mut parser = Parser::new();
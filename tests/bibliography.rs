@@ -0,0 +1,148 @@
+use std::fs;
+
+use quarto_zed::bibliography::{self, Entry};
+
+const BIBTEX: &str = include_str!("fixtures/bibliography/references.bib");
+const CSL_JSON: &str = include_str!("fixtures/bibliography/references.json");
+
+fn entries() -> Vec<Entry> {
+    let mut entries = bibliography::parse_bibtex(BIBTEX);
+    entries.extend(bibliography::parse_csl_json(CSL_JSON).unwrap());
+    entries
+}
+
+fn keys(matches: &[&Entry]) -> Vec<String> {
+    matches.iter().map(|entry| entry.key.clone()).collect()
+}
+
+#[test]
+fn bibtex_entries_are_parsed() {
+    let entries = bibliography::parse_bibtex(BIBTEX);
+    assert_eq!(
+        entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>(),
+        ["breiman2001random", "hastie2009elements", "chen2016xgboost"],
+        "@string and @comment are not references"
+    );
+
+    let hastie = &entries[1];
+    assert_eq!(hastie.authors, ["Hastie", "Tibshirani", "Friedman"]);
+    assert_eq!(
+        hastie.title.as_deref(),
+        Some("The Elements of Statistical Learning: Data Mining, Inference, and Prediction"),
+        "grouping braces are stripped"
+    );
+    assert_eq!(hastie.year.as_deref(), Some("2009"));
+    assert_eq!(hastie.container.as_deref(), Some("Springer"));
+
+    let chen = &entries[2];
+    assert_eq!(chen.authors, ["Chen", "Guestrin"], "First Last names");
+    assert_eq!(chen.year.as_deref(), Some("2016"), "year from date");
+    assert_eq!(
+        chen.container.as_deref(),
+        Some("Proceedings of the 22nd ACM SIGKDD Conference (KDD & Data Mining)")
+    );
+    assert_eq!(entries[0].year.as_deref(), Some("2001"), "bare numbers");
+}
+
+#[test]
+fn csl_json_entries_are_parsed() {
+    let entries = bibliography::parse_csl_json(CSL_JSON).unwrap();
+    assert_eq!(entries[0].key, "tibshirani1996regression");
+    assert_eq!(entries[0].authors, ["Tibshirani"]);
+    assert_eq!(entries[0].year.as_deref(), Some("1996"));
+    assert_eq!(entries[1].authors, ["R Core Team"], "literal names");
+    assert_eq!(entries[1].year.as_deref(), Some("2023"), "raw dates");
+    assert_eq!(
+        entries[1].container.as_deref(),
+        Some("R Foundation for Statistical Computing")
+    );
+
+    assert!(bibliography::parse_csl_json("{\"not\": \"a list\"}").is_err());
+}
+
+#[test]
+fn search_matches_author_title_year_and_key() {
+    let entries = entries();
+    assert_eq!(
+        keys(&bibliography::search(&entries, "breiman")),
+        ["breiman2001random"]
+    );
+    assert_eq!(
+        keys(&bibliography::search(&entries, "tibshirani")),
+        ["tibshirani1996regression", "hastie2009elements"],
+        "the key match ranks first"
+    );
+    assert_eq!(
+        keys(&bibliography::search(&entries, "lasso 1996")),
+        ["tibshirani1996regression"]
+    );
+    assert_eq!(
+        keys(&bibliography::search(&entries, "Boosting")),
+        ["chen2016xgboost"]
+    );
+    assert_eq!(
+        keys(&bibliography::search(&entries, "hst09")),
+        ["hastie2009elements"],
+        "subsequences of the key match"
+    );
+    assert!(bibliography::search(&entries, "breiman 2009").is_empty());
+    assert_eq!(
+        bibliography::search(&entries, "").len(),
+        entries.len(),
+        "an empty query lists everything"
+    );
+}
+
+#[test]
+fn citations_and_references_are_formatted() {
+    let entries = entries();
+    let hastie = &entries[1];
+    assert_eq!(hastie.citation(), "[@hastie2009elements]");
+    assert_eq!(hastie.short_authors(), "Hastie et al. (2009)");
+    assert_eq!(
+        entries[2].reference(),
+        "Chen & Guestrin (2016). XGBoost: A Scalable Tree Boosting System. \
+         *Proceedings of the 22nd ACM SIGKDD Conference (KDD & Data Mining)*."
+    );
+}
+
+#[test]
+fn project_entries_use_configured_files_or_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("refs")).unwrap();
+    fs::write(root.join("refs/main.bib"), BIBTEX).unwrap();
+    fs::write(root.join("references.json"), CSL_JSON).unwrap();
+    fs::write(root.join("package.json"), "{\"name\": \"site\"}").unwrap();
+    fs::write(root.join("points.json"), "[{\"id\": 1, \"x\": 2}]").unwrap();
+    fs::create_dir_all(root.join("node_modules/citation-js")).unwrap();
+    fs::write(root.join("node_modules/citation-js/test.bib"), BIBTEX).unwrap();
+
+    let scanned = bibliography::project_entries(root).unwrap();
+    assert_eq!(
+        scanned.len(),
+        5,
+        "unrelated JSON files and node_modules are ignored"
+    );
+
+    fs::create_dir(root.join("posts")).unwrap();
+    fs::write(
+        root.join("posts/intro.qmd"),
+        "---\r\ntitle: Intro\r\nbibliography: ../references.json\r\n---\r\n\nText.\n",
+    )
+    .unwrap();
+    let from_front_matter = bibliography::project_entries(root).unwrap();
+    assert_eq!(
+        from_front_matter.len(),
+        2,
+        "front matter is read before scanning, relative to the document"
+    );
+
+    fs::write(root.join("_quarto.yml"), "bibliography: refs/main.bib\n").unwrap();
+    let configured = bibliography::project_entries(root).unwrap();
+    assert_eq!(configured.len(), 3, "only the configured file is read");
+
+    fs::write(root.join("_quarto.yml"), "bibliography: missing.bib\n").unwrap();
+    let error = bibliography::project_entries(root).unwrap_err();
+    assert!(error.contains("missing.bib"), "{error}");
+}
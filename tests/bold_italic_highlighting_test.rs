@@ -1,17 +1,16 @@
+mod support;
+
+use support::language;
+
 /// Test that bold and italic text highlighting actually works
 ///
 /// This test validates that our highlights.scm queries successfully capture
 /// bold and italic content with the tree-sitter-quarto grammar.
-use tree_sitter::{Language, Parser, Query, QueryCursor, StreamingIteratorMut};
-
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIteratorMut};
 
 #[test]
 fn bold_text_is_highlighted() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
@@ -60,7 +59,7 @@ fn bold_text_is_highlighted() {
 
 #[test]
 fn italic_text_is_highlighted() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
@@ -109,7 +108,7 @@ fn italic_text_is_highlighted() {
 
 #[test]
 fn heading_with_bold_and_italic() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
@@ -152,7 +151,7 @@ fn heading_with_bold_and_italic() {
 
 #[test]
 fn link_text_is_highlighted() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
@@ -191,7 +190,7 @@ fn link_text_is_highlighted() {
 #[test]
 fn diagnostic_print_all_captures() {
     // This test prints all captures for debugging
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
 
@@ -0,0 +1,309 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quarto_zed::book::{self, ItemKind};
+use quarto_zed::lsp::{path_to_uri, Server};
+use quarto_zed::project::Project;
+use serde_json::json;
+
+fn book_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/projects/book")
+}
+
+fn book_contents(profile: Option<&str>) -> book::Contents {
+    let project = Project::load(&book_root(), profile).unwrap();
+    book::contents(&project, book::read_from(&project.root)).unwrap()
+}
+
+#[test]
+fn chapters_are_numbered_in_reading_order() {
+    let contents = book_contents(None);
+    let headings: Vec<(ItemKind, String)> = contents
+        .items
+        .iter()
+        .map(|item| (item.kind, item.heading()))
+        .collect();
+    assert_eq!(
+        headings,
+        [
+            (ItemKind::Chapter, "Preface".to_string()),
+            (ItemKind::Part, "Data".to_string()),
+            (ItemKind::Chapter, "1 Collection".to_string()),
+            (ItemKind::Chapter, "2 Cleaning".to_string()),
+            (ItemKind::Part, "Modelling".to_string()),
+            (ItemKind::Chapter, "3 Regression".to_string()),
+            (ItemKind::Appendix, "A Glossary".to_string()),
+        ],
+        "unnumbered preface, chapters counted across parts, lettered appendices"
+    );
+    assert_eq!(contents.title.as_deref(), Some("Fuel Economy in Practice"));
+    assert_eq!(
+        contents.items[4].path.as_deref(),
+        Some("chapters/modelling-part.qmd"),
+        "a part page takes its title from its heading"
+    );
+}
+
+#[test]
+fn sections_are_numbered_within_chapters() {
+    let contents = book_contents(None);
+    let numbers = |index: usize| -> Vec<(Option<String>, String)> {
+        contents.items[index]
+            .sections
+            .iter()
+            .map(|section| (section.number.clone(), section.title.clone()))
+            .collect()
+    };
+    assert_eq!(
+        numbers(3),
+        [
+            (None, "Missing values".to_string()),
+            (Some("2.1".to_string()), "Outliers".to_string()),
+        ],
+        "unnumbered sections do not advance the counter"
+    );
+    assert_eq!(
+        numbers(5),
+        [
+            (Some("3.1".to_string()), "Linear models".to_string()),
+            (Some("3.1.1".to_string()), "Diagnostics".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn numbering_follows_project_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(
+        root.join("_quarto.yml"),
+        "project:\n  type: book\nnumber-depth: 2\nbook:\n  chapters:\n    - intro.qmd\n  appendices:\n    - a.qmd\n    - b.qmd\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("intro.qmd"),
+        "# Intro\n\n## Scope\n\n### Detail\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("a.qmd"),
+        "---\r\ntitle: \"Data sources\"\r\n---\r\n\r\n## Surveys\r\n",
+    )
+    .unwrap();
+    fs::write(root.join("b.qmd"), "# Code {.unnumbered}\n").unwrap();
+
+    let project = Project::load(root, None).unwrap();
+    let contents = book::contents(&project, book::read_from(root)).unwrap();
+    let intro = &contents.items[0];
+    assert_eq!(intro.heading(), "1 Intro");
+    assert_eq!(intro.sections[0].number.as_deref(), Some("1.1"));
+    assert_eq!(intro.sections[1].number, None, "deeper than number-depth");
+    assert_eq!(
+        contents.items[1].heading(),
+        "A Data sources",
+        "front matter title, with CRLF line endings"
+    );
+    assert_eq!(contents.items[1].sections[0].number.as_deref(), Some("A.1"));
+    assert_eq!(contents.items[2].heading(), "Code");
+
+    fs::write(
+        root.join("_quarto.yml"),
+        "project:\n  type: book\nnumber-sections: false\nbook:\n  chapters:\n    - intro.qmd\n",
+    )
+    .unwrap();
+    let project = Project::load(root, None).unwrap();
+    let contents = book::contents(&project, book::read_from(root)).unwrap();
+    assert_eq!(contents.items[0].heading(), "Intro");
+    assert_eq!(contents.items[0].sections[0].number, None);
+}
+
+#[test]
+fn missing_and_unlisted_files_are_reported() {
+    let contents = book_contents(None);
+    assert_eq!(
+        contents.unlisted,
+        ["chapters/scratch.qmd"],
+        "output and underscore directories are not chapters"
+    );
+    assert_eq!(contents.missing().count(), 0);
+
+    let dev = book_contents(Some("dev"));
+    let missing: Vec<_> = dev.missing().map(|item| item.path.clone()).collect();
+    assert_eq!(missing, [Some("chapters/drafts.qmd".to_string())]);
+
+    let config = fs::read_to_string(book_root().join("_quarto-dev.yml")).unwrap();
+    let diagnostics = book::config_diagnostics(&dev, &config);
+    assert_eq!(diagnostics[0].code, "missing-chapter");
+    assert_eq!(
+        diagnostics[0].range.start.line as usize,
+        config
+            .lines()
+            .position(|line| line.contains("chapters/drafts.qmd"))
+            .unwrap()
+    );
+    assert_eq!(diagnostics[1].code, "unlisted-chapter");
+    assert!(diagnostics[1].message.contains("chapters/scratch.qmd"));
+}
+
+fn chapter(path: &str, missing: bool) -> book::Item {
+    book::Item {
+        kind: ItemKind::Chapter,
+        path: Some(path.to_string()),
+        title: path.to_string(),
+        line: 0,
+        number: None,
+        sections: Vec::new(),
+        missing,
+        in_part: false,
+    }
+}
+
+#[test]
+fn config_diagnostics_point_at_exact_entries() {
+    let config = "\
+book:
+  # chapters/intro.qmd.bak is an old copy
+  chapters:
+    - appendix-intro.qmd
+    - file: \"chapters/intro.qmd\"  # the introduction
+    - chapters/methods.qmd
+    - chapters/results.qmd
+    - notes.qmd
+";
+    let contents = book::Contents {
+        title: None,
+        items: vec![
+            chapter("appendix-intro.qmd", false),
+            chapter("chapters/intro.qmd", true),
+            chapter("chapters/methods.qmd", false),
+            chapter("chapters/results.qmd", false),
+            chapter("notes.qmd", false),
+        ],
+        unlisted: vec!["chapters/model.qmd".to_string(), "summary.qmd".to_string()],
+    };
+    let diagnostics = book::config_diagnostics(&contents, config);
+    let lines: Vec<&str> = config.lines().collect();
+    let located = |index: usize| {
+        let range = diagnostics[index].range;
+        let line = lines[range.start.line as usize];
+        (
+            range.start.line,
+            &line[range.start.character as usize..range.end.character as usize],
+        )
+    };
+
+    assert_eq!(diagnostics[0].code, "missing-chapter");
+    assert_eq!(located(0), (4, "chapters/intro.qmd"));
+    // Unlisted files sit next to the entry they would follow in their directory
+    assert_eq!(located(1), (5, "chapters/methods.qmd"));
+    assert!(diagnostics[1]
+        .message
+        .contains("next to chapters/methods.qmd"));
+    assert_eq!(located(2), (7, "notes.qmd"));
+}
+
+#[test]
+fn rendered_contents_list_problems() {
+    let (text, sections) = book::render(&book_contents(Some("dev")));
+    assert!(
+        text.starts_with("Fuel Economy in Practice\n\nPreface (index.qmd)\nPart: Data\n  1 Collection (chapters/collection.qmd)\n    1.1 Sources (line 3)\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("Appendices\n  A Glossary (chapters/glossary.qmd)\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("drafts (chapters/drafts.qmd, missing)"),
+        "{text}"
+    );
+    assert!(
+        text.contains("\nMissing chapters (listed but not found):\n- chapters/drafts.qmd\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains(
+            "\nNot in the book (not listed in chapters or appendices):\n- chapters/scratch.qmd\n"
+        ),
+        "{text}"
+    );
+    assert_eq!(sections[2].1, "1 Collection");
+    assert!(text[sections[2].0.clone()].contains("1.2 Sampling"));
+}
+
+fn open(server: &mut Server, path: &Path) -> Vec<serde_json::Value> {
+    let text = fs::read_to_string(path).unwrap();
+    server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": path_to_uri(path), "languageId": "quarto", "version": 1, "text": text }
+        }
+    }))
+}
+
+#[test]
+fn language_server_lists_the_book_as_workspace_symbols() {
+    let root = book_root();
+    let mut server = Server::new();
+    let replies = open(&mut server, &root.join("chapters/scratch.qmd"));
+
+    assert_eq!(
+        replies[0]["params"]["diagnostics"][0]["code"],
+        "not-in-book"
+    );
+    assert_eq!(
+        replies[1]["params"]["uri"],
+        path_to_uri(&root.join("_quarto.yml"))
+    );
+    assert_eq!(
+        replies[1]["params"]["diagnostics"][0]["code"],
+        "unlisted-chapter"
+    );
+
+    let mut symbols = |query: &str| {
+        let reply = server.handle(&json!({
+            "jsonrpc": "2.0", "id": 1, "method": "workspace/symbol", "params": { "query": query }
+        }));
+        reply[0]["result"].as_array().unwrap().clone()
+    };
+    let all = symbols("");
+    let names: Vec<&str> = all.iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        [
+            "Preface",
+            "Data",
+            "1 Collection",
+            "1.1 Sources",
+            "1.2 Sampling",
+            "2 Cleaning",
+            "Missing values",
+            "2.1 Outliers",
+            "Modelling",
+            "3 Regression",
+            "3.1 Linear models",
+            "3.1.1 Diagnostics",
+            "A Glossary",
+        ]
+    );
+    assert_eq!(
+        all[1]["location"]["uri"],
+        path_to_uri(&root.join("chapters/collection.qmd"))
+    );
+    assert_eq!(all[2]["containerName"], "Data");
+    assert_eq!(all[7]["containerName"], "2 Cleaning");
+    assert_eq!(all[7]["location"]["range"]["start"]["line"], 4);
+
+    let filtered = symbols("lnmod");
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0]["name"], "3.1 Linear models");
+}
+
+#[test]
+fn uris_round_trip_through_paths() {
+    let path = Path::new("/home/me/My Book/ch#1.qmd");
+    let uri = path_to_uri(path);
+    assert_eq!(uri, "file:///home/me/My%20Book/ch%231.qmd");
+    assert_eq!(quarto_zed::lsp::uri_to_path(&uri).as_deref(), Some(path));
+}
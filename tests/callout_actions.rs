@@ -0,0 +1,246 @@
+use quarto_zed::callouts::{self, CALLOUT_TYPES};
+use quarto_zed::lsp::Server;
+use quarto_zed::text::{Position, Range, TextEdit};
+use serde_json::json;
+
+/// Applies non-overlapping edits to `text`, last edit first.
+fn apply(text: &str, edits: &[TextEdit]) -> String {
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+
+    for edit in edits {
+        let start = edit.range.start;
+        let end = edit.range.end;
+        let head = lines[start.line as usize][..start.character as usize].to_string();
+        let tail = lines[end.line as usize][end.character as usize..].to_string();
+        let replacement = format!("{head}{}{tail}", edit.new_text);
+        lines.splice(
+            start.line as usize..=end.line as usize,
+            replacement.split('\n').map(str::to_string),
+        );
+    }
+    lines.join("\n")
+}
+
+fn cursor(line: u32) -> Range {
+    Range::new(Position::new(line, 0), Position::new(line, 0))
+}
+
+fn action<'a>(
+    actions: &'a [quarto_zed::text::CodeAction],
+    title: &str,
+) -> &'a quarto_zed::text::CodeAction {
+    actions
+        .iter()
+        .find(|action| action.title == title)
+        .unwrap_or_else(|| panic!("missing action {title:?}"))
+}
+
+#[test]
+fn callouts_are_matched_with_their_closing_fence() {
+    let source = r#"::: {.callout-note}
+Outer note.
+
+::: {.callout-tip collapse="true"}
+Nested tip.
+:::
+:::
+
+```markdown
+::: {.callout-warning}
+```
+"#;
+
+    let callouts = callouts::callouts(source);
+    assert_eq!(callouts.len(), 2, "fences inside code blocks are ignored");
+    assert_eq!(callouts[0].kind, "note");
+    assert_eq!(callouts[0].div.close_line, Some(6));
+    assert_eq!(callouts[1].kind, "tip");
+    assert_eq!(callouts[1].div.close_line, Some(5));
+    assert!(callouts[1].is_collapsed());
+}
+
+#[test]
+fn all_callout_types_parse_without_diagnostics() {
+    for callout_type in CALLOUT_TYPES {
+        let source = format!("::: {{.callout-{callout_type}}}\nBody.\n:::\n");
+        assert!(
+            callouts::diagnostics(&source).is_empty(),
+            "callout-{callout_type} should be accepted"
+        );
+    }
+}
+
+#[test]
+fn unknown_callout_type_is_flagged() {
+    let source = "::: {.callout-danger}\nBody.\n:::\n";
+    let diagnostics = callouts::diagnostics(source);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "unknown-callout");
+    assert_eq!(diagnostics[0].range.start.line, 0);
+    assert!(diagnostics[0].message.contains("callout-danger"));
+}
+
+#[test]
+fn unbalanced_fences_are_flagged() {
+    let unclosed = "::: {.callout-note}\nNever closed.\n";
+    let diagnostics = callouts::diagnostics(unclosed);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "unclosed-fence");
+
+    let stray = "Text.\n:::\n";
+    let diagnostics = callouts::diagnostics(stray);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, "unmatched-fence");
+    assert_eq!(diagnostics[0].range.start.line, 1);
+}
+
+#[test]
+fn wrap_selection_in_callout() {
+    let source = "Intro.\n\nFirst line.\nSecond line.\n\nOutro.\n";
+    let selection = Range::new(Position::new(2, 0), Position::new(4, 0));
+
+    let actions = callouts::code_actions(source, selection);
+    for callout_type in CALLOUT_TYPES {
+        action(&actions, &format!("Wrap in callout-{callout_type}"));
+    }
+
+    let wrapped = apply(source, &action(&actions, "Wrap in callout-warning").edits);
+    assert_eq!(
+        wrapped,
+        "Intro.\n\n::: {.callout-warning}\nFirst line.\nSecond line.\n:::\n\nOutro.\n"
+    );
+    assert!(callouts::diagnostics(&wrapped).is_empty());
+}
+
+#[test]
+fn wrap_last_line_without_trailing_newline() {
+    let source = "Only line";
+    let selection = Range::new(Position::new(0, 0), Position::new(0, 9));
+
+    let actions = callouts::code_actions(source, selection);
+    let wrapped = apply(source, &action(&actions, "Wrap in callout-note").edits);
+    assert_eq!(wrapped, "::: {.callout-note}\nOnly line\n:::");
+}
+
+#[test]
+fn change_callout_type_keeps_other_attributes() {
+    let source = "::: {#cau-data .callout-note collapse=\"true\"}\nBody.\n:::\n";
+    let actions = callouts::code_actions(source, cursor(1));
+
+    assert!(
+        actions.iter().all(|a| a.title != "Change to callout-note"),
+        "current type should not be offered"
+    );
+    let changed = apply(source, &action(&actions, "Change to callout-tip").edits);
+    assert_eq!(
+        changed,
+        "::: {#cau-data .callout-tip collapse=\"true\"}\nBody.\n:::\n"
+    );
+}
+
+#[test]
+fn toggle_collapse() {
+    let source = "::: {.callout-tip}\nBody.\n:::\n";
+    let collapsed = apply(
+        source,
+        &action(
+            &callouts::code_actions(source, cursor(0)),
+            "Collapse callout by default",
+        )
+        .edits,
+    );
+    assert_eq!(
+        collapsed,
+        "::: {.callout-tip collapse=\"true\"}\nBody.\n:::\n"
+    );
+
+    let expanded = apply(
+        &collapsed,
+        &action(
+            &callouts::code_actions(&collapsed, cursor(0)),
+            "Expand callout by default",
+        )
+        .edits,
+    );
+    assert_eq!(expanded, source);
+}
+
+#[test]
+fn add_title_only_when_missing() {
+    let source = "::: {.callout-important}\nBody.\n:::\n";
+    let actions = callouts::code_actions(source, cursor(1));
+    let titled = apply(source, &action(&actions, "Add callout title").edits);
+    assert_eq!(
+        titled,
+        "::: {.callout-important}\n## Important\nBody.\n:::\n"
+    );
+
+    let actions = callouts::code_actions(&titled, cursor(1));
+    assert!(actions.iter().all(|a| a.title != "Add callout title"));
+
+    let with_attribute = "::: {.callout-note title=\"Heads up\"}\nBody.\n:::\n";
+    let actions = callouts::code_actions(with_attribute, cursor(1));
+    assert!(actions.iter().all(|a| a.title != "Add callout title"));
+}
+
+#[test]
+fn innermost_callout_is_targeted() {
+    let source = "::: {.callout-note}\n::: {.callout-tip}\nInner.\n:::\n:::\n";
+    let actions = callouts::code_actions(source, cursor(2));
+    let changed = apply(source, &action(&actions, "Change to callout-caution").edits);
+    assert_eq!(
+        changed,
+        "::: {.callout-note}\n::: {.callout-caution}\nInner.\n:::\n:::\n"
+    );
+}
+
+#[test]
+fn language_server_publishes_diagnostics_and_actions() {
+    let mut server = Server::new();
+    let uri = "file:///doc.qmd";
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {
+                "uri": uri,
+                "languageId": "quarto",
+                "version": 1,
+                "text": "::: {.callout-oops}\nBody.\n:::\n",
+            }
+        }
+    }));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(
+        replies[0]["params"]["diagnostics"][0]["code"],
+        "unknown-callout"
+    );
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": "textDocument/codeAction",
+        "params": {
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 1, "character": 0 },
+            },
+            "context": { "diagnostics": [] },
+        }
+    }));
+    let actions = replies[0]["result"].as_array().expect("action list");
+    let fix = actions
+        .iter()
+        .find(|a| a["title"] == "Change to callout-note")
+        .expect("type change offered for unknown callout");
+    assert_eq!(
+        fix["edit"]["changes"][uri][0]["newText"],
+        "::: {.callout-note}"
+    );
+}
@@ -0,0 +1,164 @@
+use quarto_zed::cells;
+use quarto_zed::knitr::{self, knitr_option_name, parse_header, quarto_option_name, yaml_value};
+use quarto_zed::text::{apply_edits, Position, Range};
+
+#[test]
+fn knitr_header_is_parsed() {
+    let header = parse_header("setup, include=FALSE, fig.width=7");
+    assert_eq!(header.label.as_deref(), Some("setup"));
+    assert_eq!(
+        header.options,
+        vec![
+            ("include".to_string(), "FALSE".to_string()),
+            ("fig.width".to_string(), "7".to_string()),
+        ]
+    );
+
+    let header = parse_header("label='plot', fig.cap=\"A, B\", out.width=c('50%', '50%')");
+    assert_eq!(header.label.as_deref(), Some("plot"));
+    assert_eq!(
+        header.options[0].1, "\"A, B\"",
+        "quoted commas do not split"
+    );
+    assert_eq!(
+        header.options[1].1, "c('50%', '50%')",
+        "commas inside calls do not split"
+    );
+}
+
+#[test]
+fn option_names_map_between_spellings() {
+    assert_eq!(quarto_option_name("fig.width"), "fig-width");
+    assert_eq!(quarto_option_name("out.height"), "out-height");
+    assert_eq!(quarto_option_name("echo"), "echo");
+    assert_eq!(knitr_option_name("fig-cap"), "fig.cap");
+    assert_eq!(
+        knitr_option_name(&quarto_option_name("fig.align")),
+        "fig.align"
+    );
+}
+
+#[test]
+fn r_values_become_yaml() {
+    assert_eq!(yaml_value("FALSE"), "false");
+    assert_eq!(yaml_value("T"), "true");
+    assert_eq!(yaml_value("7"), "7");
+    assert_eq!(yaml_value("0.5"), "0.5");
+    assert_eq!(yaml_value("-1e-3"), "-1e-3");
+    assert_eq!(yaml_value("10L"), "10");
+    assert_eq!(yaml_value("Inf"), "!expr Inf");
+    assert_eq!(yaml_value("NaN"), "!expr NaN");
+    assert_eq!(yaml_value("infinity"), "!expr infinity");
+    assert_eq!(yaml_value("."), "!expr .");
+    assert_eq!(yaml_value("'hide'"), "hide");
+    assert_eq!(yaml_value("\"A plot: left\""), "\"A plot: left\"");
+    assert_eq!(yaml_value("c(1, 2)"), "!expr c(1, 2)");
+}
+
+#[test]
+fn knitr_headers_supply_labels_and_captions() {
+    let text = "```{r revenue-plot, fig.width=8, fig.cap=\"Revenue by region\"}\nplot(x)\n```\n\n```{r, label='setup'}\n```\n\n```{r header}\n#| label: options-win\n```\n";
+    let cells = cells::cells(text);
+
+    assert_eq!(cells[0].label(), Some("revenue-plot"));
+    assert_eq!(
+        cells[0].option_or_header("fig-cap"),
+        Some("\"Revenue by region\"")
+    );
+    assert_eq!(cells[0].option_or_header("fig-width"), Some("8"));
+    assert_eq!(
+        cells[0].option("fig-cap"),
+        None,
+        "header values are not #| options"
+    );
+    assert_eq!(cells[1].label(), Some("setup"));
+    assert_eq!(cells[2].label(), Some("options-win"));
+
+    let (migrated, _) = knitr::migrate_document(text);
+    assert!(
+        migrated.contains("```{r}\n#| label: revenue-plot\n"),
+        "{}",
+        migrated
+    );
+}
+
+#[test]
+fn setup_chunk_is_migrated() {
+    let source = "```{r setup, include=FALSE, fig.width=7}\nlibrary(ggplot2)\n```\n";
+    let (migrated, count) = knitr::migrate_document(source);
+
+    assert_eq!(count, 1);
+    assert_eq!(
+        migrated,
+        "```{r}\n#| label: setup\n#| include: false\n#| fig-width: 7\nlibrary(ggplot2)\n```\n"
+    );
+
+    let cell = &cells::cells(&migrated)[0];
+    assert_eq!(cell.header, "");
+    assert_eq!(cell.label(), Some("setup"));
+    assert_eq!(cell.option("fig-width"), Some("7"));
+}
+
+#[test]
+fn existing_hash_pipe_options_take_precedence() {
+    let source = "```{r plot, echo=FALSE}\n#| echo: true\nplot(1)\n```\n";
+    let (migrated, _) = knitr::migrate_document(source);
+    assert_eq!(
+        migrated,
+        "```{r}\n#| label: plot\n#| echo: true\nplot(1)\n```\n"
+    );
+}
+
+#[test]
+fn modern_cells_are_left_alone() {
+    let source = "```{python}\n#| label: fig-plot\nprint(1)\n```\n\n```r\nx <- 1\n```\n";
+    let (migrated, count) = knitr::migrate_document(source);
+    assert_eq!(count, 0);
+    assert_eq!(migrated, source);
+}
+
+#[test]
+fn fixture_chunks_are_migrated() {
+    let source = include_str!("fixtures/r-code-examples.qmd");
+    let (migrated, count) = knitr::migrate_document(source);
+
+    assert!(count >= 2, "fixture has knitr-style headers, got {count}");
+    assert!(migrated.contains("```{r}\n#| echo: true\n#| warning: false\n"));
+    assert!(migrated.contains("```{r}\n#| label: my-analysis\n#| fig-width: 8\n#| fig-height: 6\n"));
+    assert!(
+        cells::cells(&migrated)
+            .iter()
+            .all(|cell| cell.header.is_empty()),
+        "every header should be bare after migration"
+    );
+    assert_eq!(
+        cells::cells(&migrated).len(),
+        cells::cells(source).len(),
+        "migration must not change the number of cells"
+    );
+}
+
+#[test]
+fn code_actions_offer_cell_and_document_migration() {
+    let source = "```{r a, echo=FALSE}\n1\n```\n\n```{r b}\n2\n```\n";
+    let cursor = Range::new(Position::new(1, 0), Position::new(1, 0));
+    let actions = knitr::code_actions(source, cursor);
+
+    let single = actions
+        .iter()
+        .find(|a| a.title == "Convert chunk header to #| options")
+        .expect("cell action");
+    assert_eq!(
+        apply_edits(source, &single.edits),
+        "```{r}\n#| label: a\n#| echo: false\n1\n```\n\n```{r b}\n2\n```\n"
+    );
+
+    let all = actions
+        .iter()
+        .find(|a| a.title == "Convert all 2 chunk headers to #| options")
+        .expect("document action");
+    assert_eq!(
+        apply_edits(source, &all.edits),
+        "```{r}\n#| label: a\n#| echo: false\n1\n```\n\n```{r}\n#| label: b\n2\n```\n"
+    );
+}
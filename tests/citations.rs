@@ -1,35 +1,6 @@
-use tree_sitter::{Language, Parser};
-use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
-
-fn highlight_configuration() -> HighlightConfiguration {
-    let highlight_query = include_str!("../grammars/quarto/queries/highlights.scm");
-    let injection_query = include_str!("../grammars/quarto/queries/injections.scm");
-    let locals_query = "";
-
-    let mut config = HighlightConfiguration::new(
-        language(),
-        "quarto",
-        highlight_query,
-        injection_query,
-        locals_query,
-    )
-    .expect("valid highlight configuration");
-
-    let scope_names: Vec<String> = config.names().iter().map(|s| s.to_string()).collect();
-    let scope_names_refs: Vec<&str> = scope_names.iter().map(|s| s.as_str()).collect();
-    config.configure(&scope_names_refs);
-
-    config
-}
+use support::{assert_highlights, highlight, parse};
 
 #[test]
 fn basic_citations_are_highlighted() {
@@ -38,51 +9,20 @@ fn basic_citations_are_highlighted() {
 Multiple studies [@jones2023; @brown2022] confirm this.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
-
-    eprintln!("\n=== PARSE TREE ===\n{}\n", tree.root_node().to_sexp());
-
-    let config = highlight_configuration();
-    let mut highlighter = Highlighter::new();
-    let events = highlighter
-        .highlight(&config, source.as_bytes(), None, |_| None)
-        .expect("highlighting succeeds");
-
-    let mut rendered = Vec::new();
-    for event in events {
-        match event.expect("valid event") {
-            tree_sitter_highlight::HighlightEvent::HighlightStart(s) => {
-                let scope_name = config.names()[s.0];
-                rendered.push(format!("<{}>", scope_name));
-            }
-            tree_sitter_highlight::HighlightEvent::HighlightEnd => {
-                rendered.push("</>".into());
-            }
-            tree_sitter_highlight::HighlightEvent::Source { start, end } => {
-                rendered.push(source[start..end].to_string());
-            }
-        }
-    }
+    let parsed = parse(source);
+    eprintln!("\n=== PARSE TREE ===\n{}\n", parsed.sexp());
 
-    let rendered_output = rendered.join("");
-    eprintln!("\n=== RENDERED OUTPUT ===\n{}\n", rendered_output);
+    let highlights = highlight(source);
+    eprintln!("\n=== RENDERED OUTPUT ===\n{}\n", highlights.rendered());
 
     // Check that @ symbol is highlighted
-    assert!(
-        rendered_output.contains("<punctuation.special>@</>"),
-        "@ prefix should be highlighted"
-    );
+    assert_highlights(source, [("@", "punctuation.special")]);
 
     // Check that citation keys are highlighted
     assert!(
-        rendered_output.contains("<variable.parameter>smith2024</>")
-            || rendered_output.contains("<variable.parameter>jones2023</>")
-            || rendered_output.contains("<variable.parameter>brown2022</>"),
+        ["smith2024", "jones2023", "brown2022"]
+            .iter()
+            .any(|key| highlights.has(key, "variable.parameter")),
         "Citation keys should be highlighted as variable.parameter"
     );
 
@@ -102,58 +42,33 @@ With prefix: As shown by [see @smith2024] the data suggests.
 With page: According to [@smith2024, p. 42] we can conclude.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).unwrap();
-    let tree = parser.parse(source.as_bytes(), None).unwrap();
-
-    eprintln!("\n=== PARSE TREE ===\n{}\n", tree.root_node().to_sexp());
-
-    let config = highlight_configuration();
-    let mut highlighter = Highlighter::new();
-    let events = highlighter
-        .highlight(&config, source.as_bytes(), None, |_| None)
-        .unwrap();
-
-    let mut rendered = Vec::new();
-    for event in events {
-        match event.unwrap() {
-            tree_sitter_highlight::HighlightEvent::HighlightStart(s) => {
-                let scope_name = config.names()[s.0];
-                rendered.push(format!("<{}>", scope_name));
-            }
-            tree_sitter_highlight::HighlightEvent::HighlightEnd => {
-                rendered.push("</>".into());
-            }
-            tree_sitter_highlight::HighlightEvent::Source { start, end } => {
-                rendered.push(source[start..end].to_string());
-            }
-        }
-    }
-
-    let rendered_output = rendered.join("");
-    eprintln!("\n=== RENDERED OUTPUT ===\n{}\n", rendered_output);
+    let parsed = parse(source);
+    eprintln!("\n=== PARSE TREE ===\n{}\n", parsed.sexp());
+    eprintln!(
+        "\n=== RENDERED OUTPUT ===\n{}\n",
+        highlight(source).rendered()
+    );
 
     // Verify citations are parsed
-    let tree_str = tree.root_node().to_sexp();
+    let citations = parsed.find_all("citation");
     assert!(
-        tree_str.contains("citation"),
+        !citations.is_empty(),
         "Document should contain citation nodes"
     );
 
-    // Check that all citation keys are highlighted
-    assert!(
-        rendered_output.contains("smith2024"),
-        "Citation key smith2024 should be present"
-    );
-    assert!(
-        rendered_output.contains("jones2023"),
-        "Citation key jones2023 should be present"
-    );
-    assert!(
-        rendered_output.contains("brown2022"),
-        "Citation key brown2022 should be present"
-    );
+    // Check that all citation keys are parsed
+    let cited = citations
+        .iter()
+        .map(|&citation| parsed.text(citation))
+        .collect::<Vec<_>>()
+        .join(" ");
+    for key in ["smith2024", "jones2023", "brown2022"] {
+        assert!(
+            cited.contains(key),
+            "Citation key {} should be present",
+            key
+        );
+    }
 
     println!("✓ Citation variations are properly parsed and highlighted");
 }
@@ -170,17 +85,15 @@ fn citation_keys_with_special_characters() {
     ];
 
     for source in test_cases {
-        let mut parser = Parser::new();
-        let lang = language();
-        parser.set_language(&lang).unwrap();
-        let tree = parser.parse(source.as_bytes(), None).unwrap();
-
-        let root = tree.root_node();
-        eprintln!("\nTesting {}: {}", source, root.to_sexp());
+        let parsed = parse(source);
+        eprintln!("\nTesting {}: {}", source, parsed.sexp());
 
         // Verify the tree structure contains citation node
-        let has_citation = root.to_sexp().contains("citation");
-        assert!(has_citation, "Source '{}' should parse as citation", source);
+        assert!(
+            parsed.has("citation"),
+            "Source '{}' should parse as citation",
+            source
+        );
 
         println!("✓ {} parses correctly as citation", source);
     }
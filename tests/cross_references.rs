@@ -1,35 +1,8 @@
-use tree_sitter::{Language, Parser};
-use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
-
-fn highlight_configuration() -> HighlightConfiguration {
-    let highlight_query = include_str!("../grammars/quarto/queries/highlights.scm");
-    let injection_query = include_str!("../grammars/quarto/queries/injections.scm");
-    let locals_query = "";
-
-    let mut config = HighlightConfiguration::new(
-        language(),
-        "quarto",
-        highlight_query,
-        injection_query,
-        locals_query,
-    )
-    .expect("valid highlight configuration");
-
-    let scope_names: Vec<String> = config.names().iter().map(|s| s.to_string()).collect();
-    let scope_names_refs: Vec<&str> = scope_names.iter().map(|s| s.as_str()).collect();
-    config.configure(&scope_names_refs);
-
-    config
-}
+use support::{highlight_configuration, language};
+use tree_sitter::Parser;
+use tree_sitter_highlight::Highlighter;
 
 #[test]
 fn cross_references_are_highlighted() {
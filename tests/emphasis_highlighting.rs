@@ -1,22 +1,15 @@
+mod support;
+
 use std::fs;
 use std::path::Path;
 
-use tree_sitter::{Language, Parser};
+use support::language;
+use tree_sitter::Parser;
 use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
 
-// Link to the compiled pandoc-markdown grammar
-#[link(name = "tree-sitter-pandoc-markdown", kind = "static")]
-extern "C" {
-    fn tree_sitter_pandoc_markdown() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_pandoc_markdown() }
-}
-
 fn highlight_configuration() -> HighlightConfiguration {
-    let highlight_query = include_str!("../grammars/quarto/queries/highlights.scm");
-    let injection_query = include_str!("../grammars/quarto/queries/injections.scm");
+    let highlight_query = support::HIGHLIGHTS;
+    let injection_query = support::INJECTIONS;
     let locals_query = "";
 
     let mut config = HighlightConfiguration::new(
@@ -26,7 +19,7 @@ fn highlight_configuration() -> HighlightConfiguration {
         injection_query,
         locals_query,
     )
-    .expect("valid highlight configuration with pandoc-markdown grammar");
+    .expect("valid highlight configuration");
 
     config.configure(&[
         "annotation",
@@ -101,7 +94,7 @@ fn emphasis_variations_are_highlighted() {
     // Zed's injection system to work properly. The basic tree-sitter-highlight
     // library used in tests doesn't support cross-grammar injection.
 
-    // In Zed, these should all work correctly with the pandoc_markdown_inline grammar.
+    // In Zed, these should all work correctly with the tree-sitter-quarto grammar.
 
     // Test single asterisks (italic)
     if rendered.contains("<text.emphasis>") && rendered.contains("italic text") {
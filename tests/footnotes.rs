@@ -0,0 +1,189 @@
+use quarto_zed::footnotes;
+use quarto_zed::lsp::Server;
+use quarto_zed::text::{apply_edits, Position, Range};
+use serde_json::json;
+
+fn cursor(line: u32, character: u32) -> Range {
+    Range::new(
+        Position::new(line, character),
+        Position::new(line, character),
+    )
+}
+
+#[test]
+fn notes_and_references_are_found() {
+    let source = r#"Text[^1] and more[^long-note], `[^code]` and ^[an *inline* note].
+
+[^1]: First note.
+
+[^long-note]: A note with
+lazy continuation.
+
+    And a second paragraph citing [^1].
+
+After the notes.
+"#;
+    let notes = footnotes::notes(source);
+    let found: Vec<(&str, usize, usize)> = notes
+        .iter()
+        .map(|note| (note.label.as_str(), note.line, note.end_line))
+        .collect();
+    assert_eq!(found, [("1", 2, 2), ("long-note", 4, 7)]);
+    assert_eq!(
+        notes[1].content,
+        "A note with\nlazy continuation.\n\nAnd a second paragraph citing [^1]."
+    );
+    assert!(notes[0].is_single_paragraph());
+    assert!(!notes[1].is_single_paragraph());
+
+    let references = footnotes::references(source);
+    let labels: Vec<(&str, usize)> = references
+        .iter()
+        .map(|reference| (reference.label.as_str(), reference.line))
+        .collect();
+    assert_eq!(
+        labels,
+        [("1", 0), ("long-note", 0), ("1", 7)],
+        "code spans and note markers are not references"
+    );
+    assert_eq!((references[0].start, references[0].end), (4, 8));
+
+    let inline = footnotes::inline_notes(source);
+    assert_eq!(inline.len(), 1);
+    assert_eq!(inline[0].content, "an *inline* note");
+}
+
+#[test]
+fn problems_are_reported() {
+    let source =
+        "One[^a] and two[^missing].\n\n[^a]: Used.\n[^unused]: Never referenced.\n[^a]: Again.\n";
+    let diagnostics = footnotes::diagnostics(source);
+    let found: Vec<(&str, u32)> = diagnostics
+        .iter()
+        .map(|d| (d.code, d.range.start.line))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("undefined-footnote", 0),
+            ("unused-footnote", 3),
+            ("duplicate-footnote", 4),
+        ]
+    );
+    assert_eq!(diagnostics[0].message, "No note for footnote [^missing]");
+    assert_eq!(diagnostics[0].range.start.character, 15);
+    assert_eq!(
+        diagnostics[2].message,
+        "Footnote [^a] is already defined on line 3"
+    );
+}
+
+#[test]
+fn navigation_goes_both_ways() {
+    let source = "First[^n] and again[^n].\n\n[^n]: The note.\n";
+    let to_note = footnotes::navigate(source, Position::new(0, 6));
+    assert_eq!(
+        to_note,
+        [Range::new(Position::new(2, 0), Position::new(2, 4))]
+    );
+
+    let to_references = footnotes::navigate(source, Position::new(2, 2));
+    let starts: Vec<Position> = to_references.iter().map(|range| range.start).collect();
+    assert_eq!(starts, [Position::new(0, 5), Position::new(0, 19)]);
+
+    assert!(footnotes::navigate(source, Position::new(2, 8)).is_empty());
+    assert_eq!(
+        footnotes::references_at(source, Position::new(0, 20), true).len(),
+        3
+    );
+}
+
+#[test]
+fn numeric_footnotes_are_renumbered_in_order_of_use() {
+    let source = "A[^3] B[^1] C[^named] D[^3].\n\n[^1]: One.\n[^3]: Three.\n[^7]: Unused.\n[^named]: Kept.\n";
+    let actions = footnotes::code_actions(source, cursor(0, 2));
+    let renumber = actions
+        .iter()
+        .find(|action| action.title == "Renumber footnotes in order of use")
+        .expect("renumber action");
+    assert_eq!(
+        apply_edits(source, &renumber.edits),
+        "A[^1] B[^2] C[^named] D[^1].\n\n[^2]: One.\n[^1]: Three.\n[^3]: Unused.\n[^named]: Kept.\n"
+    );
+
+    let ordered = "A[^1] B[^2].\n\n[^1]: One.\n[^2]: Two.\n";
+    assert!(footnotes::code_actions(ordered, cursor(0, 2))
+        .iter()
+        .all(|action| action.kind != "source"));
+}
+
+#[test]
+fn notes_convert_between_inline_and_reference_style() {
+    let source = "Claim^[Source, p. 4.] and another[^2].\n\n[^2]: Existing.\n";
+    let actions = footnotes::code_actions(source, cursor(0, 8));
+    let reference = actions
+        .iter()
+        .find(|action| action.kind == "refactor.rewrite")
+        .expect("reference-style action");
+    assert_eq!(reference.title, "Convert to footnote [^3]");
+    assert_eq!(
+        apply_edits(source, &reference.edits),
+        "Claim[^3] and another[^2].\n\n[^2]: Existing.\n[^3]: Source, p. 4.\n"
+    );
+
+    let source = "Claim[^src] here.\n\n[^src]: Source,\n    p. 4.\n\nMore text.\n";
+    let actions = footnotes::code_actions(source, cursor(0, 7));
+    let inline = actions
+        .iter()
+        .find(|action| action.kind == "refactor.inline")
+        .expect("inline action");
+    assert_eq!(
+        apply_edits(source, &inline.edits),
+        "Claim^[Source, p. 4.] here.\n\n\nMore text.\n"
+    );
+}
+
+#[test]
+fn language_server_navigates_footnotes() {
+    let mut server = Server::new();
+    let uri = "untitled:notes.qmd";
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {
+                "uri": uri,
+                "languageId": "quarto",
+                "version": 1,
+                "text": "Text[^1] and[^2].\n\n[^1]: Note.\n",
+            }
+        }
+    }));
+    assert_eq!(
+        replies[0]["params"]["diagnostics"][0]["code"],
+        "undefined-footnote"
+    );
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/definition",
+        "params": {
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 5 }
+        }
+    }));
+    assert_eq!(replies[0]["result"][0]["uri"], uri);
+    assert_eq!(replies[0]["result"][0]["range"]["start"]["line"], 2);
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "textDocument/definition",
+        "params": {
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 0 }
+        }
+    }));
+    assert!(replies[0]["result"].is_null());
+}
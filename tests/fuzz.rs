@@ -0,0 +1,101 @@
+//! Robustness of tree-sitter-quarto on generated and previously crashing input.
+//!
+//! The property tests assemble documents with `support::fuzz` from a fixed
+//! seed, so every run checks the same cases; proptest records shrunk failures
+//! in `tests/fuzz.regressions` and replays them first. Crashers minimized by
+//! the cargo-fuzz targets in `fuzz/` are kept in `tests/fuzz-regressions/`:
+//! `raw/` holds documents and `documents/` holds generator input.
+mod support;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proptest::prelude::*;
+use proptest::test_runner::{FileFailurePersistence, RngSeed};
+use support::fuzz::{self, Document};
+
+fn regressions(kind: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fuzz-regressions")
+        .join(kind);
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("{} readable: {}", dir.display(), e))
+        .map(|entry| entry.expect("readable regression entry").path())
+        .filter(|path| path.is_file())
+        .filter(|path| !path.file_name().unwrap().to_string_lossy().starts_with('.'))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn saved_documents_terminate() {
+    for path in regressions("raw") {
+        let source =
+            String::from_utf8_lossy(&fs::read(&path).expect("readable regression")).into_owned();
+        if let Err(message) = fuzz::check_terminates(&source) {
+            panic!("{}: {}", path.display(), message);
+        }
+    }
+}
+
+#[test]
+fn saved_generator_inputs_pass() {
+    for path in regressions("documents") {
+        let document = Document::from_bytes(&fs::read(&path).expect("readable regression"));
+        if let Err(message) = fuzz::check(&document) {
+            panic!(
+                "{}: {}\nDocument:\n{}",
+                path.display(),
+                message,
+                document.source
+            );
+        }
+    }
+}
+
+#[test]
+fn generator_covers_fenced_regions() {
+    // Selector bytes: front matter, then a python cell and a tilde block
+    let document = Document::from_bytes(&[0, 1, 0, 2, 0, 0, 0, 1, 0, 3, 1, 0, 1, 0]);
+    assert!(document.source.starts_with("---\n"));
+    assert!(document.source.contains("```{python}\n"));
+    assert_eq!(document.fenced.len(), 3, "{}", document.source);
+    for fenced in &document.fenced {
+        let text = &document.source[fenced.clone()];
+        let first = text.lines().next().unwrap();
+        let last = text.lines().last().unwrap();
+        assert!(
+            first.starts_with(last),
+            "{:?} should open with its closing fence {:?}",
+            first,
+            last
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 256,
+        rng_seed: RngSeed::Fixed(0x7175_6172_746f),
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn generated_documents_parse_within_their_fences(
+        bytes in proptest::collection::vec(any::<u8>(), 0..1024)
+    ) {
+        let document = Document::from_bytes(&bytes);
+        if let Err(message) = fuzz::check(&document) {
+            return Err(TestCaseError::fail(format!("{}\nDocument:\n{}", message, document.source)));
+        }
+    }
+
+    #[test]
+    fn arbitrary_text_parses_within_the_time_bound(source in "\\PC{0,512}") {
+        if let Err(message) = fuzz::check_terminates(&source) {
+            return Err(TestCaseError::fail(message));
+        }
+    }
+}
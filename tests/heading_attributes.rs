@@ -1,14 +1,6 @@
-use tree_sitter::{Language, Parser};
+mod support;
 
-// External linkage to tree-sitter-quarto grammar
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
+use support::parse;
 
 #[test]
 fn heading_with_class_attribute() {
@@ -17,20 +9,15 @@ fn heading_with_class_attribute() {
 Some text.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
+    let parsed = parse(source);
 
     // Debug: print the tree structure
     eprintln!(
         "\n=== HEADING WITH CLASS ATTRIBUTE ===\n{}\n",
-        tree.root_node().to_sexp()
+        parsed.sexp()
     );
 
-    let root = tree.root_node();
+    let root = parsed.root();
     let heading = root.child(0).expect("should have heading");
 
     assert_eq!(heading.kind(), "atx_heading");
@@ -47,7 +34,7 @@ Some text.
     for i in 0..attributes.child_count() {
         if let Some(child) = attributes.child(i) {
             if child.kind() == "attribute_class" {
-                let class_text = &source[child.byte_range()];
+                let class_text = parsed.text(child);
                 assert!(class_text.contains("unnumbered"));
                 found_class = true;
             }
@@ -65,14 +52,9 @@ fn heading_with_id_attribute() {
 Content.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
+    let parsed = parse(source);
 
-    let root = tree.root_node();
+    let root = parsed.root();
     let heading = root.child(0).expect("should have heading");
 
     // Verify heading has attributes
@@ -85,7 +67,7 @@ Content.
     for i in 0..attributes.child_count() {
         if let Some(child) = attributes.child(i) {
             if child.kind() == "attribute_id" {
-                let id_text = &source[child.byte_range()];
+                let id_text = parsed.text(child);
                 assert!(id_text.contains("custom-id"));
                 found_id = true;
             }
@@ -103,19 +85,11 @@ fn heading_with_multiple_attributes() {
 Text.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
+    let parsed = parse(source);
 
-    eprintln!(
-        "\n=== MULTIPLE ATTRIBUTES ===\n{}\n",
-        tree.root_node().to_sexp()
-    );
+    eprintln!("\n=== MULTIPLE ATTRIBUTES ===\n{}\n", parsed.sexp());
 
-    let root = tree.root_node();
+    let root = parsed.root();
     let heading = root.child(0).expect("should have heading");
 
     let attributes = heading
@@ -152,14 +126,9 @@ fn heading_without_attributes() {
 Text.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
+    let parsed = parse(source);
 
-    let root = tree.root_node();
+    let root = parsed.root();
     let heading = root.child(0).expect("should have heading");
 
     assert_eq!(heading.kind(), "atx_heading");
@@ -186,38 +155,13 @@ This is plain text that should not be highlighted as part of the heading.
 More text.
 "#;
 
-    let mut parser = Parser::new();
-    let lang = language();
-    parser.set_language(&lang).expect("parser loads language");
-    let tree = parser
-        .parse(source.as_bytes(), None)
-        .expect("parse succeeds");
-
-    // Walk the entire tree looking for ERROR nodes
-    let mut cursor = tree.walk();
-    let mut has_errors = false;
-
-    fn check_for_errors(cursor: &mut tree_sitter::TreeCursor, has_errors: &mut bool) {
-        if cursor.node().kind() == "ERROR" {
-            *has_errors = true;
-        }
-
-        if cursor.goto_first_child() {
-            loop {
-                check_for_errors(cursor, has_errors);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
-    }
-
-    check_for_errors(&mut cursor, &mut has_errors);
+    let parsed = parse(source);
 
+    let errors = parsed.errors();
     assert!(
-        !has_errors,
-        "Document with heading attributes should not contain ERROR nodes"
+        errors.is_empty(),
+        "Document with heading attributes should not contain ERROR nodes: {}",
+        errors.join(", ")
     );
     println!("✓ No ERROR nodes with heading attributes - bug is fixed!");
 }
@@ -1,19 +1,12 @@
-use tree_sitter::{Language, Parser};
-use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
-
-// External linkage to tree-sitter-quarto grammar
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+mod support;
 
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
+use support::language;
+use tree_sitter::Parser;
+use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
 
 fn highlight_configuration() -> HighlightConfiguration {
-    let highlight_query = include_str!("../grammars/quarto/queries/highlights.scm");
-    let injection_query = include_str!("../grammars/quarto/queries/injections.scm");
+    let highlight_query = support::HIGHLIGHTS;
+    let injection_query = support::INJECTIONS;
     let locals_query = "";
 
     eprintln!("\n=== QUERIES BEING USED ===");
@@ -1,18 +1,17 @@
+mod support;
+
+use support::language;
+
 /// Deep analysis of what highlight captures are actually being generated
 /// This will show us if child text nodes are being captured or not
-use tree_sitter::{Language, Parser};
+use tree_sitter::Parser;
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
 #[test]
 fn analyze_emphasis_captures() {
     let source = "*italic text*\n";
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
 
     // First, let's see the AST structure
     let mut parser = Parser::new();
@@ -175,7 +174,7 @@ fn analyze_emphasis_captures() {
 fn analyze_heading_captures() {
     let source = "# Heading Text\n";
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
     let tree = parser.parse(source, None).unwrap();
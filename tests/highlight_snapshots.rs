@@ -0,0 +1,153 @@
+//! Highlight snapshots for the documents in `tests/corpus/`.
+//!
+//! Each document is highlighted with the extension's queries and rendered by
+//! `Highlights::snapshot`, then compared with `tests/corpus/snapshots/<name>.snap`.
+//! A mismatch writes `<name>.snap.new` next to the snapshot and fails with the
+//! diff. Review pending snapshots with `scripts/review-snapshots.sh`, or rerun
+//! with `UPDATE_SNAPSHOTS=1` to accept every change.
+mod support;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+fn snapshot_dir() -> PathBuf {
+    corpus_dir().join("snapshots")
+}
+
+fn corpus_documents() -> Vec<PathBuf> {
+    let mut documents: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .expect("tests/corpus exists")
+        .map(|entry| entry.expect("readable corpus entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "qmd"))
+        .collect();
+    documents.sort();
+    documents
+}
+
+fn name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .expect("UTF-8 file name")
+        .to_string()
+}
+
+#[test]
+fn corpus_highlights_match_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let documents = corpus_documents();
+    assert!(!documents.is_empty(), "tests/corpus has no .qmd documents");
+
+    fs::create_dir_all(snapshot_dir()).expect("snapshot directory created");
+    let mut failures = Vec::new();
+    for document in &documents {
+        let source = fs::read_to_string(document)
+            .unwrap_or_else(|e| panic!("{} readable: {}", document.display(), e));
+        let actual = support::highlight(&source).snapshot();
+        let snapshot = snapshot_dir().join(format!("{}.snap", name(document)));
+        let pending = snapshot.with_extension("snap.new");
+
+        let expected = fs::read_to_string(&snapshot).ok();
+        if expected.as_deref() == Some(actual.as_str()) || update {
+            if expected.as_deref() != Some(actual.as_str()) {
+                fs::write(&snapshot, &actual).expect("snapshot written");
+                eprintln!("updated {}", snapshot.display());
+            }
+            let _ = fs::remove_file(&pending);
+            continue;
+        }
+
+        fs::write(&pending, &actual).expect("pending snapshot written");
+        failures.push(match expected {
+            Some(expected) => format!(
+                "{} changed:\n{}",
+                snapshot.display(),
+                diff(&expected, &actual)
+            ),
+            None => format!("{} has no snapshot yet", document.display()),
+        });
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\n\nReview the .snap.new files with scripts/review-snapshots.sh, \
+         or rerun with UPDATE_SNAPSHOTS=1 to accept them all.",
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn every_snapshot_has_a_corpus_document() {
+    let documents: Vec<String> = corpus_documents().iter().map(|path| name(path)).collect();
+    let Ok(snapshots) = fs::read_dir(snapshot_dir()) else {
+        return;
+    };
+    let orphans: Vec<String> = snapshots
+        .map(|entry| entry.expect("readable snapshot entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "snap"))
+        .filter(|path| !documents.contains(&name(path)))
+        .map(|path| path.display().to_string())
+        .collect();
+    assert!(
+        orphans.is_empty(),
+        "snapshots without a document in tests/corpus (delete them): {:?}",
+        orphans
+    );
+}
+
+/// Changed lines, `-` from the snapshot and `+` from the new rendering, with
+/// two lines of context around each change.
+fn diff(expected: &str, actual: &str) -> String {
+    const CONTEXT: usize = 2;
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    let mut output = String::new();
+    let mut last_shown = None;
+    for (k, (marker, line)) in lines.iter().enumerate() {
+        let near_change = changed
+            .iter()
+            .any(|&c| k + CONTEXT >= c && k <= c + CONTEXT);
+        if !near_change {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < k) {
+            output.push_str("  ...\n");
+        }
+        output.push_str(&format!("{} {}\n", marker, line));
+        last_shown = Some(k);
+    }
+    output
+}
@@ -13,16 +13,14 @@
 ///
 /// This test documents which capture names are valid for indent queries
 /// and ensures our queries don't use unrecognized names.
-use tree_sitter::{Language, Query};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use support::language;
+use tree_sitter::Query;
 
 #[test]
 fn indents_query_is_valid() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let indents = std::fs::read_to_string("languages/quarto/indents.scm")
         .expect("Failed to read indents.scm");
 
@@ -40,7 +38,7 @@ fn indents_query_uses_recognized_captures() {
     // Document valid capture names for Zed indent queries
     // Based on tree-sitter documentation and Zed's implementation
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let indents = std::fs::read_to_string("languages/quarto/indents.scm")
         .expect("Failed to read indents.scm");
 
@@ -94,7 +92,7 @@ fn indents_query_compiles_without_warnings() {
     // This test will fail if indents.scm has issues that would cause
     // runtime warnings in Zed
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let indents = std::fs::read_to_string("languages/quarto/indents.scm")
         .expect("Failed to read indents.scm");
 
@@ -18,25 +18,19 @@
 /// 3. Links have link_text and link_destination nodes
 /// 4. Images have image_alt and image_source nodes
 /// 5. Headings contain inline content with nested formatting
-use tree_sitter::{Language, Parser, Query};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use support::{language, parse};
+use tree_sitter::Query;
 
 #[test]
 fn emphasis_has_delimiter_and_text_nodes() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = Parser::new();
-    parser.set_language(&language).unwrap();
-
     let source = "*italic text*\n";
-    let tree = parser.parse(source, None).unwrap();
-    let root = tree.root_node();
+    let parsed = parse(source);
+    let root = parsed.root();
 
     // Should have emphasis_delimiter nodes
-    let has_delimiter = check_for_node_type(&root, "emphasis_delimiter");
+    let has_delimiter = parsed.has("emphasis_delimiter");
     assert!(
         has_delimiter,
         "Grammar doesn't create emphasis_delimiter nodes. Tree:\n{}",
@@ -44,7 +38,7 @@ fn emphasis_has_delimiter_and_text_nodes() {
     );
 
     // Should have text nodes inside emphasis
-    let has_text = check_for_nested_node(&root, "emphasis", "text");
+    let has_text = parsed.has_within("emphasis", "text");
     assert!(
         has_text,
         "Grammar doesn't create text nodes inside emphasis. Tree:\n{}",
@@ -54,16 +48,12 @@ fn emphasis_has_delimiter_and_text_nodes() {
 
 #[test]
 fn strong_emphasis_has_delimiter_and_text_nodes() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = Parser::new();
-    parser.set_language(&language).unwrap();
-
     let source = "**bold text**\n";
-    let tree = parser.parse(source, None).unwrap();
-    let root = tree.root_node();
+    let parsed = parse(source);
+    let root = parsed.root();
 
     // Should have strong_emphasis_delimiter nodes
-    let has_delimiter = check_for_node_type(&root, "strong_emphasis_delimiter");
+    let has_delimiter = parsed.has("strong_emphasis_delimiter");
     assert!(
         has_delimiter,
         "Grammar doesn't create strong_emphasis_delimiter nodes. Tree:\n{}",
@@ -71,7 +61,7 @@ fn strong_emphasis_has_delimiter_and_text_nodes() {
     );
 
     // Should have text nodes inside strong_emphasis
-    let has_text = check_for_nested_node(&root, "strong_emphasis", "text");
+    let has_text = parsed.has_within("strong_emphasis", "text");
     assert!(
         has_text,
         "Grammar doesn't create text nodes inside strong_emphasis. Tree:\n{}",
@@ -81,16 +71,12 @@ fn strong_emphasis_has_delimiter_and_text_nodes() {
 
 #[test]
 fn link_has_text_and_destination_nodes() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = Parser::new();
-    parser.set_language(&language).unwrap();
-
     let source = "This is a [link](https://example.com) in text.\n";
-    let tree = parser.parse(source, None).unwrap();
-    let root = tree.root_node();
+    let parsed = parse(source);
+    let root = parsed.root();
 
     // Should have link_text node
-    let has_link_text = check_for_node_type(&root, "link_text");
+    let has_link_text = parsed.has("link_text");
     assert!(
         has_link_text,
         "Grammar doesn't create link_text nodes. Tree:\n{}",
@@ -98,7 +84,7 @@ fn link_has_text_and_destination_nodes() {
     );
 
     // Should have link_destination node
-    let has_link_dest = check_for_node_type(&root, "link_destination");
+    let has_link_dest = parsed.has("link_destination");
     assert!(
         has_link_dest,
         "Grammar doesn't create link_destination nodes. Tree:\n{}",
@@ -108,16 +94,12 @@ fn link_has_text_and_destination_nodes() {
 
 #[test]
 fn image_has_alt_and_source_nodes() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = Parser::new();
-    parser.set_language(&language).unwrap();
-
     let source = "![alt text](image.png)\n";
-    let tree = parser.parse(source, None).unwrap();
-    let root = tree.root_node();
+    let parsed = parse(source);
+    let root = parsed.root();
 
     // Should have image_alt node
-    let has_image_alt = check_for_node_type(&root, "image_alt");
+    let has_image_alt = parsed.has("image_alt");
     assert!(
         has_image_alt,
         "Grammar doesn't create image_alt nodes. Tree:\n{}",
@@ -125,7 +107,7 @@ fn image_has_alt_and_source_nodes() {
     );
 
     // Should have image_source node
-    let has_image_src = check_for_node_type(&root, "image_source");
+    let has_image_src = parsed.has("image_source");
     assert!(
         has_image_src,
         "Grammar doesn't create image_source nodes. Tree:\n{}",
@@ -135,20 +117,16 @@ fn image_has_alt_and_source_nodes() {
 
 #[test]
 fn heading_contains_inline_with_nested_formatting() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = Parser::new();
-    parser.set_language(&language).unwrap();
-
     let source = "## Heading with *italic* and **bold**\n";
-    let tree = parser.parse(source, None).unwrap();
-    let root = tree.root_node();
+    let parsed = parse(source);
+    let root = parsed.root();
 
     // Should have atx_heading with inline content
-    let has_heading = check_for_node_type(&root, "atx_heading");
+    let has_heading = parsed.has("atx_heading");
     assert!(has_heading, "Grammar doesn't parse ATX headings");
 
     // Should have emphasis inside heading
-    let has_emphasis_in_heading = check_for_nested_node(&root, "atx_heading", "emphasis");
+    let has_emphasis_in_heading = parsed.has_within("atx_heading", "emphasis");
     assert!(
         has_emphasis_in_heading,
         "Grammar doesn't parse emphasis inside headings. Tree:\n{}",
@@ -156,7 +134,7 @@ fn heading_contains_inline_with_nested_formatting() {
     );
 
     // Should have strong_emphasis inside heading
-    let has_strong_in_heading = check_for_nested_node(&root, "atx_heading", "strong_emphasis");
+    let has_strong_in_heading = parsed.has_within("atx_heading", "strong_emphasis");
     assert!(
         has_strong_in_heading,
         "Grammar doesn't parse strong_emphasis inside headings. Tree:\n{}",
@@ -168,7 +146,7 @@ fn heading_contains_inline_with_nested_formatting() {
 fn highlights_query_uses_valid_node_types() {
     // This test validates that our highlights.scm only uses node types
     // that actually exist in the grammar
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let highlights = std::fs::read_to_string("languages/quarto/highlights.scm")
         .expect("Failed to read highlights.scm");
 
@@ -186,7 +164,7 @@ fn highlights_query_uses_valid_node_types() {
 #[test]
 fn emphasis_queries_compile_successfully() {
     // Test that our emphasis queries from highlights.scm can compile
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
 
     // Our emphasis queries from highlights.scm
     let emphasis_queries = r#"
@@ -208,7 +186,7 @@ fn emphasis_queries_compile_successfully() {
 #[test]
 fn link_queries_compile_successfully() {
     // Test that our link queries from highlights.scm can compile
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
 
     // Our link queries from highlights.scm
     let link_queries = r#"
@@ -224,44 +202,3 @@ fn link_queries_compile_successfully() {
         result.err()
     );
 }
-
-// Helper functions
-
-fn check_for_node_type(node: &tree_sitter::Node, node_type: &str) -> bool {
-    if node.kind() == node_type {
-        return true;
-    }
-
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if check_for_node_type(&child, node_type) {
-            return true;
-        }
-    }
-
-    false
-}
-
-fn check_for_nested_node(node: &tree_sitter::Node, parent_type: &str, child_type: &str) -> bool {
-    if node.kind() == parent_type {
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.kind() == child_type {
-                return true;
-            }
-            // Also check grandchildren in case child_type is nested deeper
-            if check_for_node_type(&child, child_type) {
-                return true;
-            }
-        }
-    }
-
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if check_for_nested_node(&child, parent_type, child_type) {
-            return true;
-        }
-    }
-
-    false
-}
@@ -40,6 +40,7 @@
 /// - `tags.scm`: Symbol navigation (go to definition, find references)
 /// - `locals.scm`: Local scope support for accurate symbol resolution
 /// - `textobjects.scm`: Text object selection (e.g., select inside function)
+/// - `runnables.scm`: Gutter run buttons (paired with task templates in `tasks.json`)
 ///
 /// ## What Happens Without These Files
 ///
@@ -141,6 +142,7 @@ fn optional_query_files_documented() {
         ("tags.scm", "Symbol navigation"),
         ("locals.scm", "Local scope support"),
         ("textobjects.scm", "Text object selection"),
+        ("runnables.scm", "Runnable code cells"),
     ];
 
     println!("\n📋 Optional language query files:");
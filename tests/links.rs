@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quarto_zed::links::{self, Problem};
+use quarto_zed::lsp::{path_to_uri, Server};
+use quarto_zed::project::Project;
+use serde_json::json;
+
+fn site_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/projects/website")
+}
+
+fn targets(text: &str) -> Vec<String> {
+    links::links(text)
+        .into_iter()
+        .map(|link| link.target)
+        .collect()
+}
+
+#[test]
+fn links_cover_inline_images_and_definitions() {
+    assert_eq!(
+        targets("[link text](https://example.com)\n"),
+        ["https://example.com"]
+    );
+    assert_eq!(
+        targets("Compare [inline link](https://inline.com) with [reference link][ref].\n\n[ref]: https://reference.com\n"),
+        ["https://inline.com", "https://reference.com"]
+    );
+    assert_eq!(
+        targets("[docs]: <guides/my page.qmd> \"Documentation\"\n"),
+        ["guides/my page.qmd"]
+    );
+    assert_eq!(
+        targets("[![Badge](badge.svg)](status.qmd \"Status\")\n"),
+        ["status.qmd", "badge.svg"],
+        "images nested in links"
+    );
+    assert!(targets("[^1]: A footnote, not a link.\n").is_empty());
+    assert!(targets("Escaped \\[text](not-a-link.qmd) and `[code](x.qmd)`\n").is_empty());
+
+    let link = &links::links("See [about](about.qmd).\n")[0];
+    assert_eq!((link.line, link.start, link.end), (0, 12, 21));
+}
+
+#[test]
+fn heading_ids_follow_pandoc() {
+    assert_eq!(links::heading_id("Getting Started"), "getting-started");
+    assert_eq!(links::heading_id("1.2 What's new?"), "whats-new");
+    assert_eq!(
+        links::heading_id("Using [Quarto](https://quarto.org) v1.4"),
+        "using-quarto-v1.4"
+    );
+    assert_eq!(
+        links::heading_id("snake_case and-dashes"),
+        "snake_case-and-dashes"
+    );
+    assert_eq!(links::heading_id("2024"), "section");
+
+    let anchors = links::anchors(
+        "# Results\n\n# Results\n\n## Methods {#sec-methods}\n\n::: {#fig-map}\n:::\n\n```{r}\n#| label: tbl-summary\n```\n",
+    );
+    assert_eq!(
+        anchors,
+        [
+            "results",
+            "results-1",
+            "sec-methods",
+            "fig-map",
+            "tbl-summary"
+        ]
+    );
+}
+
+#[test]
+fn relative_links_and_anchors_are_checked() {
+    let root = site_root();
+    let path = root.join("index.qmd");
+    let text = fs::read_to_string(&path).unwrap();
+    let broken: Vec<(String, Problem)> = links::check_document(&root, &path, &text)
+        .into_iter()
+        .map(|broken| (broken.link.target, broken.problem))
+        .collect();
+    assert_eq!(
+        broken,
+        [
+            (
+                "guides/setup.qmd#prerequisites".to_string(),
+                Problem::MissingAnchor
+            ),
+            ("notes.qmd".to_string(), Problem::MissingFile),
+            ("#summary".to_string(), Problem::MissingAnchor),
+            ("images/logo.png".to_string(), Problem::MissingFile),
+        ],
+        "about.qmd#team, .html pages, site-root links, code and external links are fine"
+    );
+
+    let setup = root.join("guides/setup.qmd");
+    let text = fs::read_to_string(&setup).unwrap();
+    assert!(
+        links::check_document(&root, &setup, &text).is_empty(),
+        "../ links resolve from the document's directory"
+    );
+}
+
+#[test]
+fn navigation_entries_are_checked() {
+    let project = Project::load(&site_root(), None).unwrap();
+    let website = project.website.as_ref().unwrap();
+    assert_eq!(website.sidebar[0].title.as_deref(), Some("Guides"));
+    assert_eq!(
+        website.sidebar[0].contents[1].text.as_deref(),
+        Some("Getting started"),
+        "sidebar sections become items with children"
+    );
+    assert_eq!(website.sidebar[0].contents[1].menu.len(), 2);
+
+    let broken = links::check_navigation(&project);
+    let hrefs: Vec<(&str, Problem)> = broken
+        .iter()
+        .map(|item| (item.href.as_str(), item.problem))
+        .collect();
+    assert_eq!(
+        hrefs,
+        [
+            ("guides/retired.qmd", Problem::MissingFile),
+            ("guides/usage.qmd#uninstall", Problem::MissingAnchor),
+        ]
+    );
+    assert_eq!(broken[0].text.as_deref(), Some("Retired guide"));
+
+    let config = fs::read_to_string(site_root().join("_quarto.yml")).unwrap();
+    let diagnostics = links::nav_diagnostics(&broken, &config);
+    assert_eq!(diagnostics[0].code, "broken-nav-link");
+    assert_eq!(
+        diagnostics[0].range.start.line as usize,
+        config
+            .lines()
+            .position(|line| line.contains("retired"))
+            .unwrap()
+    );
+}
+
+#[test]
+fn nav_diagnostics_match_the_whole_href() {
+    let config = "\
+website:
+  navbar:
+    left:
+      - href: team/about.qmd
+      - text: About
+        href: 'about.qmd' # the old page
+";
+    let broken = [links::BrokenNavItem {
+        href: "about.qmd".to_string(),
+        text: Some("About".to_string()),
+        problem: Problem::MissingFile,
+        message: "about.qmd does not exist".to_string(),
+    }];
+    let diagnostics = links::nav_diagnostics(&broken, config);
+    let range = diagnostics[0].range;
+    assert_eq!(range.start.line, 5, "not the team/about.qmd entry");
+    let line = config.lines().nth(5).unwrap();
+    assert_eq!(
+        &line[range.start.character as usize..range.end.character as usize],
+        "about.qmd"
+    );
+}
+
+#[test]
+fn report_groups_problems_by_document() {
+    let root = site_root();
+    let documents: Vec<(String, Vec<links::BrokenLink>)> = ["index.qmd", "about.qmd"]
+        .iter()
+        .map(|path| {
+            let text = fs::read_to_string(root.join(path)).unwrap();
+            (
+                path.to_string(),
+                links::check_document(&root, &root.join(path), &text),
+            )
+        })
+        .collect();
+    let project = Project::load(&root, None).unwrap();
+    let (text, sections) = links::render(&documents, &links::check_navigation(&project), 12);
+
+    assert!(
+        text.starts_with("Checked 12 links in 2 documents: 6 broken.\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("- guides/retired.qmd (Retired guide): guides/retired.qmd does not exist\n"),
+        "{text}"
+    );
+    assert!(
+        text.contains("\nindex.qmd:\n- line 10: guides/setup.qmd#prerequisites: No heading or element with id #prerequisites in guides/setup.qmd\n- line 10: notes.qmd: notes.qmd does not exist\n"),
+        "{text}"
+    );
+    assert!(
+        !text.contains("about.qmd:"),
+        "documents without problems are left out"
+    );
+    let labels: Vec<&str> = sections.iter().map(|(_, label)| label.as_str()).collect();
+    assert_eq!(labels, ["Navigation", "index.qmd (4)"]);
+}
+
+#[test]
+fn language_server_reports_broken_links() {
+    let root = site_root();
+    let path = root.join("index.qmd");
+    let uri = path_to_uri(&path);
+    let text = fs::read_to_string(&path).unwrap();
+    let mut server = Server::new();
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri, "languageId": "quarto", "version": 1, "text": text }
+        }
+    }));
+
+    let diagnostics = replies[0]["params"]["diagnostics"].as_array().unwrap();
+    let codes: Vec<&str> = diagnostics
+        .iter()
+        .map(|d| d["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            "broken-anchor",
+            "broken-link",
+            "broken-anchor",
+            "broken-link"
+        ]
+    );
+    assert_eq!(
+        diagnostics[1]["range"]["start"],
+        json!({ "line": 9, "character": 72 })
+    );
+
+    assert_eq!(
+        replies[1]["params"]["uri"],
+        path_to_uri(&root.join("_quarto.yml"))
+    );
+    assert_eq!(
+        replies[1]["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Links are checked on open and save; edited ones drop out until then.
+    let codes = |replies: Vec<serde_json::Value>| -> Vec<String> {
+        replies[0]["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["code"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": format!("\n{text}") }],
+        }
+    }));
+    assert!(codes(replies).is_empty(), "every checked link moved down");
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didSave",
+        "params": { "textDocument": { "uri": uri } }
+    }));
+    assert_eq!(codes(replies).len(), 4);
+    assert_eq!(
+        server.diagnostics(&uri)[1].range.start.line,
+        10,
+        "positions come from the saved text"
+    );
+}
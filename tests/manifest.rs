@@ -6,35 +6,136 @@ use std::process::Command;
 fn language_grammar_is_valid() {
     let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
 
-    let config_str = fs::read_to_string(manifest_dir.join("languages/quarto/config.toml"))
-        .expect("language config readable");
-    let config: toml::Value = toml::from_str(&config_str).expect("language config parses as TOML");
-    let language_grammar = config
-        .get("grammar")
-        .and_then(|value| value.as_str())
-        .expect("language config must declare a grammar");
+    let manifest_str = fs::read_to_string(manifest_dir.join("extension.toml"))
+        .expect("extension manifest readable");
+    let manifest: toml::Value = toml::from_str(&manifest_str).expect("manifest parses as TOML");
+
+    let languages = manifest
+        .get("languages")
+        .and_then(|value| value.as_array())
+        .expect("extension manifest must list languages");
+
+    for language_dir in languages.iter().filter_map(|value| value.as_str()) {
+        let config_str = fs::read_to_string(manifest_dir.join(language_dir).join("config.toml"))
+            .expect("language config readable");
+        let config: toml::Value =
+            toml::from_str(&config_str).expect("language config parses as TOML");
+        let language_grammar = config
+            .get("grammar")
+            .and_then(|value| value.as_str())
+            .expect("language config must declare a grammar");
+
+        // Check if grammar is defined in extension manifest or is a known built-in
+        let known_builtin_grammars = ["markdown", "json", "toml", "yaml"];
+        let is_defined_in_manifest = manifest
+            .get("grammars")
+            .and_then(|value| value.as_table())
+            .map(|table| table.contains_key(language_grammar))
+            .unwrap_or(false);
+
+        let is_builtin = known_builtin_grammars.contains(&language_grammar);
+
+        assert!(
+            is_defined_in_manifest || is_builtin,
+            "grammar '{}' in {} must be either defined in extension.toml or be a known Zed built-in grammar",
+            language_grammar,
+            language_dir
+        );
+    }
+}
+
+#[test]
+fn grammar_revision_is_pinned_only_in_manifest() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
 
     let manifest_str = fs::read_to_string(manifest_dir.join("extension.toml"))
         .expect("extension manifest readable");
     let manifest: toml::Value = toml::from_str(&manifest_str).expect("manifest parses as TOML");
+    let rev = manifest["grammars"]["quarto"]["rev"]
+        .as_str()
+        .expect("extension manifest must pin [grammars.quarto] rev");
 
-    // Check if grammar is defined in extension manifest or is a known built-in
-    let known_builtin_grammars = ["markdown", "json", "toml", "yaml"];
-    let is_defined_in_manifest = manifest
-        .get("grammars")
-        .and_then(|value| value.as_table())
-        .map(|table| table.contains_key(language_grammar))
-        .unwrap_or(false);
+    // build.rs and scripts/fetch-grammars.sh read the rev from extension.toml, so
+    // any other commit hash in the files that fetch or build the grammar is a
+    // stale pin. Workflows also pin action versions by hash, so only their
+    // lines that mention the grammar count.
+    let commit = regex::Regex::new(r"\b[0-9a-f]{40}\b").unwrap();
+    let mut files: Vec<(std::path::PathBuf, bool)> =
+        ["build.rs", "Cargo.toml", "grammars/SHA256SUMS"]
+            .iter()
+            .map(|file| (manifest_dir.join(file), false))
+            .collect();
+    for (dir, workflow) in [("scripts", false), (".github/workflows", true)] {
+        for entry in fs::read_dir(manifest_dir.join(dir))
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            files.push((entry.path(), workflow));
+        }
+    }
 
-    let is_builtin = known_builtin_grammars.contains(&language_grammar);
+    let mut stale = Vec::new();
+    for (path, workflow) in files {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let relative = path.strip_prefix(manifest_dir).unwrap();
+        for (index, line) in text.lines().enumerate() {
+            if workflow && !line.contains("tree-sitter-quarto") && !line.contains("grammars") {
+                continue;
+            }
+            for found in commit.find_iter(line) {
+                if found.as_str() != rev {
+                    stale.push(format!(
+                        "{}:{}: {}",
+                        relative.display(),
+                        index + 1,
+                        found.as_str()
+                    ));
+                }
+            }
+        }
+    }
 
     assert!(
-        is_defined_in_manifest || is_builtin,
-        "grammar '{}' must be either defined in extension.toml or be a known Zed built-in grammar",
-        language_grammar
+        stale.is_empty(),
+        "extension.toml pins tree-sitter-quarto at {rev}, but other files pin different commits:\n{}",
+        stale.join("\n")
     );
 }
 
+#[test]
+fn snippets_file_is_valid() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let manifest_str = fs::read_to_string(manifest_dir.join("extension.toml"))
+        .expect("extension manifest readable");
+    let manifest: toml::Value = toml::from_str(&manifest_str).expect("manifest parses as TOML");
+    let snippets_path = manifest
+        .get("snippets")
+        .and_then(|value| value.as_str())
+        .expect("extension manifest must declare snippets");
+
+    let snippets_str =
+        fs::read_to_string(manifest_dir.join(snippets_path)).expect("snippets file readable");
+    let snippets: serde_json::Value =
+        serde_json::from_str(&snippets_str).expect("snippets file parses as JSON");
+
+    for (name, snippet) in snippets.as_object().expect("snippets are an object") {
+        assert!(
+            snippet.get("prefix").and_then(|p| p.as_str()).is_some(),
+            "snippet '{}' must have a prefix",
+            name
+        );
+        assert!(
+            snippet.get("body").and_then(|b| b.as_array()).is_some(),
+            "snippet '{}' must have a body",
+            name
+        );
+    }
+}
+
 #[test]
 fn wasm_extension_builds_successfully() {
     let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
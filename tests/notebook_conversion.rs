@@ -0,0 +1,247 @@
+use quarto_zed::notebook::{ipynb_to_qmd, qmd_to_ipynb};
+use serde_json::Value;
+
+/// Cell type, source text and tags: what must survive a round trip.
+fn cell_summary(notebook: &Value) -> Vec<(String, String, Vec<String>)> {
+    notebook["cells"]
+        .as_array()
+        .expect("cells array")
+        .iter()
+        .map(|cell| {
+            let source = match &cell["source"] {
+                Value::String(text) => text.clone(),
+                Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+                _ => String::new(),
+            };
+            let tags = cell["metadata"]["tags"]
+                .as_array()
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|t| t.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            (
+                cell["cell_type"].as_str().unwrap_or_default().to_string(),
+                source,
+                tags,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn notebook_converts_to_qmd() {
+    let notebook = include_str!("fixtures/notebooks/penguins.ipynb");
+    let qmd = ipynb_to_qmd(notebook).expect("notebook converts");
+
+    eprintln!("\n=== CONVERTED ===\n{}", qmd);
+
+    assert_eq!(
+        qmd,
+        r#"---
+title: "Palmer Penguins"
+format: html
+jupyter: python3
+---
+
+# Penguins
+
+We look at **bill length** by species.
+
+```{python}
+#| tags: [setup]
+import pandas as pd
+import seaborn as sns
+```
+
+```{python}
+#| label: fig-bills
+#| fig-cap: "Bill length by species"
+penguins = sns.load_dataset("penguins")
+sns.boxplot(data=penguins, x="species", y="bill_length_mm")
+```
+
+See @fig-bills for the distribution.
+"#
+    );
+}
+
+#[test]
+fn kernel_language_selects_cell_engine() {
+    let notebook = include_str!("fixtures/notebooks/mtcars-r.ipynb");
+    let qmd = ipynb_to_qmd(notebook).expect("notebook converts");
+
+    assert!(qmd.starts_with("---\njupyter: ir\n---\n"));
+    assert!(qmd.contains("```{r}\n#| tags: [parameters, hide-input]\ncyl <- 4\n```"));
+    assert!(qmd.contains("```{r}\nmodel <- lm("));
+    assert!(!qmd.contains("{python}"));
+}
+
+#[test]
+fn cell_metadata_becomes_options() {
+    let notebook = r#"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {"collapsed": true, "label": "fig-scatter", "fig-cap": "A: B", "echo": false},
+   "outputs": [],
+   "source": "plot(x, y)"
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3", "language": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"#;
+
+    let qmd = ipynb_to_qmd(notebook).expect("notebook converts");
+    assert!(qmd.contains("#| label: fig-scatter\n"));
+    assert!(qmd.contains("#| fig-cap: \"A: B\"\n"));
+    assert!(qmd.contains("#| echo: false\n"));
+    assert!(
+        !qmd.contains("collapsed"),
+        "front-end metadata is not a Quarto option"
+    );
+}
+
+#[test]
+fn cells_containing_fences_get_longer_fences() {
+    let notebook = r#"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": ["doc = \"\"\"\n", "```python\n", "print(1)\n", "```\n", "\"\"\"\n"]
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3", "language": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"#;
+
+    let qmd = ipynb_to_qmd(notebook).expect("notebook converts");
+    assert!(
+        qmd.contains("````{python}\ndoc = \"\"\"\n```python\n"),
+        "{}",
+        qmd
+    );
+    assert!(qmd.ends_with("\"\"\"\n````\n"), "{}", qmd);
+
+    let converted: Value = serde_json::from_str(&qmd_to_ipynb(&qmd)).unwrap();
+    let cells = converted["cells"].as_array().expect("cells");
+    assert_eq!(cells.len(), 2, "front matter and one code cell");
+    assert_eq!(cells[1]["source"].as_array().map(Vec::len), Some(5));
+}
+
+#[test]
+fn cell_options_round_trip_through_metadata() {
+    let notebook = r#"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {"label": "fig-scatter", "fig-width": 7, "echo": false},
+   "outputs": [],
+   "source": "plot(x, y)"
+  }
+ ],
+ "metadata": {"kernelspec": {"name": "python3", "language": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"#;
+
+    let qmd = ipynb_to_qmd(notebook).expect("notebook converts");
+    let converted: Value = serde_json::from_str(&qmd_to_ipynb(&qmd)).unwrap();
+    let code = &converted["cells"][1];
+    assert_eq!(code["metadata"]["label"], "fig-scatter");
+    assert_eq!(code["metadata"]["fig-width"], 7);
+    assert_eq!(code["metadata"]["echo"], false);
+
+    // The options are in the source too; converting back writes them once
+    assert_eq!(ipynb_to_qmd(&converted.to_string()).unwrap(), qmd);
+}
+
+#[test]
+fn unsupported_notebooks_are_rejected() {
+    assert!(ipynb_to_qmd("not json").is_err());
+    assert!(ipynb_to_qmd(r#"{"nbformat": 3, "worksheets": []}"#).is_err());
+}
+
+#[test]
+fn qmd_converts_to_valid_nbformat() {
+    let qmd = include_str!("fixtures/notebooks/analysis.qmd");
+    let notebook: Value = serde_json::from_str(&qmd_to_ipynb(qmd)).expect("valid JSON");
+
+    assert_eq!(notebook["nbformat"], 4);
+    assert_eq!(notebook["nbformat_minor"], 5);
+    assert_eq!(notebook["metadata"]["kernelspec"]["name"], "python3");
+    assert_eq!(notebook["metadata"]["kernelspec"]["language"], "python");
+
+    let cells = notebook["cells"].as_array().expect("cells");
+    let types: Vec<&str> = cells
+        .iter()
+        .map(|c| c["cell_type"].as_str().unwrap())
+        .collect();
+    assert_eq!(types, ["raw", "markdown", "code", "code", "markdown"]);
+
+    for cell in cells {
+        assert!(cell["id"].is_string(), "nbformat 4.5 cells need ids");
+        assert!(cell["metadata"].is_object());
+        assert!(cell["source"].is_array());
+        if cell["cell_type"] == "code" {
+            assert!(cell["outputs"].as_array().is_some_and(|o| o.is_empty()));
+            assert!(cell["execution_count"].is_null());
+        }
+    }
+
+    assert_eq!(cells[2]["metadata"]["tags"][0], "setup");
+    assert_eq!(cells[2]["metadata"]["tags"][1], "hide-cell");
+    assert_eq!(cells[2]["source"][0], "import numpy as np");
+    assert_eq!(cells[3]["source"][0], "#| label: tbl-summary\n");
+
+    let last = cell_summary(&notebook).pop().expect("markdown cell").1;
+    assert!(
+        last.contains("```python\nprint(\"not executed\")\n```"),
+        "plain code blocks stay in markdown"
+    );
+}
+
+#[test]
+fn qmd_round_trip_is_lossless() {
+    let qmd = include_str!("fixtures/notebooks/analysis.qmd");
+    let round_tripped = ipynb_to_qmd(&qmd_to_ipynb(qmd)).expect("notebook converts");
+    assert_eq!(round_tripped, qmd);
+}
+
+#[test]
+fn notebook_round_trip_preserves_cells() {
+    for fixture in [
+        include_str!("fixtures/notebooks/penguins.ipynb"),
+        include_str!("fixtures/notebooks/mtcars-r.ipynb"),
+    ] {
+        let original: Value = serde_json::from_str(fixture).unwrap();
+        let qmd = ipynb_to_qmd(fixture).expect("notebook converts");
+        let converted: Value = serde_json::from_str(&qmd_to_ipynb(&qmd)).unwrap();
+
+        let mut expected = cell_summary(&original);
+        let actual = cell_summary(&converted);
+
+        // Notebooks without front matter gain one carrying the kernel name.
+        if expected[0].0 != "raw" {
+            assert_eq!(actual[0].0, "raw");
+            expected.insert(0, actual[0].clone());
+        } else {
+            expected[0].1 = actual[0].1.clone();
+            assert!(expected[0].1.contains("jupyter: "));
+        }
+
+        assert_eq!(actual, expected);
+        assert_eq!(
+            converted["metadata"]["kernelspec"]["name"],
+            original["metadata"]["kernelspec"]["name"]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+mod support;
+
+use std::fs;
+use std::path::Path;
+
+use quarto_zed::outline::{self, EntryKind};
+use support::language;
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+const REPORT: &str = include_str!("fixtures/outline-report.qmd");
+
+#[test]
+fn report_outline_is_rendered() {
+    let (text, _) = outline::render(&outline::outline(REPORT));
+
+    eprintln!("\n=== OUTLINE ===\n{}", text);
+
+    assert_eq!(
+        text,
+        "# Introduction {#sec-intro} (lines 6-14)
+  callout-note: Data source (lines 10-13)
+# Methods (lines 15-60)
+  ## Model (lines 17-34)
+    cell {r} #fig-mpg: Fuel economy by weight (lines 19-23)
+    cell {r} #tbl-summary: Summary statistics (lines 25-29)
+    cell {python} (lines 31-33)
+  ## Results (lines 35-60)
+    @fig-engine: Engine layout (line 38)
+    @tbl-cyl: Cars by cylinder count (line 44)
+    @fig-panels: Two panels (lines 46-52)
+    callout-warning: Small sample (lines 54-56)
+"
+    );
+}
+
+#[test]
+fn each_entry_is_a_section_labelled_with_its_lines() {
+    let entries = outline::outline(REPORT);
+    let (text, sections) = outline::render(&entries);
+
+    assert_eq!(sections.len(), entries.len());
+    for (range, label) in &sections {
+        let line = &text[range.clone()];
+        assert!(line.ends_with('\n'), "sections cover whole lines");
+        assert!(
+            line.trim().ends_with(label.as_str()),
+            "section label {label:?} should describe {line:?}"
+        );
+        assert!(label.contains("(line"), "label carries the line range");
+    }
+    assert_eq!(sections.last().unwrap().0.end, text.len());
+}
+
+#[test]
+fn callout_titles_and_code_are_not_headings() {
+    let headings: Vec<String> = outline::outline(REPORT)
+        .into_iter()
+        .filter(|entry| matches!(entry.kind, EntryKind::Heading { .. }))
+        .filter_map(|entry| entry.title)
+        .collect();
+    assert_eq!(headings, ["Introduction", "Methods", "Model", "Results"]);
+}
+
+#[test]
+fn sections_end_at_next_heading_of_same_level() {
+    let source = "# A\ntext\n## B\n### C\n## D\n# E\n";
+    let spans: Vec<(usize, usize, usize)> = outline::outline(source)
+        .iter()
+        .map(|entry| (entry.start_line, entry.end_line, entry.depth))
+        .collect();
+    assert_eq!(
+        spans,
+        [(0, 4, 0), (2, 3, 1), (3, 3, 2), (4, 4, 1), (5, 5, 0)]
+    );
+}
+
+#[test]
+fn empty_document_has_no_entries() {
+    assert!(outline::outline("---\ntitle: x\n---\n\nJust prose.\n").is_empty());
+}
+
+#[test]
+fn unclosed_trailing_div_does_not_panic() {
+    // Normal while typing: the float's fence is the last line.
+    let entries = outline::outline("# Title\n\n::: {#fig-map}");
+    let float = entries
+        .iter()
+        .find(|entry| matches!(&entry.kind, EntryKind::Float { label } if label == "fig-map"))
+        .expect("the open div is still listed");
+    assert_eq!((float.start_line, float.end_line), (2, 2));
+    assert_eq!(float.title, None);
+}
+
+/// The text outline must agree with the headings Zed's outline panel shows,
+/// which come from `outline.scm`.
+#[test]
+fn headings_match_outline_query() {
+    let query_source = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto/outline.scm"),
+    )
+    .expect("outline.scm readable");
+    let query = Query::new(&language(), &query_source).expect("outline query compiles");
+    let item = query.capture_index_for_name("item").expect("@item capture");
+
+    let mut parser = Parser::new();
+    parser.set_language(&language()).unwrap();
+    let tree = parser.parse(REPORT.as_bytes(), None).unwrap();
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), REPORT.as_bytes());
+    let mut query_lines = Vec::new();
+    while let Some(m) = matches.next() {
+        for capture in m.captures.iter().filter(|c| c.index == item) {
+            query_lines.push(capture.node.start_position().row);
+        }
+    }
+    query_lines.sort_unstable();
+    query_lines.dedup();
+
+    let mut outline_lines: Vec<usize> = outline::outline(REPORT)
+        .iter()
+        .filter(|entry| matches!(entry.kind, EntryKind::Heading { .. }))
+        .map(|entry| entry.start_line)
+        .collect();
+    // The grammar also sees the heading that titles the callout.
+    outline_lines.push(10);
+    outline_lines.sort_unstable();
+
+    assert_eq!(outline_lines, query_lines);
+}
+
+/// Cells and fenced divs start where the grammar's `executable_code_cell` and
+/// `fenced_div` nodes start, like headings above.
+#[test]
+fn cells_and_divs_match_the_grammar() {
+    let mut parser = Parser::new();
+    parser.set_language(&language()).unwrap();
+    let tree = parser.parse(REPORT.as_bytes(), None).unwrap();
+    let node_rows = |kind: &str| {
+        let query = Query::new(&language(), &format!("({}) @node", kind)).unwrap();
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, tree.root_node(), REPORT.as_bytes());
+        let mut rows = Vec::new();
+        while let Some(m) = matches.next() {
+            rows.extend(m.captures.iter().map(|c| c.node.start_position().row));
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    };
+
+    let lines: Vec<&str> = REPORT.lines().collect();
+    let entries = outline::outline(REPORT);
+    let entry_rows = |keep: &dyn Fn(&outline::OutlineEntry) -> bool| {
+        let mut rows: Vec<usize> = entries
+            .iter()
+            .filter(|entry| keep(entry))
+            .map(|entry| entry.start_line)
+            .collect();
+        rows.sort_unstable();
+        rows
+    };
+
+    assert_eq!(
+        entry_rows(&|entry| matches!(entry.kind, EntryKind::Cell { .. })),
+        node_rows("executable_code_cell")
+    );
+    // Every div in the report is a callout or a figure div.
+    assert_eq!(
+        entry_rows(&|entry| lines[entry.start_line].starts_with(":::")),
+        node_rows("fenced_div")
+    );
+}
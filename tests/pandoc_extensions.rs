@@ -1,37 +1,11 @@
-/// Test Pandoc-specific markdown extensions
-/// These features are part of Pandoc Markdown but not standard CommonMark
-use tree_sitter::{Language, Parser};
-use tree_sitter_highlight::{HighlightConfiguration, Highlighter};
-
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+mod support;
 
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
+use support::{highlight_configuration, language};
 
-fn highlight_configuration() -> HighlightConfiguration {
-    let highlight_query = include_str!("../grammars/quarto/queries/highlights.scm");
-    let injection_query = include_str!("../grammars/quarto/queries/injections.scm");
-    let locals_query = "";
-
-    let mut config = HighlightConfiguration::new(
-        language(),
-        "quarto",
-        highlight_query,
-        injection_query,
-        locals_query,
-    )
-    .expect("valid highlight configuration");
-
-    let scope_names: Vec<String> = config.names().iter().map(|s| s.to_string()).collect();
-    let scope_names_refs: Vec<&str> = scope_names.iter().map(|s| s.as_str()).collect();
-    config.configure(&scope_names_refs);
-
-    config
-}
+/// Test Pandoc-specific markdown extensions
+/// These features are part of Pandoc Markdown but not standard CommonMark
+use tree_sitter::Parser;
+use tree_sitter_highlight::Highlighter;
 
 #[test]
 fn strikethrough_is_highlighted() {
@@ -0,0 +1,368 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quarto_zed::lsp::{uri_to_path, Server};
+use quarto_zed::project::{self, Chapter, ExecuteDir, Freeze, Project, ProjectType};
+use serde_json::json;
+
+fn book_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/projects/book")
+}
+
+fn parse(yaml: &str) -> Project {
+    Project::parse(PathBuf::from("/project"), yaml, None).unwrap()
+}
+
+#[test]
+fn root_is_found_by_walking_up() {
+    let root = book_root();
+    assert_eq!(
+        Project::find_root(&root.join("chapters/regression.qmd")),
+        Some(root.clone())
+    );
+    assert_eq!(
+        Project::find_root(&root.join("chapters")),
+        Some(root.clone())
+    );
+    assert_eq!(Project::find_root(&root), Some(root));
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("standalone.qmd"), "# Alone\n").unwrap();
+    assert!(Project::discover(&dir.path().join("standalone.qmd"), None)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn yaml_extension_is_accepted() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("posts")).unwrap();
+    fs::write(
+        dir.path().join("_quarto.yaml"),
+        "project:\n  type: website\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("_quarto-dev.yaml"),
+        "project:\n  output-dir: _dev\n",
+    )
+    .unwrap();
+
+    let post = dir.path().join("posts/first.qmd");
+    assert_eq!(Project::find_root(&post), Some(dir.path().to_path_buf()));
+    assert_eq!(Project::profiles(dir.path()), ["dev"]);
+    let project = Project::discover(&post, Some("dev")).unwrap().unwrap();
+    assert_eq!(project.kind, ProjectType::Website);
+    assert_eq!(project.output_dir(), Some("_dev"));
+    assert_eq!(project.config_path(), dir.path().join("_quarto.yaml"));
+}
+
+#[test]
+fn book_configuration_is_typed() {
+    let project = Project::load(&book_root(), None).unwrap();
+    assert_eq!(project.kind, ProjectType::Book);
+    assert_eq!(project.execute_dir, ExecuteDir::Project);
+    assert_eq!(project.freeze, Freeze::Auto);
+    assert_eq!(project.bibliography, ["references.bib"]);
+    assert_eq!(
+        project.output_dir(),
+        Some("_book"),
+        "books default to _book"
+    );
+    assert_eq!(project.describe(), "book \"Fuel Economy in Practice\"");
+
+    let book = project.book.as_ref().unwrap();
+    assert_eq!(book.chapters[0], Chapter::File("index.qmd".to_string()));
+    assert_eq!(
+        book.chapters[1],
+        Chapter::Part {
+            part: "Data".to_string(),
+            chapters: vec![
+                "chapters/collection.qmd".to_string(),
+                "chapters/cleaning.qmd".to_string()
+            ],
+        }
+    );
+    assert_eq!(
+        book.files(),
+        [
+            "index.qmd",
+            "chapters/collection.qmd",
+            "chapters/cleaning.qmd",
+            "chapters/modelling-part.qmd",
+            "chapters/regression.qmd",
+            "chapters/glossary.qmd",
+        ],
+        "reading order includes part pages and appendices"
+    );
+}
+
+#[test]
+fn profiles_are_listed_and_merged() {
+    let root = book_root();
+    assert_eq!(Project::profiles(&root), ["dev"]);
+
+    let dev = Project::load(&root, Some("dev")).unwrap();
+    assert_eq!(dev.profile.as_deref(), Some("dev"));
+    assert_eq!(dev.output_dir(), Some("_preview"), "scalars are replaced");
+    assert_eq!(dev.freeze, Freeze::Off);
+    assert_eq!(
+        dev.kind,
+        ProjectType::Book,
+        "unset keys keep the base value"
+    );
+    let book = dev.book.as_ref().unwrap();
+    assert_eq!(
+        book.chapters.last(),
+        Some(&Chapter::File("chapters/drafts.qmd".to_string())),
+        "lists are concatenated"
+    );
+    assert_eq!(book.chapters.len(), 4);
+    assert_eq!(
+        dev.describe(),
+        "book \"Fuel Economy in Practice\", profile: dev"
+    );
+
+    let error = Project::load(&root, Some("prod")).unwrap_err();
+    assert!(error.contains("_quarto-prod.yml"), "{error}");
+}
+
+#[test]
+fn website_navbar_and_defaults() {
+    let project = parse(
+        "project:
+  type: website
+website:
+  title: \"Lab notes\"
+  navbar:
+    left:
+      - index.qmd
+      - text: Analyses
+        menu:
+          - analyses/one.qmd
+          - href: analyses/two.qmd
+            text: Second
+    right:
+      - icon: github
+        href: https://github.com/example
+",
+    );
+    assert_eq!(project.kind, ProjectType::Website);
+    assert_eq!(project.output_dir(), Some("_site"));
+    assert_eq!(project.freeze, Freeze::Off);
+    assert_eq!(project.execute_dir, ExecuteDir::File);
+
+    let navbar = project.website.unwrap().navbar.unwrap();
+    assert_eq!(navbar.left[0].href.as_deref(), Some("index.qmd"));
+    assert_eq!(navbar.left[1].text.as_deref(), Some("Analyses"));
+    assert_eq!(navbar.left[1].menu[1].text.as_deref(), Some("Second"));
+    assert_eq!(
+        navbar.right[0].href.as_deref(),
+        Some("https://github.com/example")
+    );
+
+    let plain = parse("bibliography: [a.bib, b.json]\nexecute:\n  freeze: true\n");
+    assert_eq!(plain.kind, ProjectType::Default);
+    assert_eq!(plain.output_dir(), None);
+    assert_eq!(plain.bibliography, ["a.bib", "b.json"]);
+    assert_eq!(plain.freeze, Freeze::On);
+    assert_eq!(
+        parse("").kind,
+        ProjectType::Default,
+        "an empty file is a project"
+    );
+    assert_eq!(
+        parse("project:\n  type: confluence\n").kind,
+        ProjectType::Other("confluence".to_string())
+    );
+}
+
+#[test]
+fn invalid_configuration_is_an_error() {
+    let error = Project::parse(PathBuf::from("/p"), "project: [unclosed", None).unwrap_err();
+    assert!(error.starts_with("Invalid _quarto.yml"), "{error}");
+    let error = Project::parse(PathBuf::from("/p"), "project:\n  render: 3\n", None).unwrap_err();
+    assert!(error.starts_with("Invalid _quarto.yml"), "{error}");
+}
+
+#[test]
+fn render_lists_select_documents() {
+    let project = parse(
+        "project:
+  render:
+    - \"*.qmd\"
+    - \"reports/**/*.qmd\"
+    - \"!draft-*.qmd\"
+",
+    );
+    assert!(project.renders("index.qmd"));
+    assert!(
+        project.renders("notes/index.qmd"),
+        "patterns without / match names"
+    );
+    assert!(project.renders("reports/2024/q1.qmd"));
+    assert!(
+        !project.renders("draft-intro.qmd"),
+        "negated by a later pattern"
+    );
+    assert!(!project.renders("reports/q1.Rmd"));
+    assert!(
+        !project.renders("_include.qmd"),
+        "underscore files are never rendered"
+    );
+
+    let all = parse("project:\n  type: website\n");
+    assert!(all.renders("about.md"));
+    assert!(!all.renders("data.csv"));
+}
+
+#[test]
+fn book_documents_are_its_chapters() {
+    let project = Project::load(&book_root(), None).unwrap();
+    let paths: Vec<String> = project
+        .documents()
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "chapters/cleaning.qmd",
+            "chapters/collection.qmd",
+            "chapters/glossary.qmd",
+            "chapters/modelling-part.qmd",
+            "chapters/regression.qmd",
+            "index.qmd",
+        ],
+        "scratch.qmd is not a chapter and _book/ is output"
+    );
+    assert_eq!(
+        project.bibliography_paths(),
+        [book_root().join("references.bib")]
+    );
+}
+
+#[test]
+fn files_skip_output_and_hidden_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    for path in [
+        "index.qmd",
+        "chapters/methods.qmd",
+        "_site/index.qmd",
+        ".quarto/cache.qmd",
+        "notes.txt",
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# Title\n").unwrap();
+    }
+
+    let files = project::files(root, &project::SOURCE_EXTENSIONS);
+    let paths: Vec<&str> = files.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(paths, ["chapters/methods.qmd", "index.qmd"]);
+    assert!(project::files(Path::new("/nonexistent"), &["qmd"]).is_empty());
+}
+
+#[test]
+fn language_server_reports_project_problems() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::write(
+        root.join("_quarto.yml"),
+        "project:\n  render:\n    - index.qmd\n",
+    )
+    .unwrap();
+    fs::create_dir(root.join("broken")).unwrap();
+    fs::write(root.join("broken/_quarto.yml"), "project: [").unwrap();
+
+    let mut server = Server::new();
+    let mut open = |relative: &str| {
+        let uri = format!("file://{}", root.join(relative).display()).replace(' ', "%20");
+        let replies = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {
+                "textDocument": { "uri": uri, "languageId": "quarto", "version": 1, "text": "# Doc\n" }
+            }
+        }));
+        replies[0]["params"]["diagnostics"].clone()
+    };
+
+    assert_eq!(open("index.qmd"), json!([]));
+
+    let skipped = open("scratch notes.qmd");
+    assert_eq!(skipped[0]["code"], "not-rendered");
+    assert_eq!(skipped[0]["severity"], 3);
+
+    let broken = open("broken/index.qmd");
+    assert_eq!(broken[0]["code"], "invalid-project");
+    assert!(broken[0]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Invalid _quarto.yml"));
+}
+
+#[test]
+fn file_uris_become_paths() {
+    assert_eq!(
+        uri_to_path("file:///home/me/My%20Book/index.qmd"),
+        Some(PathBuf::from("/home/me/My Book/index.qmd"))
+    );
+    assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn profile_flag_is_split_from_arguments() {
+    assert_eq!(
+        project::take_profile_arg(&strings(&["index.qmd", "--profile", "dev"])).unwrap(),
+        (Some("dev".to_string()), strings(&["index.qmd"]))
+    );
+    assert_eq!(
+        project::take_profile_arg(&strings(&["--profile=prod"])).unwrap(),
+        (Some("prod".to_string()), Vec::new())
+    );
+    assert_eq!(
+        project::take_profile_arg(&strings(&["index.qmd"])).unwrap(),
+        (None, strings(&["index.qmd"]))
+    );
+
+    let error = project::take_profile_arg(&strings(&["--profile"])).unwrap_err();
+    assert!(error.contains("needs a profile name"), "{error}");
+    let error =
+        project::take_profile_arg(&strings(&["--profile", "a", "--profile=b"])).unwrap_err();
+    assert!(error.contains("only be given once"), "{error}");
+}
+
+#[test]
+fn profiles_complete_after_the_flag() {
+    let fixtures = book_root().parent().unwrap().to_path_buf();
+    let complete = |args: &[&str]| project::profile_completions(&strings(args), &book_root());
+
+    assert_eq!(complete(&["--pr"]), ["--profile"]);
+    assert_eq!(complete(&["--profile", ""]), ["dev"]);
+    assert_eq!(complete(&["--profile", "d"]), ["dev"]);
+    assert!(complete(&["--profile", "x"]).is_empty());
+    assert_eq!(complete(&["--profile="]), ["--profile=dev"]);
+    assert!(
+        complete(&["index"]).is_empty(),
+        "documents are not completed"
+    );
+
+    // The document argument decides which project's profiles are offered
+    assert_eq!(
+        project::profile_completions(
+            &strings(&["book/chapters/cleaning.qmd", "--profile", ""]),
+            &fixtures
+        ),
+        ["dev"]
+    );
+    assert!(
+        project::profile_completions(&strings(&["--profile", ""]), &fixtures).is_empty(),
+        "the fixtures directory is not a project"
+    );
+}
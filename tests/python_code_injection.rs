@@ -1,16 +1,25 @@
+mod support;
+
 use std::fs;
 use std::path::Path;
 
-use tree_sitter::{Language, Parser};
-
-// Link to the compiled pandoc-markdown grammar
-#[link(name = "tree-sitter-pandoc-markdown", kind = "static")]
-extern "C" {
-    fn tree_sitter_pandoc_markdown() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_pandoc_markdown() }
+use support::language;
+use tree_sitter::Parser;
+
+/// `(language, content)` of every executable code cell under `node`.
+fn code_cells(node: tree_sitter::Node, source: &str, cells: &mut Vec<(String, String)>) {
+    if node.kind() == "executable_code_cell" {
+        let field = |name| {
+            node.child_by_field_name(name)
+                .map(|child| source[child.byte_range()].to_string())
+                .unwrap_or_default()
+        };
+        cells.push((field("language"), field("content")));
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        code_cells(child, source, cells);
+    }
 }
 
 #[test]
@@ -33,76 +42,22 @@ fn python_code_blocks_are_parsed() {
     eprintln!("\n=== PYTHON CODE PARSE TREE (first 2000 chars) ===");
     eprintln!("{}", &sexp.chars().take(2000).collect::<String>());
 
-    // Verify Python code blocks are parsed as fenced_code_block
+    // Verify Python code blocks are parsed as executable_code_cell
     assert!(
-        sexp.contains("(fenced_code_block"),
-        "Document should contain fenced code blocks"
-    );
-    println!("✓ Python code blocks are parsed as fenced_code_block nodes");
-
-    // Verify info_string contains 'python' (case insensitive)
-    let mut cursor = tree.walk();
-    let mut python_code_blocks = 0;
-    let mut python_code_contents = Vec::new();
-
-    fn find_python_blocks(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        count: &mut usize,
-        contents: &mut Vec<String>,
-    ) {
-        let node = cursor.node();
-
-        if node.kind() == "fenced_code_block" {
-            // Check if this is a Python code block
-            if cursor.goto_first_child() {
-                let mut is_python_block = false;
-                let mut code_content = String::new();
-
-                loop {
-                    let child = cursor.node();
-
-                    if child.kind() == "info_string" {
-                        let info = &source[child.byte_range()];
-                        // Match {python} or {python ...} or {Python}
-                        if info.to_lowercase().contains("python") {
-                            is_python_block = true;
-                        }
-                    } else if child.kind() == "code_fence_content" {
-                        code_content = source[child.byte_range()].to_string();
-                    }
-
-                    if !cursor.goto_next_sibling() {
-                        break;
-                    }
-                }
-
-                if is_python_block {
-                    *count += 1;
-                    contents.push(code_content);
-                }
-
-                cursor.goto_parent();
-            }
-        }
-
-        if cursor.goto_first_child() {
-            loop {
-                find_python_blocks(cursor, source, count, contents);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
-    }
-
-    find_python_blocks(
-        &mut cursor,
-        &source,
-        &mut python_code_blocks,
-        &mut python_code_contents,
+        sexp.contains("(executable_code_cell"),
+        "Document should contain executable code cells"
     );
+    println!("✓ Python code blocks are parsed as executable_code_cell nodes");
+
+    // Verify the language name is 'python' (case insensitive)
+    let mut cells = Vec::new();
+    code_cells(root, &source, &mut cells);
+    let python_code_contents: Vec<String> = cells
+        .into_iter()
+        .filter(|(language, _)| language.eq_ignore_ascii_case("python"))
+        .map(|(_, content)| content)
+        .collect();
+    let python_code_blocks = python_code_contents.len();
 
     eprintln!("\n=== PYTHON CODE BLOCKS FOUND: {} ===", python_code_blocks);
 
@@ -194,18 +149,14 @@ fn python_injection_query_matches() {
 
         eprintln!("Parse tree: {}", sexp);
 
-        // Should have a fenced_code_block
-        assert!(
-            sexp.contains("(fenced_code_block"),
-            "Test case {} should parse as fenced_code_block",
-            i + 1
-        );
-
-        // Should have an info_string with 'python' or 'Python'
+        // Should have an executable_code_cell whose language is 'python' or 'Python'
+        let mut cells = Vec::new();
+        code_cells(root, source, &mut cells);
         assert!(
-            sexp.contains("(info_string")
-                && (source.contains("{python") || source.contains("{Python")),
-            "Test case {} should have Python info_string",
+            cells
+                .iter()
+                .any(|(language, _)| language.eq_ignore_ascii_case("python")),
+            "Test case {} should parse as a Python executable_code_cell",
             i + 1
         );
 
@@ -259,51 +210,16 @@ print("Python block 2")
     eprintln!("{}", sexp);
 
     // Count code blocks
-    let mut cursor = tree.walk();
-    let mut python_blocks = 0;
-    let mut r_blocks = 0;
-
-    fn count_blocks(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        python_count: &mut usize,
-        r_count: &mut usize,
-    ) {
-        let node = cursor.node();
-
-        if node.kind() == "fenced_code_block" && cursor.goto_first_child() {
-            loop {
-                let child = cursor.node();
-                if child.kind() == "info_string" {
-                    let info = &source[child.byte_range()];
-                    if info.to_lowercase().contains("python") {
-                        *python_count += 1;
-                    } else if info.to_lowercase().contains("r")
-                        && !info.to_lowercase().contains("python")
-                    {
-                        *r_count += 1;
-                    }
-                    break;
-                }
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
-
-        if cursor.goto_first_child() {
-            loop {
-                count_blocks(cursor, source, python_count, r_count);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
-    }
-
-    count_blocks(&mut cursor, source, &mut python_blocks, &mut r_blocks);
+    let mut cells = Vec::new();
+    code_cells(root, source, &mut cells);
+    let count = |name: &str| {
+        cells
+            .iter()
+            .filter(|(language, _)| language.eq_ignore_ascii_case(name))
+            .count()
+    };
+    let python_blocks = count("python");
+    let r_blocks = count("r");
 
     eprintln!("\n=== BLOCK COUNTS ===");
     eprintln!("Python blocks: {}", python_blocks);
@@ -27,8 +27,9 @@ mod tests {
         assert!(cli_not_found.contains("https://"));
         assert!(cli_not_found.contains("Install"));
 
-        let wrong_extension = "Preview only works with .qmd files";
+        let wrong_extension = "Preview only works with .qmd and .Rmd files";
         assert!(wrong_extension.contains(".qmd"));
+        assert!(wrong_extension.contains(".Rmd"));
 
         let no_file = "No file to preview. Please specify a file path or open a Quarto document.";
         assert!(no_file.contains("specify"));
@@ -31,16 +31,14 @@
 /// `extension.toml`. Since we use tree-sitter-quarto, we must use its queries.
 ///
 /// This test validates that highlights.scm can be parsed with the grammar.
-use tree_sitter::{Language, Query};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use support::language;
+use tree_sitter::Query;
 
 #[test]
 fn highlights_query_compatible_with_grammar() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let highlights_path =
         std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto/highlights.scm");
 
@@ -67,8 +65,7 @@ fn highlights_query_compatible_with_grammar() {
                  2. Copy from: https://github.com/ck37/tree-sitter-quarto/tree/main/queries\n\
                  3. Match the commit specified in extension.toml\n\
                  \n\
-                 Current grammar commit in extension.toml:\n\
-                 rev = \"9f7e5d2ef6af2af9dd47b259d9d50fa5d0e18638\"\n\
+                 The grammar commit is the `rev` under [grammars.quarto] in extension.toml.\n\
                  \n\
                  Query files must use tree-sitter-quarto node names, not pandoc-markdown.",
                 e
@@ -79,7 +76,7 @@ fn highlights_query_compatible_with_grammar() {
 
 #[test]
 fn injections_query_compatible_with_grammar() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let injections_path =
         std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto/injections.scm");
 
@@ -98,7 +95,7 @@ fn injections_query_compatible_with_grammar() {
 
 #[test]
 fn all_scm_files_compatible_with_grammar() {
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let quarto_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto");
 
     let query_files = [
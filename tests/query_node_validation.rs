@@ -1,24 +1,15 @@
 /// Test to validate that all node types used in highlight queries
 /// actually exist in the grammar. This prevents silent highlighting failures
 /// when queries reference non-existent node types.
-use std::path::Path;
-use tree_sitter::{Language, Query};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use support::{language, parse, HIGHLIGHTS};
+use tree_sitter::Query;
 
 #[test]
 fn all_query_node_types_exist_in_grammar() {
-    let language = unsafe { tree_sitter_quarto() };
-    let highlights_query = std::fs::read_to_string(
-        Path::new(env!("CARGO_MANIFEST_DIR")).join("grammars/quarto/queries/highlights.scm"),
-    )
-    .expect("Failed to read highlights.scm");
-
     // Try to compile the query - this will fail if node types don't exist
-    let result = Query::new(&language, &highlights_query);
+    let result = Query::new(&language(), HIGHLIGHTS);
 
     match result {
         Ok(_) => {
@@ -35,7 +26,7 @@ fn all_query_node_types_exist_in_grammar() {
                 3. The grammar version matches what extension.toml expects\n\n\
                 To debug:\n\
                 - Run: tree-sitter parse <test-file> to see actual AST nodes\n\
-                - Run: tree-sitter query grammars/quarto/queries/highlights.scm to validate query",
+                - Run: tree-sitter query languages/quarto/highlights.scm to validate query",
                 e
             );
         }
@@ -44,8 +35,6 @@ fn all_query_node_types_exist_in_grammar() {
 
 #[test]
 fn emphasis_and_strong_nodes_exist() {
-    let language = unsafe { tree_sitter_quarto() };
-
     // Test that basic inline formatting nodes exist
     let test_query = r#"
         (emphasis) @test
@@ -54,7 +43,7 @@ fn emphasis_and_strong_nodes_exist() {
         (text) @test
     "#;
 
-    let result = Query::new(&language, test_query);
+    let result = Query::new(&language(), test_query);
 
     assert!(
         result.is_ok(),
@@ -64,119 +53,46 @@ fn emphasis_and_strong_nodes_exist() {
 
 #[test]
 fn grammar_parses_basic_inline_content() {
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&language)
-        .expect("Failed to set language");
-
     // Test emphasis
-    let tree = parser
-        .parse("*italic text*", None)
-        .expect("Failed to parse emphasis");
-    let root = tree.root_node();
-
-    // Should have an emphasis node somewhere in the tree
-    let has_emphasis = check_for_node_type(&root, "emphasis");
+    let parsed = parse("*italic text*");
     assert!(
-        has_emphasis,
+        parsed.has("emphasis"),
         "Grammar failed to parse emphasis (*italic*). Tree:\n{}",
-        root.to_sexp()
+        parsed.sexp()
     );
 
     // Test strong emphasis
-    let tree = parser
-        .parse("**bold text**", None)
-        .expect("Failed to parse strong");
-    let root = tree.root_node();
-
-    let has_strong = check_for_node_type(&root, "strong_emphasis");
+    let parsed = parse("**bold text**");
     assert!(
-        has_strong,
+        parsed.has("strong_emphasis"),
         "Grammar failed to parse strong emphasis (**bold**). Tree:\n{}",
-        root.to_sexp()
+        parsed.sexp()
     );
 
     // Test heading
-    let tree = parser
-        .parse("# Heading\n", None)
-        .expect("Failed to parse heading");
-    let root = tree.root_node();
-
-    let has_heading = check_for_node_type(&root, "atx_heading");
+    let parsed = parse("# Heading\n");
     assert!(
-        has_heading,
+        parsed.has("atx_heading"),
         "Grammar failed to parse ATX heading. Tree:\n{}",
-        root.to_sexp()
+        parsed.sexp()
     );
 
     // Verify text nodes exist inside emphasis
-    let tree = parser
-        .parse("*italic text*", None)
-        .expect("Failed to parse");
-    let root = tree.root_node();
-
-    let has_text_in_emphasis = check_for_nested_node(&root, "emphasis", "text");
+    let parsed = parse("*italic text*");
     assert!(
-        has_text_in_emphasis,
+        parsed.has_within("emphasis", "text"),
         "Grammar doesn't create text nodes inside emphasis. Tree:\n{}",
-        root.to_sexp()
+        parsed.sexp()
     );
 }
 
-// Helper function to recursively check for a node type
-fn check_for_node_type(node: &tree_sitter::Node, node_type: &str) -> bool {
-    if node.kind() == node_type {
-        return true;
-    }
-
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if check_for_node_type(&child, node_type) {
-            return true;
-        }
-    }
-
-    false
-}
-
-// Helper function to check if a node of type parent_type contains a child of type child_type
-fn check_for_nested_node(node: &tree_sitter::Node, parent_type: &str, child_type: &str) -> bool {
-    if node.kind() == parent_type {
-        // Found parent, check children
-        let mut cursor = node.walk();
-        for child in node.children(&mut cursor) {
-            if child.kind() == child_type {
-                return true;
-            }
-        }
-    }
-
-    // Recursively search children
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if check_for_nested_node(&child, parent_type, child_type) {
-            return true;
-        }
-    }
-
-    false
-}
-
 #[test]
 fn document_unsupported_pandoc_features() {
     // This test documents Pandoc features that are NOT yet supported
     // by tree-sitter-quarto. If these start passing, we can add queries for them!
 
-    let language = unsafe { tree_sitter_quarto() };
-    let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&language)
-        .expect("Failed to set language");
-
     // Test strikethrough (NOT supported yet)
-    let tree = parser.parse("~~strikethrough~~", None).unwrap();
-    let has_strikethrough = check_for_node_type(&tree.root_node(), "strikethrough");
+    let has_strikethrough = parse("~~strikethrough~~").has("strikethrough");
 
     if has_strikethrough {
         println!("✨ Grammar now supports strikethrough! Add highlight queries for it.");
@@ -185,8 +101,7 @@ fn document_unsupported_pandoc_features() {
     }
 
     // Test highlight (NOT supported yet)
-    let tree = parser.parse("==highlight==", None).unwrap();
-    let has_highlight = check_for_node_type(&tree.root_node(), "highlight");
+    let has_highlight = parse("==highlight==").has("highlight");
 
     if has_highlight {
         println!("✨ Grammar now supports highlight! Add highlight queries for it.");
@@ -195,8 +110,7 @@ fn document_unsupported_pandoc_features() {
     }
 
     // Test subscript (NOT supported yet)
-    let tree = parser.parse("H~2~O", None).unwrap();
-    let has_subscript = check_for_node_type(&tree.root_node(), "subscript");
+    let has_subscript = parse("H~2~O").has("subscript");
 
     if has_subscript {
         println!("✨ Grammar now supports subscript! Add highlight queries for it.");
@@ -205,8 +119,7 @@ fn document_unsupported_pandoc_features() {
     }
 
     // Test superscript (NOT supported yet)
-    let tree = parser.parse("x^2^", None).unwrap();
-    let has_superscript = check_for_node_type(&tree.root_node(), "superscript");
+    let has_superscript = parse("x^2^").has("superscript");
 
     if has_superscript {
         println!("✨ Grammar now supports superscript! Add highlight queries for it.");
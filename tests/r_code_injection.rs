@@ -1,16 +1,25 @@
+mod support;
+
 use std::fs;
 use std::path::Path;
 
-use tree_sitter::{Language, Parser};
-
-// Link to the compiled pandoc-markdown grammar
-#[link(name = "tree-sitter-pandoc-markdown", kind = "static")]
-extern "C" {
-    fn tree_sitter_pandoc_markdown() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_pandoc_markdown() }
+use support::language;
+use tree_sitter::Parser;
+
+/// `(language, content)` of every executable code cell under `node`.
+fn code_cells(node: tree_sitter::Node, source: &str, cells: &mut Vec<(String, String)>) {
+    if node.kind() == "executable_code_cell" {
+        let field = |name| {
+            node.child_by_field_name(name)
+                .map(|child| source[child.byte_range()].to_string())
+                .unwrap_or_default()
+        };
+        cells.push((field("language"), field("content")));
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        code_cells(child, source, cells);
+    }
 }
 
 #[test]
@@ -32,76 +41,22 @@ fn r_code_blocks_are_parsed() {
     eprintln!("\n=== R CODE PARSE TREE (first 2000 chars) ===");
     eprintln!("{}", &sexp.chars().take(2000).collect::<String>());
 
-    // Verify R code blocks are parsed as fenced_code_block
+    // Verify R code blocks are parsed as executable_code_cell
     assert!(
-        sexp.contains("(fenced_code_block"),
-        "Document should contain fenced code blocks"
-    );
-    println!("✓ R code blocks are parsed as fenced_code_block nodes");
-
-    // Verify info_string contains 'r' (case insensitive)
-    let mut cursor = tree.walk();
-    let mut r_code_blocks = 0;
-    let mut r_code_contents = Vec::new();
-
-    fn find_r_blocks(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        count: &mut usize,
-        contents: &mut Vec<String>,
-    ) {
-        let node = cursor.node();
-
-        if node.kind() == "fenced_code_block" {
-            // Check if this is an R code block
-            if cursor.goto_first_child() {
-                let mut is_r_block = false;
-                let mut code_content = String::new();
-
-                loop {
-                    let child = cursor.node();
-
-                    if child.kind() == "info_string" {
-                        let info = &source[child.byte_range()];
-                        // Match {r} or {r ...} or {R}
-                        if info.to_lowercase().contains("r") {
-                            is_r_block = true;
-                        }
-                    } else if child.kind() == "code_fence_content" {
-                        code_content = source[child.byte_range()].to_string();
-                    }
-
-                    if !cursor.goto_next_sibling() {
-                        break;
-                    }
-                }
-
-                if is_r_block {
-                    *count += 1;
-                    contents.push(code_content);
-                }
-
-                cursor.goto_parent();
-            }
-        }
-
-        if cursor.goto_first_child() {
-            loop {
-                find_r_blocks(cursor, source, count, contents);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
-    }
-
-    find_r_blocks(
-        &mut cursor,
-        &source,
-        &mut r_code_blocks,
-        &mut r_code_contents,
+        sexp.contains("(executable_code_cell"),
+        "Document should contain executable code cells"
     );
+    println!("✓ R code blocks are parsed as executable_code_cell nodes");
+
+    // Verify the language name is 'r' (case insensitive)
+    let mut cells = Vec::new();
+    code_cells(root, &source, &mut cells);
+    let r_code_contents: Vec<String> = cells
+        .into_iter()
+        .filter(|(language, _)| language.eq_ignore_ascii_case("r"))
+        .map(|(_, content)| content)
+        .collect();
+    let r_code_blocks = r_code_contents.len();
 
     eprintln!("\n=== R CODE BLOCKS FOUND: {} ===", r_code_blocks);
 
@@ -179,17 +134,14 @@ fn r_injection_query_matches() {
 
         eprintln!("Parse tree: {}", sexp);
 
-        // Should have a fenced_code_block
-        assert!(
-            sexp.contains("(fenced_code_block"),
-            "Test case {} should parse as fenced_code_block",
-            i + 1
-        );
-
-        // Should have an info_string with 'r' or 'R'
+        // Should have an executable_code_cell whose language is 'r' or 'R'
+        let mut cells = Vec::new();
+        code_cells(root, source, &mut cells);
         assert!(
-            sexp.contains("(info_string") && (source.contains("{r") || source.contains("{R")),
-            "Test case {} should have R info_string",
+            cells
+                .iter()
+                .any(|(language, _)| language.eq_ignore_ascii_case("r")),
+            "Test case {} should parse as an R executable_code_cell",
             i + 1
         );
 
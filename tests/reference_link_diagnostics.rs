@@ -0,0 +1,178 @@
+use quarto_zed::lsp::Server;
+use quarto_zed::reference_links::{self, UseKind};
+use quarto_zed::text::{apply_edits, Position, Range};
+use serde_json::json;
+
+fn cursor(line: u32, character: u32) -> Range {
+    Range::new(
+        Position::new(line, character),
+        Position::new(line, character),
+    )
+}
+
+#[test]
+fn definitions_and_uses_are_found() {
+    let source = r#"See [Python][] and [the R project][r-lang], or just [Docs].
+
+![Logo][logo] and [not a link] and [@knuth84] and [^1] and `[code][x]`.
+
+[python]: https://python.org
+[R-Lang]: <https://r-project.org> "The R Project"
+  [docs]:   https://example.com 'Documentation'
+[logo]: logo.png (Project logo)
+[^1]: A footnote.
+"#;
+    let definitions = reference_links::definitions(source);
+    let labels: Vec<&str> = definitions.iter().map(|d| d.label.as_str()).collect();
+    assert_eq!(labels, ["python", "R-Lang", "docs", "logo"]);
+    assert_eq!(definitions[1].url, "https://r-project.org");
+    assert_eq!(definitions[1].title.as_deref(), Some("The R Project"));
+    assert_eq!(definitions[2].title.as_deref(), Some("Documentation"));
+    assert_eq!(definitions[3].title.as_deref(), Some("Project logo"));
+
+    let uses = reference_links::uses(source, &definitions);
+    let found: Vec<(UseKind, &str, bool)> = uses
+        .iter()
+        .map(|found| (found.kind, found.label.as_str(), found.image))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (UseKind::Collapsed, "Python", false),
+            (UseKind::Full, "r-lang", false),
+            (UseKind::Shortcut, "Docs", false),
+            (UseKind::Full, "logo", true),
+        ],
+        "undefined shortcuts, citations, footnotes and code are not references"
+    );
+    assert_eq!((uses[3].start, uses[3].end), (0, 13));
+}
+
+#[test]
+fn labels_match_case_insensitively() {
+    assert_eq!(
+        reference_links::normalize("  The   R\tProject "),
+        "the r project"
+    );
+    let source = "Read [the guide][User  Guide].\n\n[user guide]: guide.html\n";
+    assert!(
+        reference_links::diagnostics(source).is_empty(),
+        "labels differing in case and spacing match"
+    );
+}
+
+#[test]
+fn problems_are_reported() {
+    let source = r#"Compare [inline link](https://inline.com) with [reference link][ref].
+Also [missing][nowhere] and [Collapsed][].
+
+[ref]: https://reference.com
+[REF]: https://other.com
+[unused]: https://unused.com
+"#;
+    let diagnostics = reference_links::diagnostics(source);
+    let found: Vec<(&str, u32)> = diagnostics
+        .iter()
+        .map(|d| (d.code, d.range.start.line))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("undefined-reference", 1),
+            ("undefined-reference", 1),
+            ("duplicate-reference", 4),
+            ("unused-reference", 5),
+        ]
+    );
+    assert_eq!(
+        diagnostics[0].message,
+        "No definition for reference [nowhere]"
+    );
+    assert_eq!(diagnostics[0].range.start.character, 5);
+    assert_eq!(
+        diagnostics[2].message,
+        "[REF] is already defined on line 4; the first definition is used"
+    );
+}
+
+#[test]
+fn missing_definitions_are_appended() {
+    let source = "Intro with [a link][later].\n";
+    let actions = reference_links::code_actions(source, cursor(0, 14));
+    assert_eq!(actions[0].title, "Add a definition for [later]");
+    assert_eq!(
+        apply_edits(source, &actions[0].edits),
+        "Intro with [a link][later].\n\n[later]: https://\n"
+    );
+
+    let source = "Intro [one][a] and [two][b].\n\n[a]: https://a.example";
+    let actions = reference_links::code_actions(source, cursor(0, 22));
+    assert_eq!(
+        apply_edits(source, &actions[0].edits),
+        "Intro [one][a] and [two][b].\n\n[a]: https://a.example\n[b]: https://\n",
+        "definitions are grouped with the existing ones"
+    );
+    assert!(reference_links::code_actions(source, cursor(0, 0)).is_empty());
+}
+
+#[test]
+fn references_are_inlined() {
+    let source = r#"Check the [documentation][docs] for more info.
+
+[docs]: https://example.com "Example Documentation"
+"#;
+    let actions = reference_links::code_actions(source, cursor(0, 12));
+    assert_eq!(actions[0].title, "Inline link to https://example.com");
+    assert_eq!(
+        apply_edits(source, &actions[0].edits),
+        "Check the [documentation](https://example.com \"Example Documentation\") for more info.\n\n",
+        "the definition goes once its only use is inlined"
+    );
+
+    let source = "![Logo][logo] and [logo] again.\n\n[logo]: logo.png\n";
+    let actions = reference_links::code_actions(source, cursor(0, 2));
+    assert_eq!(
+        apply_edits(source, &actions[0].edits),
+        "![Logo](logo.png) and [logo] again.\n\n[logo]: logo.png\n"
+    );
+}
+
+#[test]
+fn language_server_reports_reference_problems() {
+    let mut server = Server::new();
+    let uri = "untitled:links.qmd";
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {
+                "uri": uri,
+                "languageId": "quarto",
+                "version": 1,
+                "text": "See [Python][py].\n",
+            }
+        }
+    }));
+    assert_eq!(
+        replies[0]["params"]["diagnostics"][0]["code"],
+        "undefined-reference"
+    );
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "textDocument/codeAction",
+        "params": {
+            "textDocument": { "uri": uri },
+            "range": { "start": { "line": 0, "character": 6 }, "end": { "line": 0, "character": 6 } },
+            "context": { "diagnostics": [] }
+        }
+    }));
+    let titles: Vec<&str> = replies[0]["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|action| action["title"].as_str().unwrap())
+        .collect();
+    assert!(titles.contains(&"Add a definition for [py]"), "{titles:?}");
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use quarto_zed::lsp::Server;
+use quarto_zed::repl::{self, kernel_language, RunScope};
+use quarto_zed::text::{Position, Range};
+use serde_json::json;
+
+const DOCUMENT: &str = r#"---
+title: "Mixed kernels"
+---
+
+```{python}
+#| label: setup
+#| echo: false
+import math
+```
+
+Text between cells.
+
+```{r}
+x <- 1
+```
+
+```{python3}
+print(math.pi)
+
+print(math.e)
+```
+
+```{mermaid}
+graph LR
+```
+
+```{python}
+#| label: empty
+```
+"#;
+
+fn line_of(needle: &str) -> usize {
+    DOCUMENT
+        .lines()
+        .position(|line| line.contains(needle))
+        .unwrap_or_else(|| panic!("{needle:?} not in document"))
+}
+
+#[test]
+fn engines_map_to_kernel_languages() {
+    assert_eq!(kernel_language("python3"), Some("python"));
+    assert_eq!(kernel_language("R"), Some("r"));
+    assert_eq!(kernel_language("julia"), Some("julia"));
+    assert_eq!(kernel_language("ojs"), None);
+    assert_eq!(kernel_language("mermaid"), None);
+}
+
+#[test]
+fn run_cell_sends_body_without_options() {
+    for line in [line_of("{python}"), line_of("import math"), 8] {
+        let plan = repl::plan(DOCUMENT, line, RunScope::Cell).expect("cursor is in a cell");
+        assert_eq!(plan.runs.len(), 1);
+        assert_eq!(plan.runs[0].kernel, "python");
+        assert_eq!(plan.runs[0].code, "import math\n", "line {line}");
+        assert_eq!(
+            plan.runs[0].range,
+            Range::new(Position::new(7, 0), Position::new(7, 11))
+        );
+    }
+}
+
+#[test]
+fn run_cell_keeps_blank_lines_inside_the_body() {
+    let plan = repl::plan(DOCUMENT, line_of("math.e"), RunScope::Cell).unwrap();
+    assert_eq!(plan.runs[0].code, "print(math.pi)\n\nprint(math.e)\n");
+    assert_eq!(plan.runs[0].range.start.line as usize, line_of("math.pi"));
+    assert_eq!(plan.runs[0].range.end.line as usize, line_of("math.e"));
+}
+
+#[test]
+fn nothing_to_run_outside_cells_or_kernels() {
+    assert!(repl::plan(DOCUMENT, line_of("Text between"), RunScope::Cell).is_none());
+    assert!(repl::plan(DOCUMENT, line_of("graph LR"), RunScope::Cell).is_none());
+    assert!(
+        repl::plan(DOCUMENT, line_of("label: empty"), RunScope::Cell).is_none(),
+        "a cell with only options has no code"
+    );
+}
+
+#[test]
+fn run_all_above_uses_the_current_kernel() {
+    let plan = repl::plan(DOCUMENT, line_of("math.e"), RunScope::AllAbove).unwrap();
+    let codes: Vec<&str> = plan.runs.iter().map(|run| run.code.as_str()).collect();
+    assert_eq!(codes, ["import math\n"], "the R cell is skipped");
+
+    let plan = repl::plan(DOCUMENT, line_of("graph LR") + 3, RunScope::AllAbove).unwrap();
+    assert_eq!(
+        plan.runs.len(),
+        2,
+        "both Python cells precede the empty cell"
+    );
+
+    assert!(repl::plan(DOCUMENT, line_of("import math"), RunScope::AllAbove).is_none());
+}
+
+#[test]
+fn run_all_above_outside_cells_follows_nearest_cell() {
+    let plan = repl::plan(DOCUMENT, line_of("Text between"), RunScope::AllAbove).unwrap();
+    assert_eq!(plan.runs.len(), 1);
+    assert_eq!(plan.runs[0].kernel, "python");
+}
+
+#[test]
+fn language_server_selects_cell_body() {
+    let mut server = Server::new();
+    let uri = "file:///cells.qmd";
+    server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri, "languageId": "quarto", "version": 1, "text": DOCUMENT }
+        }
+    }));
+
+    let line = line_of("import math");
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "textDocument/codeAction",
+        "params": {
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": line, "character": 0 },
+                "end": { "line": line, "character": 0 },
+            },
+            "context": { "diagnostics": [] },
+        }
+    }));
+    let actions = replies[0]["result"].as_array().unwrap();
+    let run = actions
+        .iter()
+        .find(|a| a["title"] == "Run cell")
+        .expect("run cell offered");
+    assert_eq!(run["command"]["command"], "quarto.runCell");
+    assert!(
+        actions
+            .iter()
+            .all(|a| a["title"] != "Run cell and advance" && a["title"] != "Run all cells above"),
+        "only actions a single selection can carry out are offered"
+    );
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": run["command"].clone(),
+    }));
+    assert_eq!(replies.len(), 2, "reply plus showDocument request");
+    assert_eq!(replies[0]["id"], 2);
+    assert_eq!(replies[0]["result"]["runs"][0]["code"], "import math\n");
+    assert_eq!(replies[0]["result"]["runs"][0]["kernel"], "python");
+
+    let show = &replies[1];
+    assert_eq!(show["method"], "window/showDocument");
+    assert_eq!(show["params"]["uri"], uri);
+    assert_eq!(show["params"]["selection"]["start"]["line"], line);
+    assert_eq!(show["params"]["selection"]["end"]["character"], 11);
+
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": { "command": "quarto.runAllAbove", "arguments": [uri, line_of("math.e")] },
+    }));
+    assert_eq!(replies.len(), 1, "no selection for several cells");
+    assert!(replies[0]["error"].is_object());
+
+    // The client's response to showDocument needs no reply.
+    let replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": show["id"].clone(),
+        "result": { "success": true },
+    }));
+    assert!(replies.is_empty());
+}
+
+#[test]
+fn helper_prints_cells_above() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cells.qmd");
+    fs::write(&path, DOCUMENT).unwrap();
+
+    let row = line_of("math.e") + 1;
+    let output = Command::cargo_bin("quarto-cell")
+        .unwrap()
+        .arg(&path)
+        .arg(row.to_string())
+        .arg("--above")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "import math\n");
+
+    Command::cargo_bin("quarto-cell")
+        .unwrap()
+        .arg(&path)
+        .arg("1")
+        .arg("--above")
+        .assert()
+        .failure();
+}
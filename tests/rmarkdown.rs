@@ -0,0 +1,264 @@
+//! R Markdown (`.Rmd`) support.
+//!
+//! The R Markdown language reuses the tree-sitter-quarto grammar and the Quarto
+//! query files, so these tests parse real `.Rmd` documents with that grammar and
+//! run the shipped `languages/rmarkdown` queries against them.
+
+mod support;
+
+use std::fs;
+use std::path::Path;
+
+use quarto_zed::{cells, knitr};
+use support::language;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+
+const FIXTURES: [&str; 2] = ["report.Rmd", "parameters.Rmd"];
+
+fn read_fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/rmarkdown")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|e| panic!("{} readable: {}", path.display(), e))
+}
+
+fn parse(source: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language())
+        .expect("parser loads language");
+    parser
+        .parse(source.as_bytes(), None)
+        .expect("parse succeeds")
+}
+
+fn error_nodes<'a>(node: Node<'a>, errors: &mut Vec<Node<'a>>) {
+    if node.is_error() || node.is_missing() {
+        errors.push(node);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        error_nodes(child, errors);
+    }
+}
+
+/// `(injection.language, injected text)` for every injection the R Markdown
+/// injection query produces.
+fn injections(source: &str) -> Vec<(String, String)> {
+    let query_source = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/rmarkdown/injections.scm"),
+    )
+    .expect("injections.scm readable");
+    let query = Query::new(&language(), &query_source).expect("injections query compiles");
+    let content_index = query
+        .capture_index_for_name("injection.content")
+        .expect("query captures @injection.content");
+
+    let tree = parse(source);
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+
+    let mut found = Vec::new();
+    while let Some(m) = matches.next() {
+        let Some(language) = query
+            .property_settings(m.pattern_index)
+            .iter()
+            .find(|setting| &*setting.key == "injection.language")
+            .and_then(|setting| setting.value.as_deref())
+        else {
+            continue;
+        };
+        for capture in m.captures.iter().filter(|c| c.index == content_index) {
+            found.push((
+                language.to_string(),
+                source[capture.node.byte_range()].to_string(),
+            ));
+        }
+    }
+    found
+}
+
+#[test]
+fn rmarkdown_language_is_registered() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config: toml::Value = toml::from_str(
+        &fs::read_to_string(manifest_dir.join("languages/rmarkdown/config.toml"))
+            .expect("R Markdown config readable"),
+    )
+    .expect("R Markdown config parses as TOML");
+
+    assert_eq!(config["name"].as_str(), Some("R Markdown"));
+    assert_eq!(
+        config["grammar"].as_str(),
+        Some("quarto"),
+        "R Markdown reuses the tree-sitter-quarto grammar"
+    );
+    let suffixes: Vec<&str> = config["path_suffixes"]
+        .as_array()
+        .expect("path_suffixes array")
+        .iter()
+        .filter_map(|s| s.as_str())
+        .collect();
+    assert!(suffixes.contains(&"Rmd") && suffixes.contains(&"rmd"));
+
+    let manifest: toml::Value = toml::from_str(
+        &fs::read_to_string(manifest_dir.join("extension.toml")).expect("manifest readable"),
+    )
+    .expect("manifest parses as TOML");
+    let languages = manifest["languages"].as_array().expect("languages array");
+    assert!(languages
+        .iter()
+        .any(|l| l.as_str() == Some("languages/rmarkdown")));
+
+    let server_languages = manifest["language_servers"]["quarto-ls"]["languages"]
+        .as_array()
+        .expect("quarto-ls languages");
+    assert!(
+        server_languages
+            .iter()
+            .any(|l| l.as_str() == Some("R Markdown")),
+        "quarto-ls should also serve R Markdown buffers"
+    );
+}
+
+#[test]
+fn rmarkdown_queries_match_quarto() {
+    let languages_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("languages");
+    let mut compared = 0;
+
+    for entry in fs::read_dir(languages_dir.join("quarto")).expect("languages/quarto listable") {
+        let path = entry.expect("directory entry").path();
+        // Queries and runnable task templates; config.toml differs by design.
+        if !matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("scm" | "json")
+        ) {
+            continue;
+        }
+        let name = path.file_name().unwrap();
+        let linked = languages_dir.join("rmarkdown").join(name);
+        assert!(
+            fs::symlink_metadata(&linked).is_ok_and(|meta| meta.file_type().is_symlink()),
+            "languages/rmarkdown/{:?} should be a symlink to ../quarto/{:?}, not a copy",
+            name,
+            name
+        );
+        let quarto = fs::read_to_string(&path).expect("Quarto file readable");
+        let rmarkdown = fs::read_to_string(languages_dir.join("rmarkdown").join(name))
+            .unwrap_or_else(|_| panic!("languages/rmarkdown is missing {:?}", name));
+        assert_eq!(
+            rmarkdown, quarto,
+            "languages/rmarkdown/{:?} must match the Quarto file",
+            name
+        );
+        compared += 1;
+    }
+
+    assert!(
+        compared >= 3,
+        "expected at least highlights, injections and outline"
+    );
+}
+
+#[test]
+fn rmd_fixtures_parse_without_errors() {
+    for name in FIXTURES {
+        let source = read_fixture(name);
+        let tree = parse(&source);
+
+        let mut errors = Vec::new();
+        error_nodes(tree.root_node(), &mut errors);
+        assert!(
+            errors.is_empty(),
+            "{} has parse errors at {:?}\n{}",
+            name,
+            errors
+                .iter()
+                .map(|node| node.start_position())
+                .collect::<Vec<_>>(),
+            tree.root_node().to_sexp()
+        );
+    }
+}
+
+#[test]
+fn knitr_chunks_are_executable_cells() {
+    let source = read_fixture("report.Rmd");
+    let tree = parse(&source);
+    let sexp = tree.root_node().to_sexp();
+
+    let expected = cells::cells(&source).len();
+    assert_eq!(expected, 4, "fixture has four R chunks");
+    assert_eq!(
+        sexp.matches("(executable_code_cell").count(),
+        expected,
+        "knitr headers like {{r setup, include=FALSE}} should still be cells\n{}",
+        sexp
+    );
+}
+
+#[test]
+fn r_chunks_inject_r() {
+    let source = read_fixture("report.Rmd");
+    let injected = injections(&source);
+
+    assert!(
+        injected
+            .iter()
+            .any(|(lang, text)| lang == "r" && text.contains("knitr::opts_chunk$set")),
+        "setup chunk should be injected as R: {:?}",
+        injected
+    );
+    assert!(
+        injected
+            .iter()
+            .any(|(lang, text)| lang == "r" && text.contains("geom_col()")),
+        "chunk with knitr options should be injected as R: {:?}",
+        injected
+    );
+}
+
+#[test]
+fn inline_r_expressions_inject_r() {
+    let source = read_fixture("report.Rmd");
+    let injected = injections(&source);
+
+    for expression in ["nrow(sales)", "format(Sys.Date(), \"%B %Y\")"] {
+        assert!(
+            injected
+                .iter()
+                .any(|(lang, text)| lang == "r" && text.trim() == expression),
+            "`r {}` should be injected as R: {:?}",
+            expression,
+            injected
+        );
+    }
+}
+
+#[test]
+fn mixed_engines_keep_their_languages() {
+    let source = read_fixture("parameters.Rmd");
+    let injected = injections(&source);
+
+    assert!(injected
+        .iter()
+        .any(|(lang, text)| lang == "python" && text.contains("reticulate")));
+    assert!(injected
+        .iter()
+        .any(|(lang, text)| lang == "r" && text.trim() == "params$region"));
+    assert!(injected
+        .iter()
+        .any(|(lang, text)| lang == "yaml" && text.contains("region: \"North\"")));
+}
+
+#[test]
+fn rmd_chunk_headers_migrate() {
+    let source = read_fixture("report.Rmd");
+    let (migrated, count) = knitr::migrate_document(&source);
+
+    assert_eq!(count, 4, "every chunk in the fixture uses a knitr header");
+    assert!(migrated.contains("```{r}\n#| label: setup\n#| include: false\n"));
+    assert!(migrated.contains("```{r}\n#| label: load-data\n"));
+    assert!(migrated.contains("#| fig-cap: \"Revenue by region\"\n"));
+    assert!(migrated.contains("```{r}\n#| echo: true\n#| eval: false\n"));
+}
@@ -0,0 +1,244 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use quarto_zed::execute::{self, format_output, parse_target, run_command};
+
+const DOCUMENT: &str = r#"---
+title: "Run cells"
+---
+
+```{python}
+#| label: where
+import os
+print(os.getcwd())
+print(open("data.txt").read(), end="")
+```
+
+```{python}
+#| label: fig-plot
+#| echo: false
+import sys
+print("to stdout")
+print("to stderr", file=sys.stderr)
+sys.exit(3)
+```
+
+```{r}
+x <- 1
+```
+
+```{bash}
+#| label: shell
+cat data.txt
+echo "to stderr" >&2
+exit 4
+```
+
+```{ojs}
+//| label: viz
+viz = 1
+```
+"#;
+
+/// Runs `label` from DOCUMENT the way the slash command does, natively.
+fn run(directory: &str, label: &str) -> std::process::Output {
+    let cell = execute::find_cell(DOCUMENT, label).expect("cell exists");
+    let lines: Vec<&str> = DOCUMENT.lines().collect();
+    let (program, args) =
+        run_command(directory, &cell.language, &cell.code(&lines)).expect("supported engine");
+    Command::new(program)
+        .args(args)
+        .output()
+        .expect("interpreter runs")
+}
+
+fn python_missing() -> bool {
+    let missing = Command::new("python3").arg("--version").output().is_err();
+    if missing {
+        eprintln!("skipping: python3 not installed");
+    }
+    missing
+}
+
+#[test]
+fn target_splits_file_and_label() {
+    assert_eq!(
+        parse_target("analysis.qmd#fig-plot").unwrap(),
+        ("analysis.qmd", "fig-plot")
+    );
+    assert_eq!(
+        parse_target("dir/#notes/a.qmd#setup").unwrap(),
+        ("dir/#notes/a.qmd", "setup"),
+        "the label follows the last #"
+    );
+    assert!(parse_target("analysis.qmd").is_err());
+    assert!(parse_target("analysis.qmd#").is_err());
+    assert!(parse_target("#setup").is_err());
+}
+
+#[test]
+fn labels_are_listed_in_document_order() {
+    assert_eq!(
+        execute::labels(DOCUMENT),
+        ["where", "fig-plot", "shell", "viz"],
+        "every #| and //| label, skipping unlabelled cells"
+    );
+}
+
+#[test]
+fn unknown_labels_and_engines_are_errors() {
+    assert!(execute::find_cell(DOCUMENT, "missing")
+        .unwrap_err()
+        .contains("missing"));
+
+    let viz = execute::find_cell(DOCUMENT, "viz").unwrap();
+    assert!(run_command("/tmp", &viz.language, "viz = 1")
+        .unwrap_err()
+        .contains("ojs"));
+}
+
+#[test]
+fn engines_map_to_interpreters() {
+    let program =
+        |language| execute::interpreter(language).map(|(program, prelude)| (program, prelude[0]));
+    assert_eq!(program("python"), Some(("python3", "-c")));
+    assert_eq!(program("r"), Some(("Rscript", "-e")));
+    assert_eq!(program("julia"), Some(("julia", "-e")));
+    assert_eq!(program("bash"), Some(("bash", "-c")));
+    assert_eq!(program("sh"), Some(("bash", "-c")));
+    assert_eq!(program("mermaid"), None);
+}
+
+#[test]
+fn manifest_allows_exactly_the_interpreter_commands() {
+    let manifest: toml::Value = toml::from_str(
+        &fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("extension.toml"))
+            .expect("manifest readable"),
+    )
+    .expect("manifest parses as TOML");
+    let capabilities = manifest["capabilities"].as_array().expect("capabilities");
+    let allowed = |command: &str| -> Vec<Vec<&str>> {
+        capabilities
+            .iter()
+            .filter(|c| {
+                c["kind"].as_str() == Some("process:exec") && c["command"].as_str() == Some(command)
+            })
+            .map(|c| {
+                c["args"]
+                    .as_array()
+                    .expect("capability args")
+                    .iter()
+                    .filter_map(|arg| arg.as_str())
+                    .collect()
+            })
+            .collect()
+    };
+
+    for language in ["python", "r", "julia", "bash"] {
+        let (program, prelude) = execute::interpreter(language).unwrap();
+        let expected: Vec<&str> = prelude.iter().copied().chain(["*", "*"]).collect();
+        assert_eq!(
+            allowed(program),
+            [expected],
+            "extension.toml must allow {} with exactly the prelude in src/execute.rs",
+            program
+        );
+        let (_, args) = run_command("/tmp", language, "1").unwrap();
+        let expected: Vec<&str> = prelude.iter().copied().chain(["/tmp", "1"]).collect();
+        assert_eq!(args, expected);
+    }
+    assert!(allowed("sh").is_empty(), "sh cells run with bash");
+}
+
+#[test]
+fn cell_runs_in_document_directory() {
+    if python_missing() {
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("data.txt"), "from the data file\n").unwrap();
+    let directory = dir.path().to_str().unwrap();
+
+    let output = run(directory, "where");
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let real_dir = dir.path().canonicalize().unwrap();
+    assert!(
+        stdout.contains(real_dir.to_str().unwrap()) || stdout.contains(directory),
+        "cell should run from {}, got {}",
+        directory,
+        stdout
+    );
+    assert!(stdout.contains("from the data file"));
+}
+
+#[test]
+fn directories_with_spaces_and_quotes_are_passed_through() {
+    if python_missing() {
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("my \"reports\" $HOME");
+    fs::create_dir(&nested).unwrap();
+    fs::write(nested.join("data.txt"), "quoted\n").unwrap();
+
+    let output = run(nested.to_str().unwrap(), "where");
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8(output.stdout).unwrap().contains("quoted"));
+}
+
+#[test]
+fn python_cell_reports_streams_and_status() {
+    if python_missing() {
+        return;
+    }
+
+    let output = run(".", "fig-plot");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "to stdout\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "to stderr\n");
+}
+
+#[test]
+fn bash_cell_runs_in_document_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("my \"reports\" $HOME");
+    fs::create_dir(&nested).unwrap();
+    fs::write(nested.join("data.txt"), "from the data file\n").unwrap();
+
+    let output = run(nested.to_str().unwrap(), "shell");
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "from the data file\n"
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "to stderr\n");
+}
+
+#[test]
+fn output_has_a_section_per_stream_and_status() {
+    let (text, sections) = format_output("fig-plot", "a\nb\n", "oops\n", Some(1));
+
+    let labels: Vec<&str> = sections.iter().map(|(_, label)| label.as_str()).collect();
+    assert_eq!(
+        labels,
+        [
+            "fig-plot: stdout",
+            "fig-plot: stderr",
+            "fig-plot: Exit status: 1"
+        ]
+    );
+    assert_eq!(&text[sections[0].0.clone()], "```\na\nb\n```\n");
+    assert_eq!(&text[sections[1].0.clone()], "```\noops\n```\n");
+    assert_eq!(&text[sections[2].0.clone()], "Exit status: 1\n");
+    assert_eq!(sections[2].0.end, text.len());
+
+    let (text, _) = format_output("x", "```\ncode\n```", "", None);
+    assert!(
+        text.starts_with("````\n```\ncode\n```\n````\n"),
+        "fences grow past backticks in the output"
+    );
+    assert!(text.ends_with("Exit status: terminated by signal\n"));
+}
@@ -0,0 +1,181 @@
+//! Gutter runnables for executable code cells.
+//!
+//! `runnables.scm` tags each cell with its language, `tasks.json` maps the tags
+//! to interpreters, and the `quarto-cell` helper extracts the code to run.
+
+mod support;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use support::language;
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+
+const DOCUMENT: &str = r#"---
+title: "Runnable cells"
+---
+
+```{python}
+#| label: setup
+import math
+print(math.pi)
+```
+
+Some prose.
+
+```{r}
+#| echo: false
+x <- c(1, 2, 3)
+mean(x)
+```
+
+```{julia}
+println(1 + 1)
+```
+
+```python
+print("not a cell")
+```
+"#;
+
+/// `(tag, zero-based row)` for every runnable the query finds in `source`.
+fn runnables(source: &str) -> Vec<(String, usize)> {
+    let query_source = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto/runnables.scm"),
+    )
+    .expect("runnables.scm readable");
+    let query = Query::new(&language(), &query_source).expect("runnables query compiles");
+    let run_index = query
+        .capture_index_for_name("run")
+        .expect("query captures @run");
+
+    let mut parser = Parser::new();
+    parser.set_language(&language()).unwrap();
+    let tree = parser.parse(source.as_bytes(), None).unwrap();
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    let mut found = Vec::new();
+    while let Some(m) = matches.next() {
+        let tag = query
+            .property_settings(m.pattern_index)
+            .iter()
+            .find(|setting| &*setting.key == "tag")
+            .and_then(|setting| setting.value.as_deref())
+            .expect("every runnable pattern sets a tag");
+        for capture in m.captures.iter().filter(|c| c.index == run_index) {
+            found.push((tag.to_string(), capture.node.start_position().row));
+        }
+    }
+    found
+}
+
+fn write_document(dir: &tempfile::TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("cells.qmd");
+    fs::write(&path, DOCUMENT).expect("document written");
+    path
+}
+
+fn extract(path: &Path, row: usize) -> std::process::Output {
+    Command::cargo_bin("quarto-cell")
+        .expect("quarto-cell binary built")
+        .arg(path)
+        .arg(row.to_string())
+        .output()
+        .expect("quarto-cell runs")
+}
+
+#[test]
+fn runnable_query_captures_each_cell() {
+    let found = runnables(DOCUMENT);
+    assert_eq!(
+        found,
+        vec![
+            ("quarto-python-cell".to_string(), 4),
+            ("quarto-r-cell".to_string(), 12),
+            ("quarto-julia-cell".to_string(), 18),
+        ],
+        "one runnable per executable cell, on its opening fence"
+    );
+}
+
+#[test]
+fn every_runnable_tag_has_a_task() {
+    let languages_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("languages/quarto");
+    let tasks: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(languages_dir.join("tasks.json")).expect("tasks.json readable"),
+    )
+    .expect("tasks.json parses");
+    let tasks = tasks.as_array().expect("tasks.json is a list of templates");
+
+    let query = fs::read_to_string(languages_dir.join("runnables.scm")).unwrap();
+    let tags = regex::Regex::new(r"#set! tag ([\w-]+)").unwrap();
+    for tag in tags.captures_iter(&query).map(|c| c[1].to_string()) {
+        let task = tasks
+            .iter()
+            .find(|task| {
+                task["tags"]
+                    .as_array()
+                    .is_some_and(|tags| tags.iter().any(|t| t.as_str() == Some(tag.as_str())))
+            })
+            .unwrap_or_else(|| panic!("no task template for tag {}", tag));
+        let command = task["command"].as_str().expect("task command");
+        assert!(
+            command.starts_with("quarto-cell \"$ZED_FILE\" \"$ZED_ROW\" | "),
+            "task for {} should extract the cell with quarto-cell: {}",
+            tag,
+            command
+        );
+    }
+}
+
+#[test]
+fn helper_extracts_exact_cell_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_document(&dir);
+
+    // One-based rows, as Zed passes $ZED_ROW: opening fence, code line, closing fence.
+    for (row, expected) in [
+        (5, "import math\nprint(math.pi)\n"),
+        (8, "import math\nprint(math.pi)\n"),
+        (9, "import math\nprint(math.pi)\n"),
+        (14, "x <- c(1, 2, 3)\nmean(x)\n"),
+        (20, "println(1 + 1)\n"),
+    ] {
+        let output = extract(&path, row);
+        assert!(
+            output.status.success(),
+            "row {} should be inside a cell",
+            row
+        );
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            expected,
+            "row {}",
+            row
+        );
+    }
+}
+
+#[test]
+fn helper_rejects_rows_outside_cells() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_document(&dir);
+
+    for row in [1, 11, 24] {
+        let output = extract(&path, row);
+        assert!(!output.status.success(), "row {} is not in a cell", row);
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("no executable cell"));
+    }
+
+    Command::cargo_bin("quarto-cell")
+        .unwrap()
+        .arg(&path)
+        .arg("0")
+        .assert()
+        .failure();
+}
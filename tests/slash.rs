@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use quarto_zed::slash::{self, Completion, Output, ProjectSummary};
+
+fn book_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/projects/book")
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn project(args: &[&str]) -> Result<Option<ProjectSummary>, String> {
+    slash::main(&strings(args))
+        .expect("project is a subcommand")
+        .map(|json| serde_json::from_str(&json).unwrap())
+}
+
+#[test]
+fn language_server_arguments_are_not_subcommands() {
+    assert_eq!(slash::main(&[]), None);
+    assert_eq!(slash::main(&strings(&["--stdio"])), None);
+    assert!(slash::main(&strings(&["slash"])).unwrap().is_err());
+}
+
+#[test]
+fn project_summary_applies_profiles() {
+    let root = book_root();
+    let chapter = root.join("chapters/regression.qmd");
+    let chapter = chapter.to_str().unwrap();
+
+    let summary = project(&["project", chapter]).unwrap().unwrap();
+    assert_eq!(summary.root, root.to_str().unwrap());
+    assert_eq!(summary.description, "book \"Fuel Economy in Practice\"");
+    assert_eq!(summary.profile, None);
+    assert_eq!(
+        summary.output_dir.as_deref(),
+        root.join("_book").to_str(),
+        "output directory is absolute"
+    );
+    assert!(summary.execute_in_root);
+
+    let dev = project(&["project", chapter, "--profile", "dev"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(dev.profile.as_deref(), Some("dev"));
+    assert_eq!(dev.output_dir.as_deref(), root.join("_preview").to_str());
+
+    let error = project(&["project", chapter, "--profile", "prod"]).unwrap_err();
+    assert!(error.contains("available: dev"), "{error}");
+}
+
+#[test]
+fn default_profile_applies_only_when_the_project_has_it() {
+    let chapter = book_root().join("index.qmd");
+    let chapter = chapter.to_str().unwrap();
+
+    let dev = project(&["project", "--default-profile", "dev", chapter]).unwrap();
+    assert_eq!(dev.unwrap().profile.as_deref(), Some("dev"));
+    let prod = project(&["project", "--default-profile", "prod", chapter]).unwrap();
+    assert_eq!(prod.unwrap().profile, None, "unknown defaults are ignored");
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("alone.qmd"), "# Alone\n").unwrap();
+    let alone = dir.path().join("alone.qmd");
+    assert_eq!(project(&["project", alone.to_str().unwrap()]), Ok(None));
+    let error = project(&["project", alone.to_str().unwrap(), "--profile", "dev"]).unwrap_err();
+    assert!(error.contains("needs a Quarto project"), "{error}");
+}
+
+#[test]
+fn project_cross_references_cover_every_document() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.qmd"),
+        "![Plot](plot.png){#fig-plot}\n\nSee @tbl-missing.\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("b.qmd"), "As @fig-plot shows.\n").unwrap();
+
+    let output = slash::run("quarto-xrefs", dir.path(), &[], None).unwrap();
+    assert!(output.text.contains("fig-plot"), "{}", output.text);
+    assert!(output.text.contains("b.qmd"), "{}", output.text);
+    assert_eq!(
+        output.sections[0].label,
+        "Cross-references: 1 labels, 0 unused, 1 dangling"
+    );
+
+    let empty = tempfile::tempdir().unwrap();
+    let error = slash::run("quarto-xrefs", empty.path(), &[], None).unwrap_err();
+    assert!(error.starts_with("No Quarto documents found"), "{error}");
+}
+
+#[test]
+fn profiles_complete_in_the_worktree() {
+    let completions = slash::complete("quarto-render", &book_root(), &strings(&["--profile", ""]));
+    assert_eq!(
+        completions,
+        [Completion {
+            label: "dev".to_string(),
+            new_text: "dev".to_string(),
+            run_command: true,
+        }]
+    );
+    assert!(slash::complete("quarto-outline", &book_root(), &strings(&["--p"])).is_empty());
+}
+
+#[test]
+fn binary_prints_json_for_the_extension() {
+    let root = book_root();
+    let output = Command::cargo_bin("quarto-ls")
+        .unwrap()
+        .args(["complete", "quarto-book", root.to_str().unwrap(), "--pro"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let completions: Vec<Completion> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(completions[0].new_text, "--profile");
+
+    let output = Command::cargo_bin("quarto-ls")
+        .unwrap()
+        .args(["slash", "quarto-xrefs", "/nonexistent/worktree"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No Quarto documents found"));
+}
+
+#[test]
+fn headers_shift_section_ranges() {
+    let output = Output::with_header(
+        "Head\n".to_string(),
+        "body".to_string(),
+        vec![(0..4, "Body".to_string())],
+    );
+    assert_eq!(&output.text[output.sections[0].range.clone()], "body");
+}
+
+#[test]
+fn citations_come_from_the_project_bibliography() {
+    let output = slash::run(
+        "quarto-cite",
+        &book_root(),
+        &strings(&["doe", "2020"]),
+        None,
+    )
+    .unwrap();
+    assert!(output.text.starts_with("[@fuel2020]\n"), "{}", output.text);
+    assert_eq!(output.sections[0].label, "@fuel2020: Doe (2020)");
+
+    let error = slash::run("quarto-cite", &book_root(), &strings(&["nobody"]), None).unwrap_err();
+    assert_eq!(error, "No references match 'nobody'");
+    let empty = tempfile::tempdir().unwrap();
+    let error = slash::run("quarto-cite", empty.path(), &strings(&["doe"]), None).unwrap_err();
+    assert!(
+        error.starts_with("No .bib or CSL-JSON bibliography"),
+        "{error}"
+    );
+
+    let completions = slash::complete("quarto-cite", &book_root(), &strings(&["fuel"]));
+    assert_eq!(completions[0].new_text, "fuel2020");
+    assert_eq!(completions[0].label, "fuel2020: Doe (2020) Fuel");
+}
+
+#[test]
+fn new_documents_use_worktree_templates_and_project_hints() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates_dir = dir.path().join(".quarto-templates");
+    std::fs::create_dir(&templates_dir).unwrap();
+    std::fs::write(templates_dir.join("article.qmd"), "# {{title}}\n").unwrap();
+    std::fs::write(
+        dir.path().join("_quarto.yml"),
+        "project:\n  type: website\n",
+    )
+    .unwrap();
+
+    let output = slash::run(
+        "quarto-new",
+        dir.path(),
+        &strings(&["article", "intro"]),
+        None,
+    )
+    .unwrap();
+    assert!(output.text.contains("# Intro\n"), "custom templates win");
+    assert!(
+        output.text.contains("Link intro.qmd from the navbar"),
+        "{}",
+        output.text
+    );
+
+    let builtin = slash::new_from_template(None, &strings(&["article", "intro"])).unwrap();
+    assert!(!builtin.text.contains("# Intro\n"));
+    assert!(!builtin.text.contains("navbar"));
+
+    let names: Vec<String> = slash::complete("quarto-new", dir.path(), &[])
+        .into_iter()
+        .map(|completion| completion.label)
+        .collect();
+    assert_eq!(names[0], "article: .quarto-templates");
+    assert_eq!(
+        names.len(),
+        6,
+        "the custom article replaces the built-in one"
+    );
+    assert_eq!(
+        slash::template_completions(None, &strings(&["article", "intro", ""])).len(),
+        3
+    );
+}
+
+#[test]
+fn book_contents_follow_the_default_profile() {
+    let root = book_root();
+    let output = slash::run("quarto-book", &root, &[], None).unwrap();
+    assert!(output
+        .text
+        .starts_with("Reading order of the book \"Fuel Economy in Practice\":\n\n"));
+
+    let dev = slash::run("quarto-book", &root, &[], Some("dev")).unwrap();
+    assert!(dev.text.contains(", profile: dev:"), "{}", dev.text);
+    assert!(dev.text.contains("chapters/drafts.qmd"), "{}", dev.text);
+    let explicit = slash::run("quarto-book", &root, &strings(&["--profile", "dev"]), None);
+    assert_eq!(explicit, Ok(dev));
+
+    let dir = tempfile::tempdir().unwrap();
+    let error = slash::run("quarto-book", dir.path(), &[], None).unwrap_err();
+    assert!(error.ends_with("is not a Quarto book project"), "{error}");
+}
+
+#[test]
+fn links_are_checked_in_a_file_or_the_whole_project() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/projects/website");
+
+    let project = slash::run("quarto-check-links", &root, &[], None).unwrap();
+    assert!(project
+        .text
+        .starts_with("Links in website \"Field Guide\": "));
+    assert!(
+        project.text.contains("guides/retired.qmd"),
+        "{}",
+        project.text
+    );
+
+    let file = slash::run(
+        "quarto-check-links",
+        &root,
+        &strings(&["guides/setup.qmd"]),
+        None,
+    )
+    .unwrap();
+    assert!(
+        file.text.starts_with("Links in setup.qmd: "),
+        "{}",
+        file.text
+    );
+    assert_eq!(file.sections.len(), 1);
+    assert_eq!(file.sections[0].range, 0..file.text.len());
+
+    let error = slash::run(
+        "quarto-check-links",
+        &root,
+        &strings(&["missing.qmd"]),
+        None,
+    )
+    .unwrap_err();
+    assert!(error.starts_with("Could not read"), "{error}");
+}
+
+#[test]
+fn cell_labels_complete_relative_to_the_worktree() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("analysis")).unwrap();
+    std::fs::write(
+        dir.path().join("analysis/model.qmd"),
+        "```{python}\n#| label: fit\nx = 1\n```\n",
+    )
+    .unwrap();
+
+    let completions = slash::complete(
+        "quarto-run-cell",
+        dir.path(),
+        &strings(&["analysis/model.qmd#"]),
+    );
+    assert_eq!(
+        completions,
+        [Completion {
+            label: "fit".to_string(),
+            new_text: "analysis/model.qmd#fit".to_string(),
+            run_command: true,
+        }]
+    );
+    assert!(slash::complete("quarto-run-cell", dir.path(), &strings(&["model.qmd#"])).is_empty());
+}
@@ -0,0 +1,430 @@
+//! Quarto documents assembled from fuzzer bytes, and the robustness checks run
+//! on them by the cargo-fuzz targets in `fuzz/` and by `tests/fuzz.rs`.
+//!
+//! The bytes pick a sequence of blocks built from the constructs the grammar
+//! tests cover, weighted towards inputs that have mis-parsed before:
+//! unterminated `$` math, deep `:::` nesting and `***` runs. Decoding is
+//! stable, so a saved crasher keeps producing the same document: give new
+//! constructs an unused selector value instead of renumbering the others.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use tree_sitter::{ParseOptions, Parser, Tree};
+
+/// How long one document may take to parse (and highlight) before the check
+/// fails. Generated documents are a few kilobytes and parse in milliseconds.
+pub const TIME_BOUND: Duration = Duration::from_secs(2);
+
+/// Nodes for fenced regions; each must lie within a pair of delimiters the
+/// generator wrote.
+pub const FENCED_KINDS: [&str; 3] = [
+    "executable_code_cell",
+    "fenced_code_block",
+    "yaml_front_matter",
+];
+
+/// A generated document and the byte ranges of its closed fenced regions,
+/// opening delimiter through closing delimiter line.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub source: String,
+    pub fenced: Vec<Range<usize>>,
+}
+
+/// Reads choices from fuzzer bytes; zeros once they run out.
+struct Bytes<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Bytes<'_> {
+    fn next(&mut self) -> u8 {
+        let byte = self.data.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.next() as usize % n
+    }
+
+    fn chance(&mut self) -> bool {
+        self.next() % 2 == 1
+    }
+
+    fn pick<'s>(&mut self, items: &[&'s str]) -> &'s str {
+        items[self.below(items.len())]
+    }
+}
+
+const WORDS: [&str; 16] = [
+    "Quarto",
+    "results",
+    "the",
+    "model",
+    "x",
+    "2024",
+    "naïve",
+    "日本語",
+    "🎉",
+    "a_b",
+    "50%",
+    "—",
+    "\\*",
+    "\\$",
+    "<b>",
+    "&amp;",
+];
+
+const PUNCTUATION: [&str; 12] = ["*", "_", "$", "`", "[", "]", "{", "}", "|", "^", "~", "@"];
+
+const LANGUAGES: [&str; 7] = ["python", "r", "julia", "ojs", "bash", "sql", "mermaid"];
+
+/// Code and YAML lines; none of them can close a fence.
+const CODE: [&str; 8] = [
+    "x = 1",
+    "print(\"$5 * 3\")",
+    "# comment with `ticks`",
+    "summary(df) |> head()",
+    "    indented()",
+    "title: \"Report\"",
+    "- item: [1, 2]",
+    "",
+];
+
+impl Document {
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut bytes = Bytes { data, position: 0 };
+        let mut document = Document {
+            source: String::new(),
+            fenced: Vec::new(),
+        };
+        if bytes.below(4) == 0 {
+            document.front_matter(&mut bytes);
+        }
+        while !bytes.is_empty() {
+            document.block(&mut bytes);
+            document.source.push('\n');
+        }
+        document
+    }
+
+    /// Appends a fenced region: the opening line, `lines`, the closing line.
+    fn fence(&mut self, open: &str, lines: &[&str], close: &str) {
+        let start = self.source.len();
+        self.source.push_str(open);
+        self.source.push('\n');
+        for line in lines {
+            self.source.push_str(line);
+            self.source.push('\n');
+        }
+        self.source.push_str(close);
+        self.source.push('\n');
+        self.fenced.push(start..self.source.len());
+    }
+
+    fn code_lines<'s>(bytes: &mut Bytes, pool: &[&'s str]) -> Vec<&'s str> {
+        (0..bytes.below(6)).map(|_| bytes.pick(pool)).collect()
+    }
+
+    fn front_matter(&mut self, bytes: &mut Bytes) {
+        let lines = Self::code_lines(bytes, &CODE[5..]);
+        self.fence("---", &lines, "---");
+        self.source.push('\n');
+    }
+
+    fn block(&mut self, bytes: &mut Bytes) {
+        match bytes.below(32) {
+            1 => {
+                let level = 1 + bytes.below(6);
+                self.source.push_str(&"#".repeat(level));
+                self.source.push(' ');
+                self.inlines(bytes, 4);
+                if bytes.chance() {
+                    self.source.push_str(" {#sec-id .unnumbered key=\"value\"}");
+                }
+                self.source.push('\n');
+            }
+            2 => {
+                let fence = "`".repeat(3 + bytes.below(3));
+                let language = bytes.pick(&LANGUAGES);
+                let mut lines = Vec::new();
+                if bytes.chance() {
+                    lines.push("#| label: fig-plot");
+                    lines.push("#| echo: false");
+                }
+                lines.extend(Self::code_lines(bytes, &CODE));
+                self.fence(&format!("{fence}{{{language}}}"), &lines, &fence);
+            }
+            3 => {
+                let fence = bytes.pick(&["```", "~~~", "````"]);
+                let info = bytes.pick(&["", "python", "{.r}", "{.bash code-line-numbers=\"2\"}"]);
+                let lines = Self::code_lines(bytes, &CODE);
+                self.fence(&format!("{fence}{info}"), &lines, fence);
+            }
+            4 => {
+                self.source.push_str("$$\n");
+                self.source
+                    .push_str(bytes.pick(&["x^2", "\\frac{a}{b}", "a $ b", ""]));
+                self.source.push('\n');
+                if bytes.chance() {
+                    self.source
+                        .push_str(bytes.pick(&["$$\n", "$$ {#eq-model}\n"]));
+                }
+            }
+            5 => {
+                let depth = 1 + bytes.below(16);
+                let widen = bytes.chance();
+                let colons = |level: usize| ":".repeat(if widen { 3 + depth - level } else { 3 });
+                for level in 0..depth {
+                    self.source
+                        .push_str(&format!("{} {{.level-{level}}}\n", colons(level)));
+                }
+                self.inlines(bytes, 3);
+                self.source.push('\n');
+                for level in (0..depth).rev() {
+                    if bytes.below(4) != 0 {
+                        self.source.push_str(&colons(level));
+                        self.source.push('\n');
+                    }
+                }
+            }
+            6 => {
+                let kind = bytes.pick(&["note", "warning", "tip"]);
+                self.source.push_str(&format!(
+                    "::: {{.callout-{kind} collapse=\"true\"}}\n## Title\n\n"
+                ));
+                self.inlines(bytes, 6);
+                self.source.push_str("\n:::\n");
+            }
+            7 => {
+                self.source.push_str("| Item | Cost |\n|------|-----:|\n");
+                for _ in 0..1 + bytes.below(4) {
+                    self.source.push_str("| ");
+                    self.inlines(bytes, 2);
+                    self.source.push_str(bytes.pick(&[
+                        " | $5 |\n",
+                        " | $1,650 |\n",
+                        " | 12 % |\n",
+                    ]));
+                }
+            }
+            8 => {
+                for number in 1..=1 + bytes.below(4) {
+                    let marker = if bytes.chance() {
+                        format!("{number}.")
+                    } else {
+                        "-".to_string()
+                    };
+                    self.source.push_str(&marker);
+                    self.source.push(' ');
+                    self.inlines(bytes, 4);
+                    self.source.push('\n');
+                }
+            }
+            9 => {
+                self.source.push_str("> ");
+                self.inlines(bytes, 6);
+                self.source.push('\n');
+            }
+            10 => {
+                self.source.push_str(bytes.pick(&[
+                    "{{< include _setup.qmd >}}\n",
+                    "{{< pagebreak >}}\n",
+                    "{{< video https://example.org/v.mp4 >}}\n",
+                ]));
+            }
+            11 => {
+                self.source.push_str(&format!("[^{}]: ", bytes.below(4)));
+                self.inlines(bytes, 4);
+                self.source.push('\n');
+            }
+            12 => {
+                let lines = Self::code_lines(bytes, &["<div>", "<b>raw</b>", "</div>"]);
+                self.fence("```{=html}", &lines, "```");
+            }
+            13 => {
+                // Not `---`: a pair of them around text could read as a YAML block
+                self.source
+                    .push_str(bytes.pick(&["***\n", "* * *\n", "___\n"]));
+            }
+            14 => {
+                self.source.push_str(&"\n".repeat(bytes.below(4)));
+            }
+            15 => {
+                self.source.push_str("<div class=\"note\">\n");
+                self.inlines(bytes, 3);
+                self.source.push_str("\n</div>\n");
+            }
+            _ => {
+                self.inlines(bytes, 10);
+                if bytes.chance() {
+                    self.source.push('\n');
+                    self.inlines(bytes, 6);
+                }
+                self.source.push('\n');
+            }
+        }
+    }
+
+    /// Up to `most` inline constructs separated by spaces.
+    fn inlines(&mut self, bytes: &mut Bytes, most: usize) {
+        for index in 0..1 + bytes.below(most) {
+            if index > 0 {
+                self.source.push(' ');
+            }
+            self.inline(bytes);
+        }
+    }
+
+    fn inline(&mut self, bytes: &mut Bytes) {
+        let word = bytes.pick(&WORDS);
+        let text = match bytes.below(16) {
+            1 => format!("*{word}*"),
+            2 => format!("**{word}**"),
+            3 => {
+                let open = "*".repeat(1 + bytes.below(9));
+                let close = "*".repeat(bytes.below(10));
+                match bytes.below(3) {
+                    0 => format!("{open}{word}{close}"),
+                    1 => format!("{open} {word}"),
+                    _ => open,
+                }
+            }
+            4 => match bytes.below(3) {
+                0 => format!("${word}^2$"),
+                1 => format!("$\\alpha + {word}"),
+                _ => "$".to_string(),
+            },
+            5 => bytes
+                .pick(&["$5", "$1,650", "costs $3 and $4", "US$ 10"])
+                .to_string(),
+            6 => bytes
+                .pick(&[
+                    "@smith2024",
+                    "[@jones2023; @brown2022, p. 4]",
+                    "[-@doe]",
+                    "@",
+                ])
+                .to_string(),
+            7 => bytes
+                .pick(&["@fig-plot", "@tbl-results", "@sec-intro"])
+                .to_string(),
+            8 => match bytes.below(3) {
+                0 => format!("`{word}`"),
+                1 => format!("``{word} ` {word}``"),
+                _ => "`".to_string(),
+            },
+            9 => bytes
+                .pick(&["`{python} 1 + 1`", "`r nrow(df)`", "`{r} mean(x)`"])
+                .to_string(),
+            10 => match bytes.below(3) {
+                0 => format!("[{word}](https://example.org/{word})"),
+                1 => "<https://quarto.org>".to_string(),
+                _ => format!("[{word}]"),
+            },
+            11 => match bytes.below(3) {
+                0 => format!("[^{}]", bytes.below(4)),
+                1 => format!("^[{word}]"),
+                _ => "^[".to_string(),
+            },
+            12 => bytes
+                .pick(&["{{< meta title >}}", "{{< var version >}}", "{{<"])
+                .to_string(),
+            13 => match bytes.below(4) {
+                0 => format!("~~{word}~~"),
+                1 => "H~2~O".to_string(),
+                2 => "x^2^".to_string(),
+                _ => format!("=={word}=="),
+            },
+            14 => format!("[{word}]{{.smallcaps}}"),
+            15 => bytes.pick(&PUNCTUATION).to_string(),
+            _ => word.to_string(),
+        };
+        self.source.push_str(&text);
+    }
+}
+
+/// Parses `source` with tree-sitter-quarto, giving up once `bound` has passed.
+///
+/// The bound is checked by the parser's progress callback, so it covers the
+/// parse loop; a hang inside the external scanner is caught by libFuzzer's
+/// `-timeout` and nextest's slow-timeout instead.
+pub fn parse_within(source: &str, bound: Duration) -> Result<Tree, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&super::language())
+        .expect("parser loads language");
+    let started = Instant::now();
+    let mut cancel = |_: &tree_sitter::ParseState| started.elapsed() > bound;
+    let bytes = source.as_bytes();
+    parser
+        .parse_with_options(
+            &mut |offset, _| &bytes[offset.min(bytes.len())..],
+            None,
+            Some(ParseOptions::new().progress_callback(&mut cancel)),
+        )
+        .ok_or_else(|| {
+            format!(
+                "parsing {} bytes took longer than {:?}",
+                source.len(),
+                bound
+            )
+        })
+}
+
+/// Parses and highlights `source` within `TIME_BOUND`. Panics from the
+/// highlighter propagate; a slow parse is an error.
+pub fn check_terminates(source: &str) -> Result<Tree, String> {
+    let started = Instant::now();
+    let tree = parse_within(source, TIME_BOUND)?;
+    super::highlight(source);
+    if started.elapsed() > TIME_BOUND {
+        return Err(format!(
+            "parsing and highlighting {} bytes took {:?}, over {:?}",
+            source.len(),
+            started.elapsed(),
+            TIME_BOUND
+        ));
+    }
+    Ok(tree)
+}
+
+/// `check_terminates`, plus: every fenced node lies within one of the
+/// document's closed fenced regions, so no code cell or front matter runs past
+/// its closing delimiter into the text around it.
+pub fn check(document: &Document) -> Result<(), String> {
+    let tree = check_terminates(&document.source)?;
+    let parsed = super::Parsed {
+        source: document.source.clone(),
+        tree,
+    };
+    for kind in FENCED_KINDS {
+        for node in parsed.find_all(kind) {
+            let range = node.byte_range();
+            // Blank lines after the closing delimiter may belong to the node
+            let inside = document.fenced.iter().any(|fenced| {
+                fenced.start <= range.start
+                    && (range.end <= fenced.end
+                        || document.source[fenced.end..range.end].trim().is_empty())
+            });
+            if !inside {
+                let start = node.start_position();
+                return Err(format!(
+                    "{kind} at {}:{} covers {:?}, outside the fenced regions {:?}\nTree:\n{}",
+                    start.row + 1,
+                    start.column + 1,
+                    parsed.text(node),
+                    document.fenced,
+                    parsed.sexp()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Shared helpers for the grammar and highlighting tests, also used by
+//! `examples/inspect-highlights.rs`.
+//!
+//! Add `mod support;` to a test file to get the tree-sitter-quarto language,
+//! the extension's own queries from `languages/quarto/`, and a small fluent
+//! API over both:
+//!
+//! ```ignore
+//! support::assert_highlights("**bold**", [("bold", "emphasis.strong")]);
+//! let parsed = support::parse("See @smith2024.").assert_no_errors();
+//! assert_eq!(parsed.texts("citation"), ["@smith2024"]);
+//! ```
+#![allow(dead_code)]
+
+pub mod fuzz;
+
+use std::ops::Range;
+
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
+
+#[link(name = "tree-sitter-quarto", kind = "static")]
+extern "C" {
+    fn tree_sitter_quarto() -> Language;
+}
+
+pub const HIGHLIGHTS: &str = include_str!("../../languages/quarto/highlights.scm");
+pub const INJECTIONS: &str = include_str!("../../languages/quarto/injections.scm");
+
+/// Injected languages the highlighter resolves to tree-sitter-quarto itself.
+/// Other injections (python, r, yaml, …) have no grammar in the native tests;
+/// `Parsed::injections` still reports them.
+const SELF_INJECTIONS: [&str; 2] = ["quarto", "markdown"];
+
+pub fn language() -> Language {
+    unsafe { tree_sitter_quarto() }
+}
+
+/// The extension's highlight and injection queries, recognizing every
+/// capture name they use.
+pub fn highlight_configuration() -> HighlightConfiguration {
+    let mut config = HighlightConfiguration::new(language(), "quarto", HIGHLIGHTS, INJECTIONS, "")
+        .expect("valid highlight configuration");
+    let names: Vec<String> = config.names().iter().map(|name| name.to_string()).collect();
+    config.configure(&names);
+    config
+}
+
+/// A parsed document and its source.
+pub struct Parsed {
+    pub source: String,
+    pub tree: Tree,
+}
+
+pub fn parse(source: &str) -> Parsed {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language())
+        .expect("parser loads language");
+    let tree = parser.parse(source, None).expect("parse succeeds");
+    Parsed {
+        source: source.to_string(),
+        tree,
+    }
+}
+
+/// A region of the document handed to another language by `injections.scm`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injection {
+    pub language: String,
+    pub text: String,
+    pub range: Range<usize>,
+}
+
+impl Parsed {
+    pub fn root(&self) -> Node<'_> {
+        self.tree.root_node()
+    }
+
+    pub fn sexp(&self) -> String {
+        self.root().to_sexp()
+    }
+
+    pub fn text(&self, node: Node) -> &str {
+        &self.source[node.byte_range()]
+    }
+
+    /// Every node of `kind`, in document order.
+    pub fn find_all(&self, kind: &str) -> Vec<Node<'_>> {
+        let mut found = Vec::new();
+        walk(self.root(), &mut |node| {
+            if node.kind() == kind {
+                found.push(node);
+            }
+        });
+        found
+    }
+
+    /// The source text of every node of `kind`.
+    pub fn texts(&self, kind: &str) -> Vec<&str> {
+        self.find_all(kind)
+            .into_iter()
+            .map(|node| self.text(node))
+            .collect()
+    }
+
+    pub fn has(&self, kind: &str) -> bool {
+        !self.find_all(kind).is_empty()
+    }
+
+    /// Whether some `parent` node has a `child` node anywhere below it.
+    pub fn has_within(&self, parent: &str, child: &str) -> bool {
+        self.find_all(parent).into_iter().any(|node| {
+            let mut found = false;
+            walk(node, &mut |inner| {
+                found |= inner != node && inner.kind() == child
+            });
+            found
+        })
+    }
+
+    /// `ERROR` and missing nodes, as `kind at row:column` for messages.
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        walk(self.root(), &mut |node| {
+            if node.is_error() || node.is_missing() {
+                let start = node.start_position();
+                errors.push(format!(
+                    "{} at {}:{}",
+                    node.kind(),
+                    start.row + 1,
+                    start.column + 1
+                ));
+            }
+        });
+        errors
+    }
+
+    pub fn assert_no_errors(self) -> Self {
+        let errors = self.errors();
+        assert!(
+            errors.is_empty() && !self.root().has_error(),
+            "parse errors ({}) in:\n{}\nTree:\n{}",
+            errors.join(", "),
+            self.source,
+            self.sexp()
+        );
+        self
+    }
+
+    /// The regions `injections.scm` hands to other languages, with the
+    /// language each one resolves to, in document order.
+    pub fn injections(&self) -> Vec<Injection> {
+        let query = Query::new(&language(), INJECTIONS).expect("valid injections query");
+        let content = query.capture_index_for_name("injection.content");
+        let language_capture = query.capture_index_for_name("injection.language");
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&query, self.root(), self.source.as_bytes());
+        let mut found = Vec::new();
+
+        while let Some(found_match) = matches.next() {
+            let set_language = query
+                .property_settings(found_match.pattern_index)
+                .iter()
+                .find(|property| &*property.key == "injection.language")
+                .and_then(|property| property.value.as_deref().map(str::to_string));
+            let captured_language = found_match
+                .captures
+                .iter()
+                .find(|capture| Some(capture.index) == language_capture)
+                .map(|capture| self.text(capture.node).to_string());
+            let Some(language) = set_language.or(captured_language) else {
+                continue;
+            };
+            for capture in found_match
+                .captures
+                .iter()
+                .filter(|capture| Some(capture.index) == content)
+            {
+                found.push(Injection {
+                    language: language.clone(),
+                    text: self.text(capture.node).to_string(),
+                    range: capture.node.byte_range(),
+                });
+            }
+        }
+        found.sort_by_key(|injection| injection.range.start);
+        found
+    }
+}
+
+fn walk<'tree>(node: Node<'tree>, visit: &mut impl FnMut(Node<'tree>)) {
+    visit(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+/// A run of source text and the captures active over it, outermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub text: String,
+    pub scopes: Vec<String>,
+}
+
+/// The document as `tree-sitter-highlight` sees it.
+pub struct Highlights {
+    pub source: String,
+    pub spans: Vec<Span>,
+}
+
+pub fn highlight(source: &str) -> Highlights {
+    let config = highlight_configuration();
+    let mut highlighter = Highlighter::new();
+    let events = highlighter
+        .highlight(&config, source.as_bytes(), None, |name| {
+            SELF_INJECTIONS.contains(&name).then_some(&config)
+        })
+        .expect("highlighting succeeds");
+
+    let mut stack: Vec<String> = Vec::new();
+    let mut spans = Vec::new();
+    for event in events {
+        match event.expect("valid event") {
+            HighlightEvent::HighlightStart(highlight) => {
+                stack.push(config.names()[highlight.0].to_string());
+            }
+            HighlightEvent::HighlightEnd => {
+                stack.pop();
+            }
+            HighlightEvent::Source { start, end } => spans.push(Span {
+                range: start..end,
+                text: source[start..end].to_string(),
+                scopes: stack.clone(),
+            }),
+        }
+    }
+    Highlights {
+        source: source.to_string(),
+        spans,
+    }
+}
+
+impl Highlights {
+    /// The scopes covering all of the first occurrence of `text`.
+    pub fn scopes_of(&self, text: &str) -> Vec<&str> {
+        let Some(start) = self.source.find(text) else {
+            return Vec::new();
+        };
+        self.scopes_over(start..start + text.len())
+    }
+
+    /// Whether some occurrence of `text` is covered by `scope` throughout.
+    pub fn has(&self, text: &str, scope: &str) -> bool {
+        self.source
+            .match_indices(text)
+            .any(|(start, _)| self.scopes_over(start..start + text.len()).contains(&scope))
+    }
+
+    fn scopes_over(&self, range: Range<usize>) -> Vec<&str> {
+        let mut covering = self
+            .spans
+            .iter()
+            .filter(|span| span.range.start < range.end && range.start < span.range.end);
+        let Some(first) = covering.next() else {
+            return Vec::new();
+        };
+        let mut scopes: Vec<&str> = first.scopes.iter().map(String::as_str).collect();
+        for span in covering {
+            scopes.retain(|scope| span.scopes.iter().any(|other| other == scope));
+        }
+        scopes
+    }
+
+    /// One line per highlighted span: `line:column-line:column`, the scopes
+    /// outermost first, and the quoted source text. Unhighlighted text is
+    /// left out. Lines and columns count from 1, columns in characters.
+    pub fn snapshot(&self) -> String {
+        let mut snapshot = String::new();
+        for span in self.spans.iter().filter(|span| !span.scopes.is_empty()) {
+            let (start_line, start_column) = self.line_column(span.range.start);
+            let (end_line, end_column) = self.line_column(span.range.end);
+            snapshot.push_str(&format!(
+                "{start_line}:{start_column}-{end_line}:{end_column} {} {:?}\n",
+                span.scopes.join(" > "),
+                span.text
+            ));
+        }
+        snapshot
+    }
+
+    fn line_column(&self, offset: usize) -> (usize, usize) {
+        let before = &self.source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    /// `<scope>text</>` markup, the format older tests print for debugging.
+    pub fn rendered(&self) -> String {
+        let mut rendered = String::new();
+        let mut open: Vec<&str> = Vec::new();
+        for span in &self.spans {
+            let common = open
+                .iter()
+                .zip(&span.scopes)
+                .take_while(|(open, scope)| **open == scope.as_str())
+                .count();
+            for _ in common..open.len() {
+                rendered.push_str("</>");
+            }
+            open.truncate(common);
+            for scope in &span.scopes[common..] {
+                rendered.push_str(&format!("<{}>", scope));
+                open.push(scope);
+            }
+            rendered.push_str(&span.text);
+        }
+        for _ in &open {
+            rendered.push_str("</>");
+        }
+        rendered
+    }
+}
+
+/// Asserts that each `(text, scope)` pair holds: some occurrence of `text` in
+/// `source` is highlighted with `scope` throughout.
+pub fn assert_highlights<'a>(source: &str, expected: impl IntoIterator<Item = (&'a str, &'a str)>) {
+    let highlights = highlight(source);
+    for (text, scope) in expected {
+        assert!(
+            highlights.source.contains(text),
+            "{text:?} does not occur in:\n{source}"
+        );
+        assert!(
+            highlights.has(text, scope),
+            "expected {text:?} to be highlighted as @{scope}, got {:?}\nRendered:\n{}",
+            highlights.scopes_of(text),
+            highlights.rendered()
+        );
+    }
+}
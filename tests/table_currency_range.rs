@@ -1,14 +1,7 @@
-use tree_sitter::{Language, Parser};
+mod support;
 
-// External linkage to tree-sitter-quarto grammar
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
-
-fn language() -> Language {
-    unsafe { tree_sitter_quarto() }
-}
+use support::language;
+use tree_sitter::Parser;
 
 #[test]
 fn test_table_with_currency_range() {
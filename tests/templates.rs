@@ -0,0 +1,176 @@
+use std::fs;
+
+use quarto_zed::cells;
+use quarto_zed::templates::{self, CustomTemplate, Engine, BUILTIN_TEMPLATES, ENGINES};
+use quarto_zed::xrefs;
+
+fn generate(template: &str, engine: &str) -> Vec<templates::GeneratedFile> {
+    templates::builtin(template, "fuel-economy", Engine::named(engine).unwrap())
+        .unwrap_or_else(|e| panic!("{template}: {e}"))
+}
+
+#[test]
+fn names_become_titles() {
+    assert_eq!(templates::title_from_name("fuel-economy"), "Fuel Economy");
+    assert_eq!(
+        templates::title_from_name("my_first analysis"),
+        "My First Analysis"
+    );
+    assert_eq!(templates::title_from_name("chapters/intro"), "Intro");
+}
+
+#[test]
+fn every_template_has_cells_in_the_chosen_engine() {
+    for (template, _) in BUILTIN_TEMPLATES {
+        for engine in ENGINES {
+            let files = generate(template, engine);
+            let document = files
+                .iter()
+                .find(|file| file.path.ends_with(".qmd"))
+                .unwrap_or_else(|| panic!("{template} produces a document"));
+
+            let cells = cells::cells(&document.contents);
+            assert!(!cells.is_empty(), "{template}/{engine} has example cells");
+            assert!(
+                cells.iter().all(|cell| cell.language == engine),
+                "{template}/{engine} cells are all {engine}"
+            );
+            assert!(
+                cells.iter().all(|cell| cell.close_line.is_some()),
+                "{template}/{engine} closes every cell"
+            );
+
+            let table = xrefs::table(&[(document.path.clone(), document.contents.clone())]);
+            assert!(
+                table.dangling.is_empty(),
+                "{template}/{engine} references only its own labels: {:?}",
+                table.dangling
+            );
+        }
+    }
+}
+
+#[test]
+fn documents_have_front_matter_for_their_format() {
+    let article = &generate("article", "python")[0];
+    assert_eq!(article.path, "fuel-economy.qmd");
+    assert!(article
+        .contents
+        .starts_with("---\ntitle: \"Fuel Economy\"\n"));
+    assert!(article.contents.contains("jupyter: python3\n"));
+    assert!(article.contents.contains("#| label: fig-fuel-economy\n"));
+
+    let slides = &generate("presentation", "r")[0];
+    assert!(slides.contents.contains("  revealjs:\n"));
+    assert!(slides.contents.contains("engine: knitr\n"));
+
+    let dashboard = &generate("dashboard", "julia")[0];
+    assert!(dashboard.contents.contains("format: dashboard\n"));
+    assert!(dashboard.contents.contains("#| content: valuebox\n"));
+
+    let chapter = &generate("book-chapter", "python")[0];
+    assert!(
+        chapter
+            .contents
+            .starts_with("# Fuel Economy {#sec-fuel-economy}\n"),
+        "chapters start with their section heading"
+    );
+
+    let page = &generate("website-page", "r")[0];
+    assert!(page
+        .contents
+        .starts_with("---\ntitle: \"Fuel Economy\"\ndescription:"));
+}
+
+#[test]
+fn manuscript_is_a_project() {
+    let files = generate("manuscript", "r");
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "fuel-economy/_quarto.yml",
+            "fuel-economy/index.qmd",
+            "fuel-economy/references.bib"
+        ]
+    );
+    assert!(files[0]
+        .contents
+        .starts_with("project:\n  type: manuscript\n"));
+    assert!(files[1].contents.contains("bibliography: references.bib\n"));
+    assert!(files[1].contents.contains("[@example2024]"));
+    assert!(files[2].contents.contains("@misc{example2024,"));
+}
+
+#[test]
+fn unknown_templates_and_engines_are_errors() {
+    let error = templates::builtin("poster", "x", Engine::named("r").unwrap()).unwrap_err();
+    assert!(error.contains("article, presentation"), "{error}");
+    assert!(Engine::named("ocaml")
+        .unwrap_err()
+        .contains("python, r, julia"));
+    assert_eq!(Engine::named("R").unwrap().language, "r");
+}
+
+#[test]
+fn custom_templates_come_from_the_worktree() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates_dir = dir.path().join(templates::CUSTOM_TEMPLATE_DIR);
+    fs::create_dir_all(templates_dir.join("lab-report/data")).unwrap();
+    fs::write(
+        templates_dir.join("memo.qmd"),
+        "---\ntitle: \"{{title}}\"\n---\n\n```{{{engine}}}\n```\n",
+    )
+    .unwrap();
+    fs::write(
+        templates_dir.join("lab-report/{{name}}.qmd"),
+        "# {{title}}\n",
+    )
+    .unwrap();
+    fs::write(
+        templates_dir.join("lab-report/data/README.md"),
+        "Raw data\n",
+    )
+    .unwrap();
+    fs::write(templates_dir.join("notes.txt"), "not a template").unwrap();
+
+    assert_eq!(
+        templates::custom_template_names(dir.path()),
+        ["lab-report", "memo"]
+    );
+    assert!(templates::load_custom(dir.path(), "article").is_none());
+
+    let memo = templates::load_custom(dir.path(), "memo").unwrap();
+    let files = templates::custom(&memo, "weekly-memo", Engine::named("r").unwrap());
+    assert_eq!(files[0].path, "weekly-memo.qmd");
+    assert_eq!(
+        files[0].contents,
+        "---\ntitle: \"Weekly Memo\"\n---\n\n```{r}\n```\n"
+    );
+
+    let report = templates::load_custom(dir.path(), "lab-report").unwrap();
+    assert!(matches!(report, CustomTemplate::Project(_)));
+    let files = templates::custom(&report, "week-1", Engine::named("python").unwrap());
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    assert_eq!(paths, ["week-1/data/README.md", "week-1/week-1.qmd"]);
+    assert_eq!(files[1].contents, "# Week 1\n");
+}
+
+#[test]
+fn output_has_a_section_per_file() {
+    let files = generate("manuscript", "python");
+    let (text, sections) = templates::render(&files);
+
+    assert_eq!(sections.len(), 3);
+    assert_eq!(sections[0].1, "fuel-economy/_quarto.yml");
+    let config = &text[sections[0].0.clone()];
+    assert!(config.starts_with("fuel-economy/_quarto.yml:\n\n```yaml\nproject:\n"));
+    assert!(config.ends_with("```\n"));
+
+    let article = &text[sections[1].0.clone()];
+    assert!(
+        article.contains("\n````qmd\n---\n"),
+        "the fence grows past the cells' backticks"
+    );
+    assert!(text[sections[2].0.clone()].contains("```bibtex\n"));
+}
@@ -1,16 +1,14 @@
-use tree_sitter::{Language, Parser};
-use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
+mod support;
 
-#[link(name = "tree-sitter-quarto", kind = "static")]
-extern "C" {
-    fn tree_sitter_quarto() -> Language;
-}
+use support::language;
+use tree_sitter::Parser;
+use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
 #[test]
 fn test_real_document_link() {
     let source = "Links should work: [link text](https://example.com)\n";
 
-    let language = unsafe { tree_sitter_quarto() };
+    let language = language();
     let mut parser = Parser::new();
     parser.set_language(&language).unwrap();
     let tree = parser.parse(source, None).unwrap();
@@ -0,0 +1,57 @@
+//! Checks for the shared helpers in `tests/support/`, so a broken harness
+//! fails here rather than as confusing failures across the suite.
+mod support;
+
+use support::{assert_highlights, highlight, parse};
+
+#[test]
+fn highlights_are_asserted_by_text_and_scope() {
+    assert_highlights("**bold**", [("bold", "emphasis.strong")]);
+
+    let highlights = highlight("Some **bold** text.");
+    assert!(highlights.has("bold", "emphasis.strong"));
+    assert!(!highlights.has("Some", "emphasis.strong"));
+    assert!(
+        highlights.rendered().contains("bold</>"),
+        "rendered markup closes the bold scope:\n{}",
+        highlights.rendered()
+    );
+}
+
+#[test]
+#[should_panic(expected = "to be highlighted as @emphasis.strong")]
+fn missing_highlights_fail_with_the_rendered_document() {
+    assert_highlights("*italic*", [("italic", "emphasis.strong")]);
+}
+
+#[test]
+fn nodes_are_found_in_document_order() {
+    let parsed = parse("See @smith2024 and [@jones2023].\n").assert_no_errors();
+    let citations = parsed.texts("citation");
+    assert_eq!(citations.len(), 2, "Tree:\n{}", parsed.sexp());
+    assert!(citations[0].contains("smith2024"));
+    assert!(citations[1].contains("jones2023"));
+    assert!(parsed.has_within("paragraph", "citation"));
+    assert!(!parsed.has("atx_heading"));
+}
+
+#[test]
+fn injected_languages_are_resolved() {
+    let source = "---\ntitle: Demo\n---\n\n```{python}\nprint(1)\n```\n\n```{r}\nsummary(x)\n```\n";
+    let injections = parse(source).assert_no_errors().injections();
+    let languages: Vec<&str> = injections
+        .iter()
+        .map(|injection| injection.language.as_str())
+        .collect();
+    assert!(
+        languages.contains(&"python") && languages.contains(&"r"),
+        "expected python and r injections, got {languages:?}"
+    );
+
+    let python = injections
+        .iter()
+        .find(|injection| injection.language == "python")
+        .unwrap();
+    assert!(python.text.contains("print(1)"));
+    assert_eq!(&source[python.range.clone()], python.text);
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use quarto_zed::themes::{self, Resolution};
+
+const HIGHLIGHTS: &str = include_str!("../languages/quarto/highlights.scm");
+
+/// Themes the maintainers use day to day; every capture must be styled in each.
+const TEAM_THEMES: [&str; 4] = ["One Dark", "One Light", "Ayu Dark", "Gruvbox Dark"];
+
+const FAMILY: &str = r##"{
+  "name": "Sample",
+  "themes": [
+    {
+      "name": "Sample Dark",
+      "appearance": "dark",
+      "style": {
+        "syntax": {
+          "title": { "color": "#61afef", "font_style": null, "font_weight": 700 },
+          "emphasis": { "color": "#74ade8", "font_style": "italic", "font_weight": null },
+          "emphasis.strong": { "color": "#bf956a", "font_style": null, "font_weight": 700 },
+          "punctuation": { "color": "#acb2be", "font_style": null, "font_weight": null },
+          "link_uri": { "color": null, "font_style": null, "font_weight": null }
+        }
+      }
+    },
+    { "name": "Sample Light", "appearance": "light", "style": {} }
+  ]
+}"##;
+
+#[test]
+fn captures_resolve_like_zed() {
+    let family = themes::parse_family(FAMILY).expect("valid theme family");
+    assert_eq!(family.len(), 2);
+    let dark = &family[0];
+    assert_eq!(
+        (dark.name.as_str(), dark.appearance.as_str()),
+        ("Sample Dark", "dark")
+    );
+
+    assert_eq!(dark.resolve("emphasis.strong"), Resolution::Styled);
+    assert_eq!(
+        dark.resolve("text.title"),
+        Resolution::Fallback("title".to_string())
+    );
+    assert_eq!(
+        dark.resolve("punctuation.special"),
+        Resolution::Fallback("punctuation".to_string())
+    );
+    // Key parts may appear anywhere in the capture, not only as a prefix
+    assert_eq!(dark.key_for("strong.emphasis"), Some("emphasis.strong"));
+    assert_eq!(
+        dark.resolve("link_uri"),
+        Resolution::Unstyled(Some("link_uri".to_string()))
+    );
+    assert_eq!(dark.resolve("text.literal"), Resolution::Unstyled(None));
+    assert!(!family[1].resolve("title").is_styled());
+}
+
+#[test]
+fn captures_skip_comments_strings_and_private_names() {
+    let query = r#"
+; @commented.out
+((text) @_name (#eq? @_name "@not.a.capture")) @string.special
+(emphasis) @emphasis
+(strong_emphasis) @emphasis.strong @emphasis
+"#;
+    assert_eq!(
+        themes::captures(query),
+        ["string.special", "emphasis", "emphasis.strong"]
+    );
+
+    let shipped = themes::captures(HIGHLIGHTS);
+    assert!(shipped.iter().any(|capture| capture == "text.title"));
+    assert!(
+        shipped
+            .iter()
+            .all(|capture| !capture.starts_with("markup.")),
+        "@markup.* only appears in highlights.scm comments: {:?}",
+        shipped
+    );
+}
+
+#[test]
+fn team_themes_style_every_capture() {
+    // Set ZED_THEMES_DIR to assets/themes in a Zed checkout to run this
+    let Some(dir) = std::env::var_os("ZED_THEMES_DIR") else {
+        eprintln!("skipping: ZED_THEMES_DIR not set");
+        return;
+    };
+
+    let mut team = Vec::new();
+    for entry in walkdir::WalkDir::new(Path::new(&dir))
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+    {
+        let json = std::fs::read_to_string(entry.path()).expect("readable theme file");
+        if let Ok(family) = themes::parse_family(&json) {
+            team.extend(
+                family
+                    .into_iter()
+                    .filter(|theme| TEAM_THEMES.contains(&theme.name.as_str())),
+            );
+        }
+    }
+    let found: Vec<&str> = team.iter().map(|theme| theme.name.as_str()).collect();
+    for name in TEAM_THEMES {
+        assert!(found.contains(&name), "{} not found in {:?}", name, dir);
+    }
+
+    let mut plain = Vec::new();
+    for theme in &team {
+        for capture in themes::captures(HIGHLIGHTS) {
+            if !theme.resolve(&capture).is_styled() {
+                plain.push(format!("{}: @{}", theme.name, capture));
+            }
+        }
+    }
+    assert!(
+        plain.is_empty(),
+        "captures that render as plain text (see `cargo run --example theme-coverage`):\n{}",
+        plain.join("\n")
+    );
+}
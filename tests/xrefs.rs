@@ -0,0 +1,163 @@
+use quarto_zed::xrefs::{self, is_xref_label};
+
+const INDEX: &str = include_str!("fixtures/xrefs/index.qmd");
+const METHODS: &str = include_str!("fixtures/xrefs/methods.qmd");
+
+fn project() -> Vec<(String, String)> {
+    vec![
+        ("index.qmd".to_string(), INDEX.to_string()),
+        ("methods.qmd".to_string(), METHODS.to_string()),
+    ]
+}
+
+#[test]
+fn prefixes_identify_cross_reference_labels() {
+    for label in [
+        "fig-mpg",
+        "tbl-x",
+        "sec-intro",
+        "eq-1",
+        "lst-a",
+        "thm-b",
+        "exr-c",
+    ] {
+        assert!(is_xref_label(label), "{label}");
+    }
+    for label in ["setup", "fig", "fig-", "smith2020", "figure-1"] {
+        assert!(!is_xref_label(label), "{label}");
+    }
+}
+
+#[test]
+fn definitions_cover_every_kind() {
+    let labels: Vec<(String, Option<String>)> = xrefs::definitions(INDEX)
+        .into_iter()
+        .map(|(label, _, caption)| (label, caption))
+        .collect();
+    let owned = |label: &str, caption: Option<&str>| (label.to_string(), caption.map(String::from));
+    assert_eq!(
+        labels,
+        [
+            owned("sec-intro", Some("Introduction")),
+            owned("fig-mpg", Some("Fuel economy by weight")),
+            owned("tbl-summary", Some("Summary statistics")),
+            owned("thm-bound", Some("Efficiency bound")),
+            owned("eq-limit", None),
+            owned("fig-engine", Some("Engine layout")),
+        ]
+    );
+
+    let labels: Vec<(String, usize, Option<String>)> = xrefs::definitions(METHODS);
+    assert_eq!(
+        labels,
+        [
+            ("sec-methods".to_string(), 0, Some("Methods".to_string())),
+            ("lst-load".to_string(), 4, Some("Load the data".to_string())),
+            (
+                "lem-helper".to_string(),
+                9,
+                Some("Helper lemma".to_string())
+            ),
+        ],
+        "listings take lst-cap and theorem divs their heading"
+    );
+}
+
+#[test]
+fn references_skip_code_and_email_addresses() {
+    let labels: Vec<String> = xrefs::references(INDEX)
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            "fig-mpg",
+            "tbl-summary",
+            "thm-bound",
+            "sec-methods",
+            "eq-limit"
+        ],
+        "team@fig-lab.org and `@fig-engine` are not references"
+    );
+
+    assert_eq!(
+        xrefs::references(METHODS),
+        [("fig-mpg".to_string(), 2), ("fig-missing".to_string(), 2)],
+        "@Fig-mpg refers to fig-mpg; code blocks are skipped"
+    );
+}
+
+#[test]
+fn table_counts_references_across_files() {
+    let table = xrefs::table(&project());
+
+    let mpg = table
+        .entries
+        .iter()
+        .find(|entry| entry.definition.label == "fig-mpg")
+        .unwrap();
+    assert_eq!(mpg.definition.location.path, "index.qmd");
+    assert_eq!(mpg.definition.location.line, 9);
+    let from: Vec<(&str, usize)> = mpg
+        .references
+        .iter()
+        .map(|location| (location.path.as_str(), location.line))
+        .collect();
+    assert_eq!(from, [("index.qmd", 6), ("methods.qmd", 2)]);
+
+    let unused: Vec<&str> = table
+        .unused()
+        .map(|entry| entry.definition.label.as_str())
+        .collect();
+    assert_eq!(
+        unused,
+        ["sec-intro", "fig-engine", "lst-load", "lem-helper"]
+    );
+
+    let dangling: Vec<&str> = table.dangling.iter().map(|r| r.label.as_str()).collect();
+    assert_eq!(dangling, ["fig-missing"]);
+    assert!(table.duplicates.is_empty());
+}
+
+#[test]
+fn duplicate_labels_are_reported() {
+    let documents = vec![
+        ("a.qmd".to_string(), "# A {#sec-a}\n".to_string()),
+        (
+            "b.qmd".to_string(),
+            "# Also A {#sec-a}\n\nSee @sec-a.\n".to_string(),
+        ),
+    ];
+    let table = xrefs::table(&documents);
+    assert_eq!(table.entries.len(), 1);
+    assert_eq!(table.entries[0].definition.location.path, "a.qmd");
+    assert_eq!(table.duplicates[0].location.path, "b.qmd");
+
+    let text = xrefs::render(&table, true);
+    assert!(
+        text.contains("Duplicate definitions:\n- @sec-a again at b.qmd:1\n"),
+        "{text}"
+    );
+}
+
+#[test]
+fn report_lists_rows_and_problems() {
+    let text = xrefs::render(&xrefs::table(&project()), true);
+    eprintln!("\n=== XREFS ===\n{}", text);
+
+    assert!(text.starts_with("| Label | Defined | Caption | Refs | Referenced from |\n"));
+    assert!(text.contains(
+        "| @fig-mpg | index.qmd:10 | Fuel economy by weight | 2 | index.qmd:7, methods.qmd:3 |\n"
+    ));
+    assert!(text.contains("| @eq-limit | index.qmd:28 |  | 1 | index.qmd:23 |\n"));
+    assert!(text.contains("Unused labels:\n- @sec-intro (defined at index.qmd:5)\n"));
+    assert!(text
+        .contains("Dangling references (no definition found):\n- @fig-missing at methods.qmd:3\n"));
+
+    let single = xrefs::render(&xrefs::table(&project()[..1]), false);
+    assert!(
+        single.contains("| @thm-bound | 22 | Efficiency bound | 1 | 7 |\n"),
+        "a single file shows bare line numbers: {single}"
+    );
+}